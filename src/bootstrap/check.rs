@@ -268,6 +268,10 @@ pub fn compiletest(build: &Build,
         cmd.env("SANITIZER_SUPPORT", "1");
     }
 
+    if build.config.profiler {
+        cmd.env("PROFILER_SUPPORT", "1");
+    }
+
     cmd.arg("--adb-path").arg("adb");
     cmd.arg("--adb-test-dir").arg(ADB_TEST_DIR);
     if target.contains("android") {
@@ -323,7 +327,9 @@ pub fn docs(build: &Build, compiler: &Compiler) {
 ///
 /// The `error_index_generator` tool lives in `src/tools` and is used to
 /// generate a markdown file from the error indexes of the code base which is
-/// then passed to `rustdoc --test`.
+/// then passed to `rustdoc --test`. The tool then checks that each erroneous
+/// example emits exactly the error code it documents, apart from the follow-up
+/// errors it allows for some codes.
 pub fn error_index(build: &Build, compiler: &Compiler) {
     println!("Testing error-index stage{}", compiler.stage);
 
@@ -339,6 +345,15 @@ pub fn error_index(build: &Build, compiler: &Compiler) {
                    .env("CFG_BUILD", &build.config.build));
 
     markdown_test(build, compiler, &output);
+
+    let examples = dir.join("error-index-examples");
+    t!(fs::create_dir_all(&examples));
+    let mut cmd = build.tool_cmd(&Compiler::new(0, compiler.host), "error_index_generator");
+    build.run(cmd.arg("check")
+                 .arg(&examples)
+                 .env("CFG_BUILD", &build.config.build)
+                 .env("RUSTC", build.compiler_path(compiler))
+                 .env("RUSTC_BOOTSTRAP", "1"));
 }
 
 fn markdown_test(build: &Build, compiler: &Compiler, markdown: &Path) {
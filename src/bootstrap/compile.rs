@@ -46,6 +46,12 @@ pub fn std(build: &Build, target: &str, compiler: &Compiler) {
     let mut cargo = build.cargo(compiler, Mode::Libstd, target, "build");
     let mut features = build.std_features();
 
+    // Bare wasm has no unwinder, no backtraces and no jemalloc; its panics
+    // abort and its allocator is alloc_system's.
+    if target.contains("wasm32-unknown-unknown") {
+        features = String::new();
+    }
+
     if let Ok(target) = env::var("MACOSX_STD_DEPLOYMENT_TARGET") {
         cargo.env("MACOSX_DEPLOYMENT_TARGET", target);
     }
@@ -393,6 +399,17 @@ pub fn assemble_rustc(build: &Build, stage: u32, host: &str) {
         let _ = fs::remove_file(&rustdoc_dst);
         copy(&rustdoc_src, &rustdoc_dst);
     }
+
+    // Ship LLD as `rust-lld` next to the other tools of the sysroot, where
+    // rustc looks for linkers, so that it doesn't shadow a system `lld`.
+    if build.config.lld_enabled {
+        let lld = build.llvm_out(host).join("bin").join(exe("lld", host));
+        let dst = sysroot.join("lib/rustlib").join(host).join("bin");
+        t!(fs::create_dir_all(&dst));
+        let dst = dst.join(exe("rust-lld", host));
+        let _ = fs::remove_file(&dst);
+        copy(&lld, &dst);
+    }
 }
 
 /// Link some files into a rustc sysroot.
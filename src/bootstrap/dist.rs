@@ -344,6 +344,13 @@ pub fn rustc(build: &Build, stage: u32, host: &str) {
             }
         }
 
+        // Copy over LLD if we built it
+        if build.config.lld_enabled {
+            let rust_lld = exe("rust-lld", host);
+            let bindir = Path::new("lib/rustlib").join(host).join("bin");
+            install(&src.join(&bindir).join(&rust_lld), &image.join(&bindir), 0o755);
+        }
+
         // Man pages
         t!(fs::create_dir_all(image.join("share/man/man1")));
         cp_r(&build.src.join("man"), &image.join("share/man/man1"));
@@ -550,6 +557,7 @@ pub fn rust_src(build: &Build) {
         "src/liblibc",
         "src/libpanic_abort",
         "src/libpanic_unwind",
+        "src/libprofiler_builtins",
         "src/librand",
         "src/librustc_asan",
         "src/librustc_lsan",
@@ -586,6 +586,9 @@ impl Build {
         if self.config.backtrace {
             features.push_str(" backtrace");
         }
+        if self.config.profiler {
+            features.push_str(" profiler");
+        }
         return features
     }
 
@@ -850,7 +853,7 @@ impl Build {
 
         let mut base = Vec::new();
         if target != self.config.build && !target.contains("msvc") &&
-            !target.contains("emscripten") {
+            !target.contains("emscripten") && !target.contains("wasm32") {
             base.push(format!("-Clinker={}", self.cc(target).display()));
         }
         return base
@@ -30,7 +30,7 @@ use cmake;
 use gcc;
 
 use Build;
-use util;
+use util::{self, exe};
 use build_helper::up_to_date;
 
 /// Compile LLVM for `target`.
@@ -54,8 +54,11 @@ pub fn llvm(build: &Build, target: &str) {
         t!(t!(File::open(&done_stamp)).read_to_string(&mut done_contents));
 
         // If LLVM was already built previously and contents of the rebuild-trigger file
-        // didn't change from the previous build, then no action is required.
-        if done_contents == rebuild_trigger_contents {
+        // didn't change from the previous build, then no action is required, unless LLD
+        // has been enabled since.
+        let lld = out_dir.join("bin").join(exe("lld", target));
+        if done_contents == rebuild_trigger_contents &&
+           (!build.config.lld_enabled || lld.exists()) {
             return
         }
     }
@@ -85,6 +88,11 @@ pub fn llvm(build: &Build, target: &str) {
         None => "X86;ARM;AArch64;Mips;PowerPC;SystemZ;JSBackend;MSP430;Sparc;NVPTX;Hexagon",
     };
 
+    let llvm_exp_targets = match build.config.llvm_experimental_targets {
+        Some(ref s) => s,
+        None => "WebAssembly",
+    };
+
     let assertions = if build.config.llvm_assertions {"ON"} else {"OFF"};
 
     cfg.target(target)
@@ -93,6 +101,7 @@ pub fn llvm(build: &Build, target: &str) {
        .profile(profile)
        .define("LLVM_ENABLE_ASSERTIONS", assertions)
        .define("LLVM_TARGETS_TO_BUILD", llvm_targets)
+       .define("LLVM_EXPERIMENTAL_TARGETS_TO_BUILD", llvm_exp_targets)
        .define("LLVM_INCLUDE_EXAMPLES", "OFF")
        .define("LLVM_INCLUDE_TESTS", "OFF")
        .define("LLVM_INCLUDE_DOCS", "OFF")
@@ -104,6 +113,13 @@ pub fn llvm(build: &Build, target: &str) {
        .define("LLVM_TARGET_ARCH", target.split('-').next().unwrap())
        .define("LLVM_DEFAULT_TARGET_TRIPLE", target);
 
+    // LLD is an LLVM "external project": it's built along with LLVM when CMake
+    // is told where its sources are.
+    if build.config.lld_enabled {
+        cfg.define("LLVM_EXTERNAL_LLD_SOURCE_DIR", build.src.join("src/tools/lld"))
+           .define("LLVM_TOOL_LLD_BUILD", "ON");
+    }
+
     if target.contains("msvc") {
         cfg.define("LLVM_USE_CRT_DEBUG", "MT");
         cfg.define("LLVM_USE_CRT_RELEASE", "MT");
@@ -86,6 +86,20 @@ pub fn check(build: &mut Build) {
         }
     }
 
+    // LLD is built along with LLVM, so it needs our LLVM and its sources.
+    if build.config.lld_enabled {
+        let custom_llvm = build.config.host.iter()
+            .filter_map(|host| build.config.target_config.get(host))
+            .any(|config| config.llvm_config.is_some());
+        if custom_llvm {
+            panic!("\n\nLLD can't be built along with a custom `llvm-config`\n\n");
+        }
+        if !build.src.join("src/tools/lld/CMakeLists.txt").exists() {
+            panic!("\n\n`lld = true` needs the LLD sources to be checked out \
+                    in src/tools/lld\n\n");
+        }
+    }
+
     if build.config.python.is_none() {
         build.config.python = have_cmd("python2.7".as_ref());
     }
@@ -121,8 +135,9 @@ pub fn check(build: &mut Build) {
     for target in build.config.target.iter() {
         // On emscripten we don't actually need the C compiler to just
         // build the target artifacts, only for testing. For the sake
-        // of easier bot configuration, just skip detection.
-        if target.contains("emscripten") {
+        // of easier bot configuration, just skip detection. Bare wasm
+        // doesn't use a C compiler at all.
+        if target.contains("emscripten") || target.contains("wasm32") {
             continue;
         }
 
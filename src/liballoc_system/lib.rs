@@ -20,6 +20,8 @@
 #![feature(allocator)]
 #![feature(staged_api)]
 #![cfg_attr(any(unix, target_os = "redox"), feature(libc))]
+#![cfg_attr(all(target_arch = "wasm32", not(target_os = "emscripten")),
+            feature(link_llvm_intrinsics))]
 
 // The minimum alignment guaranteed by the architecture. This value is used to
 // add fast paths for low alignment values. In practice, the alignment is a
@@ -272,3 +274,124 @@ mod imp {
         size
     }
 }
+
+/// Bare WebAssembly has no system allocator, only a linear memory which can
+/// grow by whole pages, so this is a small allocator of our own.
+///
+/// Every allocation is rounded up to a power of two which is at least its
+/// alignment, and blocks are aligned to their own size. A block can then be
+/// recycled through a free list per size class, and the size class of a
+/// block is known again from the size and alignment it is freed with. New
+/// blocks are carved from the end of the memory, which grows as needed.
+///
+/// wasm32-unknown-unknown is single threaded, so there is no locking.
+#[cfg(all(target_arch = "wasm32", not(target_os = "emscripten")))]
+mod imp {
+    use core::cmp;
+    use core::ptr;
+    use MIN_ALIGN;
+
+    const PAGE_SIZE: usize = 64 * 1024;
+
+    extern {
+        #[link_name = "llvm.wasm.current.memory.i32"]
+        fn current_memory() -> u32;
+        #[link_name = "llvm.wasm.grow.memory.i32"]
+        fn grow_memory(pages: u32) -> i32;
+    }
+
+    /// The heads of the free lists, indexed by the log2 of the block size.
+    /// The first word of a free block points to the next free block.
+    static mut FREE: [*mut u8; 32] = [0 as *mut u8; 32];
+    /// The unused end of the memory, once initialized.
+    static mut NEXT: usize = 0;
+    static mut END: usize = 0;
+
+    /// The log2 of the size of the blocks that allocations of `size` and
+    /// `align` are made from, or `None` if no block is large enough.
+    fn size_class(size: usize, align: usize) -> Option<usize> {
+        let size = cmp::max(size, cmp::max(align, MIN_ALIGN));
+        size.checked_next_power_of_two().map(|size| size.trailing_zeros() as usize)
+    }
+
+    unsafe fn alloc_class(class: usize) -> *mut u8 {
+        let head = FREE[class];
+        if !head.is_null() {
+            FREE[class] = *(head as *mut *mut u8);
+            return head;
+        }
+
+        if NEXT == 0 {
+            NEXT = current_memory() as usize * PAGE_SIZE;
+            END = NEXT;
+        }
+        let size = 1 << class;
+        let start = (NEXT + size - 1) & !(size - 1);
+        let end = match start.checked_add(size) {
+            Some(end) => end,
+            None => return ptr::null_mut(),
+        };
+        if end > END {
+            let pages = (end - END + PAGE_SIZE - 1) / PAGE_SIZE;
+            if grow_memory(pages as u32) < 0 {
+                return ptr::null_mut();
+            }
+            END += pages * PAGE_SIZE;
+        }
+        NEXT = end;
+        start as *mut u8
+    }
+
+    pub unsafe fn allocate(size: usize, align: usize) -> *mut u8 {
+        match size_class(size, align) {
+            Some(class) => alloc_class(class),
+            None => ptr::null_mut(),
+        }
+    }
+
+    pub unsafe fn allocate_zeroed(size: usize, align: usize) -> *mut u8 {
+        let ptr = allocate(size, align);
+        if !ptr.is_null() {
+            ptr::write_bytes(ptr, 0, size);
+        }
+        ptr
+    }
+
+    pub unsafe fn reallocate(ptr: *mut u8, old_size: usize, size: usize, align: usize) -> *mut u8 {
+        if size_class(old_size, align) == size_class(size, align) {
+            return ptr;
+        }
+        let new_ptr = allocate(size, align);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, cmp::min(size, old_size));
+            deallocate(ptr, old_size, align);
+        }
+        new_ptr
+    }
+
+    pub unsafe fn reallocate_inplace(_ptr: *mut u8,
+                                     old_size: usize,
+                                     size: usize,
+                                     align: usize)
+                                     -> usize {
+        if size_class(old_size, align) == size_class(size, align) {
+            size
+        } else {
+            old_size
+        }
+    }
+
+    pub unsafe fn deallocate(ptr: *mut u8, old_size: usize, align: usize) {
+        // The block was allocated, so it has a class.
+        let class = size_class(old_size, align).unwrap();
+        *(ptr as *mut *mut u8) = FREE[class];
+        FREE[class] = ptr;
+    }
+
+    pub fn usable_size(size: usize, align: usize) -> usize {
+        match size_class(size, align) {
+            Some(class) => 1 << class,
+            None => size,
+        }
+    }
+}
@@ -28,7 +28,9 @@
 #![panic_runtime]
 #![feature(panic_runtime)]
 #![cfg_attr(unix, feature(libc))]
-#![cfg_attr(any(target_os = "redox", windows), feature(core_intrinsics))]
+#![cfg_attr(any(target_os = "redox", windows,
+                all(target_arch = "wasm32", not(target_os = "emscripten"))),
+            feature(core_intrinsics))]
 
 // Rust's "try" function, but if we're aborting on panics we just call the
 // function as there's nothing else we need to do here.
@@ -50,7 +52,8 @@ pub unsafe extern fn __rust_maybe_catch_panic(f: fn(*mut u8),
 // and the `RaiseFailFastException` function isn't available until Windows 7
 // which would break compat with XP. For now just use `intrinsics::abort` which
 // will kill us with an illegal instruction, which will do a good enough job for
-// now hopefully.
+// now hopefully. Bare WebAssembly has no libc either, and there the
+// intrinsic is the `unreachable` instruction, which traps.
 #[no_mangle]
 pub unsafe extern fn __rust_start_panic(_data: usize, _vtable: usize) -> u32 {
     abort();
@@ -61,7 +64,8 @@ pub unsafe extern fn __rust_start_panic(_data: usize, _vtable: usize) -> u32 {
         libc::abort();
     }
 
-    #[cfg(any(target_os = "redox", windows))]
+    #[cfg(any(target_os = "redox", windows,
+              all(target_arch = "wasm32", not(target_os = "emscripten"))))]
     unsafe fn abort() -> ! {
         core::intrinsics::abort();
     }
@@ -79,6 +79,7 @@ pub enum DepNode<D: Clone + Debug> {
     BorrowCheck(D),
     RvalueCheck(D),
     Reachability,
+    UpstreamMonomorphizations,
     MirKeys,
     LateLintCheck,
     TransCrateItem(D),
@@ -229,6 +230,7 @@ impl<D: Clone + Debug> DepNode<D> {
             Variance => Some(Variance),
             PrivacyAccessLevels(k) => Some(PrivacyAccessLevels(k)),
             Reachability => Some(Reachability),
+            UpstreamMonomorphizations => Some(UpstreamMonomorphizations),
             MirKeys => Some(MirKeys),
             LateLintCheck => Some(LateLintCheck),
             TransWriteMetadata => Some(TransWriteMetadata),
@@ -329,4 +331,5 @@ pub enum GlobalMetaDataKind {
     CodeMap,
     Impls,
     ExportedSymbols,
+    ExportedGenerics,
 }
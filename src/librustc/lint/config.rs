@@ -0,0 +1,504 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Lint configuration files, as passed with `--lint-config <file>`.
+//!
+//! The file is written in a small subset of TOML:
+//!
+//! ```toml
+//! [lints]
+//! unused-variables = "deny"
+//! nonstandard-style = "warn"
+//!
+//! [[overrides]]
+//! paths = ["generated/**"]
+//!
+//! [overrides.lints]
+//! dead-code = "allow"
+//! ```
+//!
+//! The `[lints]` table sets the level of lints and lint groups for the whole
+//! crate. The levels are applied before the `-A`/`-W`/`-D`/`-F` flags and
+//! before any lint attribute, so both of those still take precedence.
+//!
+//! Each `[[overrides]]` entry sets levels for the source files matching one
+//! of its `paths` globs. Globs support `?`, `*` and `**`. A glob that starts
+//! with `/` is matched against the absolute path of the file. Any other glob
+//! only matches files in the directory of the configuration file or below
+//! it, at any directory level, so `generated/**` matches
+//! `src/generated/mod.rs` next to the configuration file. Overrides only replace levels that come
+//! from the lint defaults or from the `[lints]` table; later overrides win.
+
+use lint::Level;
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::iter::Peekable;
+use std::path::{Component, Path, PathBuf};
+use std::str::Chars;
+
+/// A parsed lint configuration file.
+#[derive(Clone, Debug, Hash)]
+pub struct LintConfig {
+    /// The file the configuration was read from.
+    pub path: PathBuf,
+
+    /// Crate-wide levels of lints and lint groups, in file order.
+    pub levels: Vec<(String, Level)>,
+
+    /// Per-path levels, in file order.
+    pub overrides: Vec<LintPathOverride>,
+}
+
+/// The levels an `[[overrides]]` entry sets for the files matching `paths`.
+#[derive(Clone, Debug, Hash)]
+pub struct LintPathOverride {
+    /// The absolute path of the directory of the configuration file, which
+    /// relative `paths` are resolved against.
+    pub base: PathBuf,
+    pub paths: Vec<String>,
+    pub levels: Vec<(String, Level)>,
+}
+
+impl LintPathOverride {
+    /// Whether this override applies to the source file `file`, given
+    /// relative to the working directory of the compiler or as an absolute
+    /// path.
+    pub fn matches(&self, file: &str) -> bool {
+        let file = absolute(Path::new(file));
+        let chars = |path: &Path| -> Vec<char> {
+            path.to_string_lossy().replace("\\", "/").chars().collect()
+        };
+        let absolute_file = chars(&file);
+        let relative_file = file.strip_prefix(&self.base).ok().map(|path| chars(path));
+        self.paths.iter().any(|pattern| {
+            let pattern: Vec<char> = pattern.chars().collect();
+            if pattern.first() == Some(&'/') {
+                return glob_matches(&pattern, &absolute_file);
+            }
+            // Relative patterns may match at any directory level below the
+            // configuration file.
+            match relative_file {
+                Some(ref file) => {
+                    (0..file.len()).filter(|&i| i == 0 || file[i - 1] == '/')
+                                   .any(|i| glob_matches(&pattern, &file[i..]))
+                }
+                None => false,
+            }
+        })
+    }
+}
+
+impl LintConfig {
+    /// Reads and parses the lint configuration file at `path`.
+    pub fn load(path: &Path) -> Result<LintConfig, String> {
+        let mut src = String::new();
+        File::open(path).and_then(|mut f| f.read_to_string(&mut src)).map_err(|e| {
+            format!("failed to read lint config `{}`: {}", path.display(), e)
+        })?;
+        LintConfig::parse(path, &src).map_err(|e| {
+            format!("invalid lint config `{}`: {}", path.display(), e)
+        })
+    }
+
+    fn parse(path: &Path, src: &str) -> Result<LintConfig, String> {
+        let mut config = LintConfig {
+            path: path.to_path_buf(),
+            levels: vec![],
+            overrides: vec![],
+        };
+
+        let mut parser = Parser {
+            chars: src.chars().peekable(),
+            line: 1,
+        };
+        let mut section = Section::Root;
+        while let Some(item) = parser.next_item()? {
+            let line = parser.line;
+            let err = |msg: String| Err(format!("line {}: {}", line, msg));
+            match item {
+                Item::Table(ref name) if name == "lints" => section = Section::Lints,
+                Item::Table(ref name) if name == "overrides.lints" => {
+                    if config.overrides.is_empty() {
+                        return err(format!("`[overrides.lints]` outside of an \
+                                            `[[overrides]]` entry"));
+                    }
+                    section = Section::OverrideLints;
+                }
+                Item::ArrayTable(ref name) if name == "overrides" => {
+                    check_override(config.overrides.last(), line)?;
+                    config.overrides.push(LintPathOverride {
+                        base: absolute(path.parent().unwrap_or(Path::new(""))),
+                        paths: vec![],
+                        levels: vec![],
+                    });
+                    section = Section::Override;
+                }
+                Item::Table(name) => return err(format!("unknown table `[{}]`", name)),
+                Item::ArrayTable(name) => return err(format!("unknown table `[[{}]]`", name)),
+                Item::KeyValue(key, value) => {
+                    match section {
+                        Section::Root => {
+                            return err(format!("unexpected key `{}` outside of a table", key));
+                        }
+                        Section::Lints => {
+                            let level = lint_level(&key, value, line)?;
+                            config.levels.push(level);
+                        }
+                        Section::OverrideLints => {
+                            let level = lint_level(&key, value, line)?;
+                            config.overrides.last_mut().unwrap().levels.push(level);
+                        }
+                        Section::Override if key == "paths" => {
+                            let paths = match value {
+                                Value::Array(paths) => paths,
+                                Value::String(_) => {
+                                    return err(format!("`paths` must be an array of strings"));
+                                }
+                            };
+                            config.overrides.last_mut().unwrap().paths = paths;
+                        }
+                        Section::Override => {
+                            return err(format!("unknown key `{}` in `[[overrides]]`", key));
+                        }
+                    }
+                }
+            }
+        }
+        check_override(config.overrides.last(), parser.line)?;
+
+        Ok(config)
+    }
+}
+
+fn check_override(o: Option<&LintPathOverride>, line: usize) -> Result<(), String> {
+    match o {
+        Some(o) if o.paths.is_empty() => {
+            Err(format!("line {}: `[[overrides]]` entry without `paths`", line))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn lint_level(key: &str, value: Value, line: usize) -> Result<(String, Level), String> {
+    let level = match value {
+        Value::String(ref s) => Level::from_str(s),
+        Value::Array(_) => None,
+    };
+    match level {
+        Some(level) => Ok((key.replace("-", "_"), level)),
+        None => Err(format!("line {}: the level of `{}` must be one of \
+                             \"allow\", \"warn\", \"deny\" or \"forbid\"", line, key)),
+    }
+}
+
+/// Makes `path` absolute by resolving it against the working directory, and
+/// removes its `.` and `..` components.
+fn absolute(path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir().map(|dir| dir.join(path)).unwrap_or(path.to_path_buf())
+    };
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => normalized.push(component.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Matches `path` against a glob `pattern`. `?` and `*` never match a `/`;
+/// `**` matches any number of directories.
+fn glob_matches(pattern: &[char], path: &[char]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&'*', rest)) if rest.first() == Some(&'*') => {
+            let rest = &rest[1..];
+            if rest.first() == Some(&'/') {
+                // `**/` matches zero or more whole directories.
+                let rest = &rest[1..];
+                (0..path.len() + 1).filter(|&i| i == 0 || path[i - 1] == '/')
+                                   .any(|i| glob_matches(rest, &path[i..]))
+            } else {
+                (0..path.len() + 1).any(|i| glob_matches(rest, &path[i..]))
+            }
+        }
+        Some((&'*', rest)) => {
+            (0..path.len() + 1).take_while(|&i| i == 0 || path[i - 1] != '/')
+                               .any(|i| glob_matches(rest, &path[i..]))
+        }
+        Some((&'?', rest)) => {
+            path.first().map_or(false, |&c| c != '/') && glob_matches(rest, &path[1..])
+        }
+        Some((&c, rest)) => {
+            path.first() == Some(&c) && glob_matches(rest, &path[1..])
+        }
+    }
+}
+
+enum Section {
+    Root,
+    Lints,
+    Override,
+    OverrideLints,
+}
+
+enum Item {
+    Table(String),
+    ArrayTable(String),
+    KeyValue(String, Value),
+}
+
+enum Value {
+    String(String),
+    Array(Vec<String>),
+}
+
+/// A parser for the subset of TOML used by lint configuration files: tables,
+/// arrays of tables, bare or quoted keys, and values that are strings or
+/// arrays of strings.
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next_item(&mut self) -> Result<Option<Item>, String> {
+        self.skip_whitespace(true);
+        let item = match self.chars.peek().cloned() {
+            None => return Ok(None),
+            Some('[') => {
+                self.chars.next();
+                let array = self.eat('[');
+                let name = self.dotted_key()?;
+                self.expect(']')?;
+                if array {
+                    self.expect(']')?;
+                    Item::ArrayTable(name)
+                } else {
+                    Item::Table(name)
+                }
+            }
+            Some(_) => {
+                let key = self.key()?;
+                self.skip_whitespace(false);
+                self.expect('=')?;
+                self.skip_whitespace(false);
+                let value = self.value()?;
+                Item::KeyValue(key, value)
+            }
+        };
+
+        self.skip_whitespace(false);
+        match self.chars.peek() {
+            None | Some(&'\n') => Ok(Some(item)),
+            Some(&c) => self.error(format!("expected a newline, found `{}`", c)),
+        }
+    }
+
+    fn dotted_key(&mut self) -> Result<String, String> {
+        let mut name = String::new();
+        loop {
+            self.skip_whitespace(false);
+            name.push_str(&self.key()?);
+            self.skip_whitespace(false);
+            if !self.eat('.') {
+                return Ok(name);
+            }
+            name.push('.');
+        }
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        if let Some(&'"') = self.chars.peek() {
+            return self.string();
+        }
+        let mut key = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !(c.is_alphanumeric() || c == '_' || c == '-') {
+                break;
+            }
+            key.push(c);
+            self.chars.next();
+        }
+        if key.is_empty() {
+            self.error(format!("expected a key"))
+        } else {
+            Ok(key)
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.chars.peek().cloned() {
+            Some('"') | Some('\'') => Ok(Value::String(self.string()?)),
+            Some('[') => {
+                self.chars.next();
+                let mut values = vec![];
+                loop {
+                    self.skip_whitespace(true);
+                    if self.eat(']') {
+                        return Ok(Value::Array(values));
+                    }
+                    values.push(self.string()?);
+                    self.skip_whitespace(true);
+                    if !self.eat(',') {
+                        self.skip_whitespace(true);
+                        self.expect(']')?;
+                        return Ok(Value::Array(values));
+                    }
+                }
+            }
+            _ => self.error(format!("expected a string or an array of strings")),
+        }
+    }
+
+    /// Parses a basic (`"..."`) or literal (`'...'`) single-line string.
+    fn string(&mut self) -> Result<String, String> {
+        let quote = match self.chars.next() {
+            Some(c @ '"') | Some(c @ '\'') => c,
+            _ => return self.error(format!("expected a string")),
+        };
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                None | Some('\n') => return self.error(format!("unterminated string")),
+                Some(c) if c == quote => return Ok(s),
+                Some('\\') if quote == '"' => {
+                    match self.chars.next() {
+                        Some('\\') => s.push('\\'),
+                        Some('"') => s.push('"'),
+                        Some('n') => s.push('\n'),
+                        Some('t') => s.push('\t'),
+                        _ => return self.error(format!("unknown escape sequence in string")),
+                    }
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    /// Skips spaces and comments, and newlines too if `newlines` is set.
+    fn skip_whitespace(&mut self, newlines: bool) {
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {}
+                '\n' if newlines => self.line += 1,
+                '#' => {
+                    while self.chars.peek().map_or(false, |&c| c != '\n') {
+                        self.chars.next();
+                    }
+                    continue;
+                }
+                _ => return,
+            }
+            self.chars.next();
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.chars.peek() == Some(&c) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{}`", c))
+        }
+    }
+
+    fn error<T>(&self, msg: String) -> Result<T, String> {
+        Err(format!("line {}: {}", self.line, msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LintConfig, LintPathOverride};
+    use lint::{Allow, Deny, Warn};
+    use std::env;
+    use std::path::Path;
+
+    /// Whether `pattern`, from a configuration file in `project`, matches
+    /// `file`.
+    fn matches(pattern: &str, file: &str) -> bool {
+        LintPathOverride {
+            base: env::current_dir().unwrap().join("project"),
+            paths: vec![pattern.to_string()],
+            levels: vec![],
+        }.matches(file)
+    }
+
+    #[test]
+    fn globs() {
+        assert!(matches("generated/**", "project/src/generated/mod.rs"));
+        assert!(matches("generated/**", "project/generated/a/b.rs"));
+        assert!(!matches("generated/**", "project/src/not_generated/mod.rs"));
+        assert!(matches("src/**/*.rs", "project/src/lib.rs"));
+        assert!(matches("src/**/*.rs", "project/src/a/b/c.rs"));
+        assert!(!matches("src/*.rs", "project/src/a/b.rs"));
+        assert!(matches("gen.rs", "project/src\\gen.rs"));
+        // Relative patterns are relative to the configuration file.
+        assert!(!matches("src/*.rs", "elsewhere/src/a.rs"));
+        assert!(matches("src/*.rs", "project/./lib/../src/a.rs"));
+        if cfg!(unix) {
+            assert!(matches("/src/?.rs", "/src/a.rs"));
+            assert!(!matches("/src/?.rs", "/project/src/a.rs"));
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let src = r#"
+# Crate-wide levels.
+[lints]
+unused-variables = "deny"   # comment
+"dead_code" = 'warn'
+
+[[overrides]]
+paths = [
+    "generated/**",
+    "bindings.rs",
+]
+[overrides.lints]
+dead-code = "allow"
+"#;
+        let config = LintConfig::parse(Path::new("lints.toml"), src).unwrap();
+        assert_eq!(config.levels, vec![("unused_variables".to_string(), Deny),
+                                       ("dead_code".to_string(), Warn)]);
+        assert_eq!(config.overrides.len(), 1);
+        assert_eq!(config.overrides[0].paths, vec!["generated/**", "bindings.rs"]);
+        assert_eq!(config.overrides[0].levels, vec![("dead_code".to_string(), Allow)]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let parse = |src| LintConfig::parse(Path::new("lints.toml"), src).unwrap_err();
+        assert_eq!(parse("[lints]\nfoo = \"loud\""),
+                   "line 2: the level of `foo` must be one of \
+                    \"allow\", \"warn\", \"deny\" or \"forbid\"");
+        assert_eq!(parse("foo = \"warn\""), "line 1: unexpected key `foo` outside of a table");
+        assert_eq!(parse("[[overrides]]\n[overrides.lints]\nfoo = \"warn\""),
+                   "line 3: `[[overrides]]` entry without `paths`");
+        assert_eq!(parse("[lints] x"), "line 1: expected a newline, found `x`");
+    }
+}
@@ -30,8 +30,8 @@ use middle::privacy::AccessLevels;
 use ty::{self, TyCtxt};
 use session::{config, early_error, Session};
 use lint::{Level, LevelSource, Lint, LintId, LintPass, LintSource};
-use lint::{EarlyLintPassObject, LateLintPassObject};
-use lint::{Default, CommandLine, Node, Allow, Warn, Deny, Forbid};
+use lint::{EarlyLintPassObject, LateLintPassObject, LintConfig, LintPathOverride};
+use lint::{Default, CommandLine, ConfigFile, Node, Allow, Warn, Deny, Forbid};
 use lint::builtin;
 use rustc_serialize::{Decoder, Decodable, Encoder, Encodable};
 use util::nodemap::FxHashMap;
@@ -79,6 +79,10 @@ pub struct LintStore {
     /// Extra info for future incompatibility lints, descibing the
     /// issue or RFC that caused the incompatibility.
     future_incompatible: FxHashMap<LintId, FutureIncompatibleInfo>,
+
+    /// The per-path levels of the lint configuration file, with their lint
+    /// groups expanded. These are looked up when a lint is emitted.
+    path_overrides: Vec<(LintPathOverride, FxHashMap<LintId, LevelSource>)>,
 }
 
 
@@ -191,6 +195,7 @@ impl LintStore {
             levels: LintLevels::default(),
             future_incompatible: FxHashMap(),
             lint_groups: FxHashMap(),
+            path_overrides: vec![],
         }
     }
 
@@ -307,37 +312,78 @@ impl LintStore {
         }
     }
 
+    /// Returns the lints named by `lint_name`, which is either a lint or a
+    /// lint group.
+    fn lints_named(&self, lint_name: &str, sess: &Session) -> Vec<LintId> {
+        match self.find_lint(lint_name, sess, None) {
+            Ok(lint_id) => vec![lint_id],
+            Err(FindLintError::Removed) => vec![],
+            Err(_) => {
+                match self.lint_groups.get(lint_name) {
+                    Some(&(ref v, _)) => v.clone(),
+                    None => {
+                        // The lint or lint group doesn't exist.
+                        // This is an error, but it was handled
+                        // by check_lint_name_cmdline.
+                        vec![]
+                    }
+                }
+            }
+        }
+    }
+
     pub fn process_command_line(&mut self, sess: &Session) {
+        // The lint configuration file comes first so that command-line flags
+        // can override it.
+        if let Some(ref config) = sess.opts.lint_config {
+            for &(ref lint_name, level) in &config.levels {
+                check_lint_name_config(sess, self, config, &lint_name[..]);
+
+                let source = ConfigFile(Symbol::intern(&lint_name));
+                for lint_id in self.lints_named(&lint_name[..], sess) {
+                    self.levels.set(lint_id, (level, source));
+                }
+            }
+
+            let mut path_overrides = vec![];
+            for path_override in &config.overrides {
+                let mut levels = FxHashMap();
+                for &(ref lint_name, level) in &path_override.levels {
+                    check_lint_name_config(sess, self, config, &lint_name[..]);
+
+                    let source = ConfigFile(Symbol::intern(&lint_name));
+                    for lint_id in self.lints_named(&lint_name[..], sess) {
+                        levels.insert(lint_id, (level, source));
+                    }
+                }
+                path_overrides.push((path_override.clone(), levels));
+            }
+            self.path_overrides = path_overrides;
+        }
+
         for &(ref lint_name, level) in &sess.opts.lint_opts {
             check_lint_name_cmdline(sess, self,
                                     &lint_name[..], level);
 
             let lint_flag_val = Symbol::intern(&lint_name);
-            match self.find_lint(&lint_name[..], sess, None) {
-                Ok(lint_id) => self.levels.set(lint_id, (level, CommandLine(lint_flag_val))),
-                Err(FindLintError::Removed) => { }
-                Err(_) => {
-                    match self.lint_groups.iter().map(|(&x, pair)| (x, pair.0.clone()))
-                                                 .collect::<FxHashMap<&'static str,
-                                                                      Vec<LintId>>>()
-                                                 .get(&lint_name[..]) {
-                        Some(v) => {
-                            for lint_id in v {
-                                self.levels.set(*lint_id, (level, CommandLine(lint_flag_val)));
-                            }
-                        }
-                        None => {
-                            // The lint or lint group doesn't exist.
-                            // This is an error, but it was handled
-                            // by check_lint_name_cmdline.
-                        }
-                    }
-                }
+            for lint_id in self.lints_named(&lint_name[..], sess) {
+                self.levels.set(lint_id, (level, CommandLine(lint_flag_val)));
             }
         }
 
         self.levels.set_lint_cap(sess.opts.lint_cap);
     }
+
+    /// Returns the level that the lint configuration file sets for `lint` in
+    /// the source file `file`, if any of its path overrides match the file.
+    fn path_override(&self, lint: LintId, file: &str) -> Option<LevelSource> {
+        self.path_overrides.iter().rev().filter_map(|&(ref path_override, ref levels)| {
+            match levels.get(&lint) {
+                Some(&lvlsrc) if path_override.matches(file) => Some(lvlsrc),
+                _ => None,
+            }
+        }).next()
+    }
 }
 
 
@@ -353,7 +399,9 @@ impl LintLevels {
         if let Some(cap) = self.lint_cap {
             lvlsrc.0 = cmp::min(lvlsrc.0, cap);
         }
-        if lvlsrc.0 == Allow {
+        // Lints that were explicitly allowed are kept so that the path
+        // overrides of the lint configuration file don't apply to them.
+        if lvlsrc == (Allow, Default) {
             self.levels.remove(&lint);
         } else {
             self.levels.insert(lint, lvlsrc);
@@ -525,6 +573,7 @@ pub fn raw_struct_lint<'a, S>(sess: &'a Session,
         (Deny, None)     => sess.struct_err(&msg[..]),
         _ => bug!("impossible level in raw_emit_lint"),
     };
+    err.lint(name.clone());
 
     match source {
         Default => {
@@ -545,6 +594,20 @@ pub fn raw_struct_lint<'a, S>(sess: &'a Session,
                                   flag, hyphen_case_lint_name, flag, hyphen_case_flag_val));
             }
         },
+        ConfigFile(lint_config_name) => {
+            let path = sess.opts.lint_config.as_ref().map_or(String::new(), |config| {
+                config.path.display().to_string()
+            });
+            if lint_config_name.as_str() == name {
+                err.note(&format!("`{} = \"{}\"` set in lint configuration `{}`",
+                                  name, level.as_str(), path));
+            } else {
+                err.note(&format!("`{} = \"{}\"` implied by `{} = \"{}\"` in lint \
+                                   configuration `{}`",
+                                  name, level.as_str(), lint_config_name, level.as_str(),
+                                  path));
+            }
+        },
         Node(lint_attr_name, src) => {
             def = Some(src);
             if lint_attr_name.as_str() != name {
@@ -632,11 +695,43 @@ pub trait LintContext<'tcx>: Sized {
         })
     }
 
+    /// Like `level_src`, but also applies the path overrides of the lint
+    /// configuration file to the file of `span`. Overrides only replace levels
+    /// that come from the defaults or from the configuration file itself.
+    fn level_src_at(&self, lint: &'static Lint, span: Option<&MultiSpan>)
+                    -> Option<LevelSource> {
+        let level_src = self.level_src(lint);
+        match level_src {
+            None | Some((_, Default)) | Some((_, ConfigFile(_))) => {}
+            Some(_) => return level_src,
+        }
+        if self.lints().path_overrides.is_empty() {
+            return level_src;
+        }
+
+        let span = match span.and_then(|span| span.primary_span()) {
+            Some(span) => span,
+            None => return level_src,
+        };
+        let file = self.sess().codemap().span_to_filename(span);
+        match self.lints().path_override(LintId::of(lint), &file) {
+            Some((level, src)) => {
+                let level = match self.sess().opts.lint_cap {
+                    Some(cap) => cmp::min(level, cap),
+                    None => level,
+                };
+                Some((level, src))
+            }
+            None => level_src,
+        }
+    }
+
     fn lookup_and_emit<S: Into<MultiSpan>>(&self,
                                            lint: &'static Lint,
                                            span: Option<S>,
                                            msg: &str) {
-        let (level, src) = match self.level_src(lint) {
+        let span = span.map(|s| s.into());
+        let (level, src) = match self.level_src_at(lint, span.as_ref()) {
             None => return,
             Some(pair) => pair,
         };
@@ -649,7 +744,8 @@ pub trait LintContext<'tcx>: Sized {
                                   span: Option<S>,
                                   msg: &str)
                                   -> DiagnosticBuilder {
-        let (level, src) = match self.level_src(lint) {
+        let span = span.map(|s| s.into());
+        let (level, src) = match self.level_src_at(lint, span.as_ref()) {
             None => return self.sess().diagnostic().struct_dummy(),
             Some(pair) => pair,
         };
@@ -772,6 +868,10 @@ pub trait LintContext<'tcx>: Sized {
                         LintSource::CommandLine(_) => {
                             diag_builder.note("`forbid` lint level was set on command line")
                         }
+                        LintSource::ConfigFile(_) => {
+                            diag_builder.note("`forbid` lint level was set in the lint \
+                                               configuration file")
+                        }
                     }.emit()
                 } else if now != level {
                     let cx = self.lint_sess_mut();
@@ -1289,6 +1389,19 @@ fn check_lint_name_attribute(cx: &LateContext, attr: &ast::Attribute) {
     }
 }
 
+// Checks the validity of lint names read from the lint configuration file
+fn check_lint_name_config(sess: &Session, lint_cx: &LintStore,
+                          config: &LintConfig, lint_name: &str) {
+    let msg = match check_lint_name(lint_cx, lint_name) {
+        CheckLintNameResult::Ok => return,
+        CheckLintNameResult::Warning(msg) => msg,
+        CheckLintNameResult::NoLint => format!("unknown lint: `{}`", lint_name),
+    };
+    sess.struct_warn(&msg)
+        .note(&format!("set in lint configuration `{}`", config.path.display()))
+        .emit();
+}
+
 // Checks the validity of lint names derived from the command line
 fn check_lint_name_cmdline(sess: &Session, lint_cx: &LintStore,
                            lint_name: &str, level: Level) {
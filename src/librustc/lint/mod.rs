@@ -46,6 +46,8 @@ pub use lint::context::{LateContext, EarlyContext, LintContext, LintStore,
 
 pub use lint::table::LintTable;
 
+pub use lint::config::{LintConfig, LintPathOverride};
+
 /// Specification of a single lint.
 #[derive(Copy, Clone, Debug)]
 pub struct Lint {
@@ -345,10 +347,14 @@ pub enum LintSource {
 
     /// Lint level was set by a command-line flag.
     CommandLine(Symbol),
+
+    /// Lint level was set by the lint configuration file.
+    ConfigFile(Symbol),
 }
 
 pub type LevelSource = (Level, LintSource);
 
 pub mod builtin;
+mod config;
 mod context;
 mod table;
@@ -104,6 +104,11 @@ pub enum ErrKind<'tcx> {
 
     ErroneousReferencedConstant(Box<ConstEvalErr<'tcx>>),
 
+    /// The evaluation of a `const fn` call failed with the given error.
+    InConstFn(DefId, Box<ConstEvalErr<'tcx>>),
+    UndefinedBehavior(&'static str),
+    LimitReached(&'static str),
+
     TypeckError
 }
 
@@ -131,6 +136,15 @@ impl<'a> ConstEvalErrDescription<'a> {
 }
 
 impl<'a, 'gcx, 'tcx> ConstEvalErr<'tcx> {
+    /// Returns the error that caused this one, looking through the
+    /// `const fn` calls it happened in.
+    pub fn innermost(&self) -> &ConstEvalErr<'tcx> {
+        match self.kind {
+            ErrKind::InConstFn(_, box ref err) => err.innermost(),
+            _ => self
+        }
+    }
+
     pub fn description(&self) -> ConstEvalErrDescription {
         use self::ErrKind::*;
         use self::ConstEvalErrDescription::*;
@@ -166,6 +180,9 @@ impl<'a, 'gcx, 'tcx> ConstEvalErr<'tcx> {
             Math(ref err) => Simple(err.description().into_cow()),
 
             ErroneousReferencedConstant(_) => simple!("could not evaluate referenced constant"),
+            InConstFn(_, ref err) => err.description(),
+            UndefinedBehavior(what) => simple!("undefined behavior: {}", what),
+            LimitReached(limit) => simple!("constant evaluation reached the {} limit", limit),
 
             TypeckError => simple!("type-checking failed"),
         }
@@ -178,14 +195,23 @@ impl<'a, 'gcx, 'tcx> ConstEvalErr<'tcx> {
         -> DiagnosticBuilder<'gcx>
     {
         let mut err = self;
-        while let &ConstEvalErr {
-            kind: ErrKind::ErroneousReferencedConstant(box ref i_err), ..
-        } = err {
-            err = i_err;
+        let mut calls = vec![];
+        loop {
+            match err.kind {
+                ErrKind::ErroneousReferencedConstant(box ref i_err) => err = i_err,
+                ErrKind::InConstFn(def_id, box ref i_err) => {
+                    calls.push((err.span, def_id));
+                    err = i_err;
+                }
+                _ => break
+            }
         }
 
         let mut diag = struct_span_err!(tcx.sess, err.span, E0080, "constant evaluation error");
         err.note(tcx, primary_span, primary_kind, &mut diag);
+        for &(span, def_id) in calls.iter().rev() {
+            diag.span_note(span, &format!("inside call to `{}`", tcx.item_path_str(def_id)));
+        }
         diag
     }
 
@@ -212,7 +238,7 @@ impl<'a, 'gcx, 'tcx> ConstEvalErr<'tcx> {
         primary_span: Span,
         primary_kind: &str)
     {
-        if let ErrKind::TypeckError = self.kind {
+        if let ErrKind::TypeckError = self.innermost().kind {
             return;
         }
         self.struct_error(tcx, primary_span, primary_kind).emit();
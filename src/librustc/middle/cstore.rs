@@ -272,6 +272,9 @@ pub trait CrateStore {
     fn derive_registrar_fn(&self, cnum: CrateNum) -> Option<DefId>;
     fn native_libraries(&self, cnum: CrateNum) -> Vec<NativeLibrary>;
     fn exported_symbols(&self, cnum: CrateNum) -> Vec<DefId>;
+    /// The generic instances the crate shares with downstream crates, as pairs
+    /// of instance hash and symbol name.
+    fn exported_generics(&self, cnum: CrateNum) -> Vec<(u64, String)>;
     fn is_no_builtins(&self, cnum: CrateNum) -> bool;
 
     // resolve
@@ -303,7 +306,8 @@ pub trait CrateStore {
     fn encode_metadata<'a, 'tcx>(&self,
                                  tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                  link_meta: &LinkMeta,
-                                 reachable: &NodeSet)
+                                 reachable: &NodeSet,
+                                 exported_generics: &[(u64, String)])
                                  -> EncodedMetadata;
     fn metadata_encoding_version(&self) -> &[u8];
 }
@@ -398,6 +402,9 @@ impl CrateStore for DummyCrateStore {
     fn native_libraries(&self, cnum: CrateNum) -> Vec<NativeLibrary>
         { bug!("native_libraries") }
     fn exported_symbols(&self, cnum: CrateNum) -> Vec<DefId> { bug!("exported_symbols") }
+    fn exported_generics(&self, cnum: CrateNum) -> Vec<(u64, String)> {
+        bug!("exported_generics")
+    }
     fn is_no_builtins(&self, cnum: CrateNum) -> bool { bug!("is_no_builtins") }
 
     // resolve
@@ -439,7 +446,8 @@ impl CrateStore for DummyCrateStore {
     fn encode_metadata<'a, 'tcx>(&self,
                                  tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                  link_meta: &LinkMeta,
-                                 reachable: &NodeSet)
+                                 reachable: &NodeSet,
+                                 exported_generics: &[(u64, String)])
                                  -> EncodedMetadata {
         bug!("encode_metadata")
     }
@@ -60,6 +60,45 @@ pub enum Sanitizer {
     Thread,
 }
 
+/// The kind of link-time optimization to perform.
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum Lto {
+    /// Don't do any LTO.
+    No,
+    /// Merge the crate and all its dependencies into a single module
+    /// (`-C lto`).
+    Fat,
+    /// ThinLTO over the codegen units of the crate and all its dependencies
+    /// (`-C lto=thin`).
+    Thin,
+    /// ThinLTO over the codegen units of the crate only (`-Z thinlto`).
+    ThinLocal,
+}
+
+/// How debuginfo is split off of the object files (`-C split-debuginfo`).
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum SplitDebuginfo {
+    /// Keep the debuginfo in the object files.
+    Off,
+    /// Move the debuginfo of each object file into a `.dwo` file next to the
+    /// output, leaving only what is needed to find it in the object file.
+    Unpacked,
+    /// Like `Unpacked`, but also package the `.dwo` files that a linked
+    /// executable or dynamic library refers to into a `.dwp` file next to it.
+    Packed,
+}
+
+/// What the linker strips from the output (`-C strip`).
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum Strip {
+    /// Keep everything.
+    None,
+    /// Strip the debuginfo, but keep the symbol table.
+    Debuginfo,
+    /// Strip the debuginfo and the symbol table.
+    Symbols,
+}
+
 #[derive(Clone, Copy, PartialEq, Hash)]
 pub enum OptLevel {
     No, // -O0
@@ -135,6 +174,8 @@ impl OutputType {
 pub enum ErrorOutputType {
     HumanReadable(ColorConfig),
     Json,
+    Sarif,
+    Short(ColorConfig),
 }
 
 impl Default for ErrorOutputType {
@@ -288,6 +329,7 @@ top_level_options!(
         debuginfo: DebugInfoLevel [TRACKED],
         lint_opts: Vec<(String, lint::Level)> [TRACKED],
         lint_cap: Option<lint::Level> [TRACKED],
+        lint_config: Option<lint::LintConfig> [TRACKED],
         describe_lints: bool [UNTRACKED],
         output_types: OutputTypes [TRACKED],
         // FIXME(mw): We track this for now but it actually doesn't make too
@@ -340,6 +382,9 @@ pub enum PrintRequest {
     RelocationModels,
     CodeModels,
     TargetSpec,
+    Deps,
+    Features,
+    ExportedSymbols,
 }
 
 pub enum Input {
@@ -460,6 +505,7 @@ pub fn basic_options() -> Options {
         debuginfo: NoDebugInfo,
         lint_opts: Vec::new(),
         lint_cap: None,
+        lint_config: None,
         describe_lints: false,
         output_types: OutputTypes(BTreeMap::new()),
         search_paths: SearchPaths::new(),
@@ -654,12 +700,21 @@ macro_rules! options {
             Some(::rustc_back::LinkerFlavor::one_of());
         pub const parse_optimization_fuel: Option<&'static str> =
             Some("crate=integer");
+        pub const parse_lto: Option<&'static str> =
+            Some("one of: `y`, `yes`, `on`, `fat`, `thin`, `n`, `no`, or `off`");
+        pub const parse_opt_pathbuf: Option<&'static str> = Some("a path");
+        pub const parse_split_debuginfo: Option<&'static str> =
+            Some("one of: `off`, `unpacked`, or `packed`");
+        pub const parse_strip: Option<&'static str> =
+            Some("one of: `none`, `debuginfo`, or `symbols`");
     }
 
     #[allow(dead_code)]
     mod $mod_set {
-        use super::{$struct_name, Passes, SomePasses, AllPasses, Sanitizer};
+        use super::{$struct_name, Passes, SomePasses, AllPasses, Sanitizer, Lto,
+                    SplitDebuginfo, Strip};
         use rustc_back::{LinkerFlavor, PanicStrategy};
+        use std::path::PathBuf;
 
         $(
             pub fn $opt(cg: &mut $struct_name, v: Option<&str>) -> bool {
@@ -700,6 +755,13 @@ macro_rules! options {
             }
         }
 
+        fn parse_opt_pathbuf(slot: &mut Option<PathBuf>, v: Option<&str>) -> bool {
+            match v {
+                Some(s) => { *slot = Some(PathBuf::from(s)); true },
+                None => false,
+            }
+        }
+
         fn parse_string(slot: &mut String, v: Option<&str>) -> bool {
             match v {
                 Some(s) => { *slot = s.to_string(); true },
@@ -799,6 +861,36 @@ macro_rules! options {
             true
         }
 
+        fn parse_lto(slot: &mut Lto, v: Option<&str>) -> bool {
+            *slot = match v {
+                None | Some("y") | Some("yes") | Some("on") | Some("fat") => Lto::Fat,
+                Some("thin") => Lto::Thin,
+                Some("n") | Some("no") | Some("off") => Lto::No,
+                _ => return false,
+            };
+            true
+        }
+
+        fn parse_split_debuginfo(slot: &mut SplitDebuginfo, v: Option<&str>) -> bool {
+            *slot = match v {
+                Some("off") => SplitDebuginfo::Off,
+                Some("unpacked") => SplitDebuginfo::Unpacked,
+                Some("packed") => SplitDebuginfo::Packed,
+                _ => return false,
+            };
+            true
+        }
+
+        fn parse_strip(slot: &mut Strip, v: Option<&str>) -> bool {
+            *slot = match v {
+                Some("none") => Strip::None,
+                Some("debuginfo") => Strip::Debuginfo,
+                Some("symbols") => Strip::Symbols,
+                _ => return false,
+            };
+            true
+        }
+
         fn parse_optimization_fuel(slot: &mut Option<(String, u64)>, v: Option<&str>) -> bool {
             match v {
                 None => false,
@@ -829,7 +921,10 @@ options! {CodegenOptions, CodegenSetter, basic_codegen_options,
         "extra arguments to append to the linker invocation (space separated)"),
     link_dead_code: bool = (false, parse_bool, [UNTRACKED],
         "don't let linker strip dead code (turning it on can be used for code coverage)"),
-    lto: bool = (false, parse_bool, [TRACKED],
+    strip: Strip = (Strip::None, parse_strip, [UNTRACKED],
+        "tell the linker to strip the debuginfo, or the debuginfo and the symbol table, \
+         of the output"),
+    lto: Lto = (Lto::No, parse_lto, [TRACKED],
         "perform LLVM link-time optimizations"),
     target_cpu: Option<String> = (None, parse_opt_string, [TRACKED],
         "select target processor (rustc --print target-cpus for details)"),
@@ -886,6 +981,13 @@ options! {CodegenOptions, CodegenSetter, basic_codegen_options,
         "set the inlining threshold for"),
     panic: Option<PanicStrategy> = (None, parse_panic_strategy,
         [TRACKED], "panic strategy to compile crate with"),
+    profile_generate: Option<PathBuf> = (None, parse_opt_pathbuf, [TRACKED],
+        "instrument the generated code to write profiling data into this directory"),
+    profile_use: Option<PathBuf> = (None, parse_opt_pathbuf, [TRACKED],
+        "optimize the generated code with the profiling data of this `.profdata` file"),
+    split_debuginfo: SplitDebuginfo = (SplitDebuginfo::Off, parse_split_debuginfo, [TRACKED],
+        "split the debuginfo off of the object files into `.dwo` files, \
+         optionally packaged into a `.dwp` file (ELF targets only)"),
 }
 
 options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
@@ -916,6 +1018,8 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "skip LLVM verification"),
     borrowck_stats: bool = (false, parse_bool, [UNTRACKED],
         "gather borrowck statistics"),
+    borrowck_mir: bool = (false, parse_bool, [UNTRACKED],
+        "also run the MIR-based borrow checker, reporting its errors with a `(Mir)` suffix"),
     no_landing_pads: bool = (false, parse_bool, [TRACKED],
         "omit landing pads for unwinding"),
     debug_llvm: bool = (false, parse_bool, [UNTRACKED],
@@ -997,8 +1101,27 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "print layout information for each type encountered"),
     print_trans_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
           "print the result of the translation item collection pass"),
+    print_partition_stats: bool = (false, parse_bool, [UNTRACKED],
+          "print the number of items and the estimated size of each codegen unit"),
+    hashed_partitioning: Option<usize> = (None, parse_opt_uint, [TRACKED],
+          "when compiling incrementally, spread the instances of generic functions over \
+           this many codegen units per module, picked by hashing the function's path"),
+    share_generics: Option<bool> = (None, parse_opt_bool, [TRACKED],
+          "make the generic instances translated by this crate available to downstream \
+           crates, and reuse the ones upstream crates make available (default: yes at \
+           opt-level=0)"),
     mir_opt_level: usize = (1, parse_uint, [TRACKED],
           "set the MIR optimization level (0-3, default: 1)"),
+    inline_mir_threshold: Option<usize> = (None, parse_opt_uint, [TRACKED],
+          "the estimated size under which the MIR inliner inlines a function (default: 50)"),
+    inline_mir_hint_threshold: Option<usize> = (None, parse_opt_uint, [TRACKED],
+          "the estimated size under which the MIR inliner inlines a function marked \
+           `#[inline]` (default: 100)"),
+    print_inline_decisions: bool = (false, parse_bool, [UNTRACKED],
+          "print whether the MIR inliner inlined each call it considered, and why"),
+    validate_mir: bool = (false, parse_bool, [UNTRACKED],
+          "check that the MIR is well-formed after each pass, and report a bug naming the \
+           pass otherwise"),
     dump_mir: Option<String> = (None, parse_opt_string, [UNTRACKED],
           "dump MIR state at various points in translation"),
     dump_mir_dir: Option<String> = (None, parse_opt_string, [UNTRACKED],
@@ -1007,6 +1130,13 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "if set, exclude the pass number when dumping MIR (used in tests)"),
     perf_stats: bool = (false, parse_bool, [UNTRACKED],
           "print some performance-related statistics"),
+    dump_query_stats: bool = (false, parse_bool, [UNTRACKED],
+          "print how often each query was run and cached, and how long it took"),
+    dump_query_stats_keys: usize = (10, parse_uint, [UNTRACKED],
+          "the number of most expensive keys to print per query with -Z dump-query-stats"),
+    self_profile: bool = (false, parse_bool, [UNTRACKED],
+          "write a Chrome trace of the passes and queries of the compilation to \
+           `<crate>.trace.json`"),
     hir_stats: bool = (false, parse_bool, [UNTRACKED],
           "print some statistics about AST and HIR"),
     mir_stats: bool = (false, parse_bool, [UNTRACKED],
@@ -1019,6 +1149,11 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
                                    "Use a sanitizer"),
     linker_flavor: Option<LinkerFlavor> = (None, parse_linker_flavor, [UNTRACKED],
                                            "Linker flavor"),
+    thinlto: bool = (false, parse_bool, [TRACKED],
+        "perform ThinLTO between the codegen units of the crate"),
+    instrument_coverage: bool = (false, parse_bool, [TRACKED],
+        "instrument the generated code for source-based code coverage, \
+         writing the coverage counters to `default_%m.profraw` on exit"),
     fuel: Option<(String, u64)> = (None, parse_optimization_fuel, [TRACKED],
         "Set the optimization fuel quota for a crate."),
     print_fuel: Option<String> = (None, parse_opt_string, [TRACKED],
@@ -1033,6 +1168,12 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "a single extra argument to prepend the linker invocation (can be used several times)"),
     pre_link_args: Option<Vec<String>> = (None, parse_opt_list, [UNTRACKED],
         "extra arguments to prepend to the linker invocation (space separated)"),
+    export_symbols_file: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "export only the symbols listed in this file, one per line, from a cdylib"),
+    apply_suggestions: bool = (false, parse_bool, [UNTRACKED],
+        "rewrite the source files with all machine-applicable suggestions"),
+    max_diagnostics_per_lint: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "print at most N diagnostics for each lint, and a summary of the suppressed ones"),
 }
 
 pub fn default_lib_output() -> CrateType {
@@ -1210,7 +1351,8 @@ mod opt {
 pub fn rustc_short_optgroups() -> Vec<RustcOptGroup> {
     let mut print_opts = vec!["crate-name", "file-names", "sysroot", "cfg",
                               "target-list", "target-cpus", "target-features",
-                              "relocation-models", "code-models"];
+                              "relocation-models", "code-models", "deps", "features",
+                              "exported-symbols"];
     if nightly_options::is_nightly_build() {
         print_opts.push("target-spec-json");
     }
@@ -1242,7 +1384,9 @@ pub fn rustc_short_optgroups() -> Vec<RustcOptGroup> {
         opt::opt_s("",  "out-dir", "Write output to compiler-chosen filename \
                                 in <dir>", "DIR"),
         opt::opt_s("", "explain", "Provide a detailed explanation of an error \
-                               message", "OPT"),
+                               message, list all error codes with `list`, or \
+                               search their explanations with `search:KEYWORD`",
+                   "CODE|list|search:KEYWORD"),
         opt::flag_s("", "test", "Build a test harness"),
         opt::opt_s("", "target", "Target triple for which the code is compiled", "TARGET"),
         opt::multi_s("W", "warn", "Set lint warnings", "OPT"),
@@ -1267,10 +1411,11 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
         opt::multi_s("", "extern", "Specify where an external rust library is located",
                      "NAME=PATH"),
         opt::opt_s("", "sysroot", "Override the system root", "PATH"),
+        opt::opt("", "lint-config", "Read lint levels from a TOML configuration file", "FILE"),
         opt::multi("Z", "", "Set internal debugging options", "FLAG"),
         opt::opt_s("", "error-format",
                       "How errors and other messages are produced",
-                      "human|json"),
+                      "human|json|sarif|short"),
         opt::opt_s("", "color", "Configure coloring of output:
                                  auto   = colorize, if output goes to a tty (default);
                                  always = always colorize output;
@@ -1339,13 +1484,15 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
         match matches.opt_str("error-format").as_ref().map(|s| &s[..]) {
             Some("human")   => ErrorOutputType::HumanReadable(color),
             Some("json") => ErrorOutputType::Json,
+            Some("sarif") => ErrorOutputType::Sarif,
+            Some("short") => ErrorOutputType::Short(color),
 
             None => ErrorOutputType::HumanReadable(color),
 
             Some(arg) => {
                 early_error(ErrorOutputType::HumanReadable(color),
-                            &format!("argument for --error-format must be human or json (instead \
-                                      was `{}`)",
+                            &format!("argument for --error-format must be human, json, \
+                                      sarif or short (instead was `{}`)",
                                      arg))
             }
         }
@@ -1376,6 +1523,12 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
         })
     });
 
+    let lint_config = matches.opt_str("lint-config").map(|path| {
+        lint::LintConfig::load(&PathBuf::from(path)).unwrap_or_else(|e| {
+            early_error(error_format, &e)
+        })
+    });
+
     let debugging_opts = build_debugging_options(matches, error_format);
 
     let mut output_types = BTreeMap::new();
@@ -1566,6 +1719,9 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
             "target-features" => PrintRequest::TargetFeatures,
             "relocation-models" => PrintRequest::RelocationModels,
             "code-models" => PrintRequest::CodeModels,
+            "deps" => PrintRequest::Deps,
+            "features" => PrintRequest::Features,
+            "exported-symbols" => PrintRequest::ExportedSymbols,
             "target-spec-json" if nightly_options::is_unstable_enabled(matches)
                 => PrintRequest::TargetSpec,
             req => {
@@ -1579,6 +1735,35 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
                                 --debuginfo");
     }
 
+    if cg.profile_generate.is_some() && cg.profile_use.is_some() {
+        early_error(error_format, "options `-C profile-generate` and `-C profile-use` \
+                                   are exclusive");
+    }
+
+    if debugging_opts.hashed_partitioning == Some(0) {
+        early_error(error_format, "value for `-Z hashed-partitioning` must be a positive \
+                                   nonzero integer");
+    }
+
+    if debugging_opts.instrument_coverage && cg.profile_generate.is_some() {
+        early_error(error_format, "options `-Z instrument-coverage` and \
+                                   `-C profile-generate` are exclusive");
+    }
+
+    if let Some(ref path) = cg.profile_use {
+        if !path.exists() {
+            early_error(error_format, &format!("file `{}` passed to `-C profile-use` \
+                                                does not exist", path.display()));
+        }
+    }
+
+    // LLVM only adds the instrumentation, or uses the profile, in its
+    // optimization pipeline.
+    if (cg.profile_generate.is_some() || cg.profile_use.is_some()) && opt_level == OptLevel::No {
+        early_warn(error_format, "profile-guided optimization has no effect without \
+                                  optimizations");
+    }
+
     let mut externs = BTreeMap::new();
     for arg in &matches.opt_strs("extern") {
         let mut parts = arg.splitn(2, '=');
@@ -1606,6 +1791,7 @@ pub fn build_session_options_and_crate_config(matches: &getopts::Matches)
         debuginfo: debuginfo,
         lint_opts: lint_opts,
         lint_cap: lint_cap,
+        lint_config: lint_config,
         describe_lints: describe_lints,
         output_types: OutputTypes(output_types),
         search_paths: search_paths,
@@ -1755,7 +1941,7 @@ mod dep_tracking {
     use std::path::PathBuf;
     use std::collections::hash_map::DefaultHasher;
     use super::{Passes, CrateType, OptLevel, DebugInfoLevel,
-                OutputTypes, Externs, ErrorOutputType, Sanitizer};
+                OutputTypes, Externs, ErrorOutputType, Sanitizer, Lto, SplitDebuginfo};
     use syntax::feature_gate::UnstableFeatures;
     use rustc_back::PanicStrategy;
 
@@ -1800,6 +1986,7 @@ mod dep_tracking {
     impl_dep_tracking_hash_via_hash!(Option<(String, u64)>);
     impl_dep_tracking_hash_via_hash!(Option<PanicStrategy>);
     impl_dep_tracking_hash_via_hash!(Option<lint::Level>);
+    impl_dep_tracking_hash_via_hash!(Option<lint::LintConfig>);
     impl_dep_tracking_hash_via_hash!(Option<PathBuf>);
     impl_dep_tracking_hash_via_hash!(Option<cstore::NativeLibraryKind>);
     impl_dep_tracking_hash_via_hash!(CrateType);
@@ -1813,6 +2000,8 @@ mod dep_tracking {
     impl_dep_tracking_hash_via_hash!(cstore::NativeLibraryKind);
     impl_dep_tracking_hash_via_hash!(Sanitizer);
     impl_dep_tracking_hash_via_hash!(Option<Sanitizer>);
+    impl_dep_tracking_hash_via_hash!(Lto);
+    impl_dep_tracking_hash_via_hash!(SplitDebuginfo);
 
     impl_dep_tracking_hash_for_sortable_vec_of!(String);
     impl_dep_tracking_hash_for_sortable_vec_of!(CrateType);
@@ -1884,7 +2073,7 @@ mod tests {
     use std::iter::FromIterator;
     use std::path::PathBuf;
     use std::rc::Rc;
-    use super::{OutputType, OutputTypes, Externs};
+    use super::{OutputType, OutputTypes, Externs, Lto, SplitDebuginfo, Strip};
     use rustc_back::PanicStrategy;
     use syntax::symbol::Symbol;
 
@@ -2339,6 +2528,8 @@ mod tests {
 
         opts.cg.link_dead_code = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.cg.strip = Strip::Symbols;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
 
         opts.cg.rpath = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
@@ -2359,7 +2550,7 @@ mod tests {
 
         // Make sure changing a [TRACKED] option changes the hash
         opts = reference.clone();
-        opts.cg.lto = true;
+        opts.cg.lto = Lto::Fat;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
 
         opts = reference.clone();
@@ -2441,6 +2632,18 @@ mod tests {
         opts = reference.clone();
         opts.cg.panic = Some(PanicStrategy::Abort);
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.cg.profile_generate = Some(PathBuf::from("abc"));
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.cg.profile_use = Some(PathBuf::from("abc"));
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.cg.split_debuginfo = SplitDebuginfo::Packed;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
     }
 
     #[test]
@@ -2463,6 +2666,8 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.borrowck_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.borrowck_mir = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.debug_llvm = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.meta_stats = true;
@@ -2519,6 +2724,12 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir_dir = Some(String::from("abc"));
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.print_partition_stats = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.print_inline_decisions = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.validate_mir = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
 
         // Make sure changing a [TRACKED] option changes the hash
         opts = reference.clone();
@@ -2564,5 +2775,21 @@ mod tests {
         opts = reference.clone();
         opts.debugging_opts.mir_opt_level = 3;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.instrument_coverage = true;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.hashed_partitioning = Some(4);
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.inline_mir_threshold = Some(200);
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.share_generics = Some(true);
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
     }
 }
@@ -27,6 +27,7 @@ use syntax::ast::NodeId;
 use errors::{self, DiagnosticBuilder};
 use errors::emitter::{Emitter, EmitterWriter};
 use syntax::json::JsonEmitter;
+use syntax::sarif::SarifEmitter;
 use syntax::feature_gate;
 use syntax::parse;
 use syntax::parse::ParseSess;
@@ -35,7 +36,7 @@ use syntax::{ast, codemap};
 use syntax::feature_gate::AttributeType;
 use syntax_pos::{Span, MultiSpan};
 
-use rustc_back::{LinkerFlavor, PanicStrategy};
+use rustc_back::{LinkerFlavor, LldFlavor, PanicStrategy};
 use rustc_back::target::Target;
 use rustc_data_structures::flock;
 
@@ -331,9 +332,9 @@ impl Session {
                                        diag_builder: &'b mut DiagnosticBuilder<'a>,
                                        lint: &'static lint::Lint, span: Span, message: &str) {
         match self.opts.error_format {
-            // when outputting JSON for tool consumption, the tool might want
-            // the duplicates
-            config::ErrorOutputType::Json => {
+            // when outputting JSON or SARIF for tool consumption, the tool
+            // might want the duplicates
+            config::ErrorOutputType::Json | config::ErrorOutputType::Sarif => {
                 diag_builder.span_note(span, &message);
             },
             _ => {
@@ -366,8 +367,13 @@ impl Session {
     pub fn print_llvm_passes(&self) -> bool {
         self.opts.debugging_opts.print_llvm_passes
     }
-    pub fn lto(&self) -> bool {
-        self.opts.cg.lto
+    /// Returns the kind of LTO to perform: `-C lto` if given, otherwise
+    /// `-Z thinlto`.
+    pub fn lto(&self) -> config::Lto {
+        match self.opts.cg.lto {
+            config::Lto::No if self.opts.debugging_opts.thinlto => config::Lto::ThinLocal,
+            lto => lto,
+        }
     }
     /// Returns the panic strategy for this compile session. If the user explicitly selected one
     /// using '-C panic', use that, otherwise use the panic strategy defined by the target.
@@ -375,7 +381,25 @@ impl Session {
         self.opts.cg.panic.unwrap_or(self.target.target.options.panic_strategy)
     }
     pub fn linker_flavor(&self) -> LinkerFlavor {
-        self.opts.debugging_opts.linker_flavor.unwrap_or(self.target.target.linker_flavor)
+        if let Some(flavor) = self.opts.debugging_opts.linker_flavor {
+            return flavor;
+        }
+        let target_flavor = self.target.target.linker_flavor;
+        if let LinkerFlavor::Lld(_) = target_flavor {
+            return target_flavor;
+        }
+        // Recognize LLD by its name, so that `-C linker=rust-lld` is enough to
+        // link with it instead of the target's default linker.
+        let linker = self.opts.cg.linker.as_ref().and_then(|linker| {
+            Path::new(linker).file_name().and_then(|name| name.to_str())
+        }).map(|name| name.trim_right_matches(".exe"));
+        match linker {
+            Some("rust-lld") if target_flavor == LinkerFlavor::Msvc => {
+                LinkerFlavor::Lld(LldFlavor::Link)
+            }
+            Some("rust-lld") | Some("ld.lld") => LinkerFlavor::Lld(LldFlavor::Ld),
+            _ => target_flavor,
+        }
     }
     pub fn no_landing_pads(&self) -> bool {
         self.opts.debugging_opts.no_landing_pads || self.panic_strategy() == PanicStrategy::Abort
@@ -391,6 +415,11 @@ impl Session {
             .or(self.opts.debugging_opts.force_overflow_checks)
             .unwrap_or(self.opts.debug_assertions)
     }
+    /// Whether generic instances are shared between crates, see `-Z share-generics`.
+    pub fn share_generics(&self) -> bool {
+        self.opts.debugging_opts.share_generics
+            .unwrap_or(self.opts.optimize == config::OptLevel::No)
+    }
 
     pub fn must_not_eliminate_frame_pointers(&self) -> bool {
         self.opts.debuginfo != DebugInfoLevel::NoDebugInfo ||
@@ -584,11 +613,23 @@ pub fn build_session_with_codemap(sopts: config::Options,
     let emitter: Box<Emitter> = match (sopts.error_format, emitter_dest) {
         (config::ErrorOutputType::HumanReadable(color_config), None) => {
             Box::new(EmitterWriter::stderr(color_config,
-                                           Some(codemap.clone())))
+                                           Some(codemap.clone()),
+                                           false))
         }
         (config::ErrorOutputType::HumanReadable(_), Some(dst)) => {
             Box::new(EmitterWriter::new(dst,
-                                        Some(codemap.clone())))
+                                        Some(codemap.clone()),
+                                        false))
+        }
+        (config::ErrorOutputType::Short(color_config), None) => {
+            Box::new(EmitterWriter::stderr(color_config,
+                                           Some(codemap.clone()),
+                                           true))
+        }
+        (config::ErrorOutputType::Short(_), Some(dst)) => {
+            Box::new(EmitterWriter::new(dst,
+                                        Some(codemap.clone()),
+                                        true))
         }
         (config::ErrorOutputType::Json, None) => {
             Box::new(JsonEmitter::stderr(Some(registry), codemap.clone()))
@@ -596,12 +637,22 @@ pub fn build_session_with_codemap(sopts: config::Options,
         (config::ErrorOutputType::Json, Some(dst)) => {
             Box::new(JsonEmitter::new(dst, Some(registry), codemap.clone()))
         }
+        (config::ErrorOutputType::Sarif, None) => {
+            Box::new(SarifEmitter::stderr(Some(registry), codemap.clone()))
+        }
+        (config::ErrorOutputType::Sarif, Some(dst)) => {
+            Box::new(SarifEmitter::new(dst, Some(registry), codemap.clone()))
+        }
     };
 
     let diagnostic_handler =
         errors::Handler::with_emitter(can_print_warnings,
                                       treat_err_as_bug,
                                       emitter);
+    if sopts.debugging_opts.apply_suggestions {
+        diagnostic_handler.record_applicable_suggestions();
+    }
+    diagnostic_handler.set_max_diagnostics_per_lint(sopts.debugging_opts.max_diagnostics_per_lint);
 
     build_session_(sopts,
                    dep_graph,
@@ -727,9 +778,16 @@ pub fn early_error(output: config::ErrorOutputType, msg: &str) -> ! {
     let emitter: Box<Emitter> = match output {
         config::ErrorOutputType::HumanReadable(color_config) => {
             Box::new(EmitterWriter::stderr(color_config,
-                                           None))
+                                           None,
+                                           false))
+        }
+        config::ErrorOutputType::Short(color_config) => {
+            Box::new(EmitterWriter::stderr(color_config,
+                                           None,
+                                           true))
         }
         config::ErrorOutputType::Json => Box::new(JsonEmitter::basic()),
+        config::ErrorOutputType::Sarif => Box::new(SarifEmitter::basic()),
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Fatal);
@@ -740,9 +798,16 @@ pub fn early_warn(output: config::ErrorOutputType, msg: &str) {
     let emitter: Box<Emitter> = match output {
         config::ErrorOutputType::HumanReadable(color_config) => {
             Box::new(EmitterWriter::stderr(color_config,
-                                           None))
+                                           None,
+                                           false))
+        }
+        config::ErrorOutputType::Short(color_config) => {
+            Box::new(EmitterWriter::stderr(color_config,
+                                           None,
+                                           true))
         }
         config::ErrorOutputType::Json => Box::new(JsonEmitter::basic()),
+        config::ErrorOutputType::Sarif => Box::new(SarifEmitter::basic()),
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Warning);
@@ -711,7 +711,13 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
             derive_macros: RefCell::new(NodeMap()),
             stability_interner: RefCell::new(FxHashSet()),
             all_traits: RefCell::new(None),
-       }, f)
+       }, |tcx| {
+            let result = f(tcx);
+            if s.opts.debugging_opts.dump_query_stats {
+                tcx.print_query_stats();
+            }
+            result
+       })
     }
 
     pub fn consider_optimizing<T: Fn() -> String>(&self, msg: T) -> bool {
@@ -24,7 +24,9 @@ use ty::item_path;
 use ty::steal::Steal;
 use ty::subst::Substs;
 use ty::fast_reject::SimplifiedType;
-use util::nodemap::{DefIdSet, NodeSet};
+use util::common::duration_to_secs_str;
+use util::nodemap::{DefIdSet, FxHashMap, NodeSet};
+use util::profiling::{self, ProfileCategory};
 
 use rustc_data_structures::indexed_vec::IndexVec;
 use std::cell::{RefCell, RefMut};
@@ -34,6 +36,7 @@ use std::mem;
 use std::collections::BTreeMap;
 use std::ops::Deref;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 use syntax_pos::{Span, DUMMY_SP};
 use syntax::attr;
 use syntax::ast;
@@ -42,6 +45,8 @@ use syntax::symbol::Symbol;
 pub trait Key: Clone + Hash + Eq + Debug {
     fn map_crate(&self) -> CrateNum;
     fn default_span(&self, tcx: TyCtxt) -> Span;
+    /// The item the key is about, if any, used to describe it by its `DefPath`.
+    fn query_def_id(&self) -> Option<DefId>;
 }
 
 impl<'tcx> Key for ty::InstanceDef<'tcx> {
@@ -52,6 +57,10 @@ impl<'tcx> Key for ty::InstanceDef<'tcx> {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         tcx.def_span(self.def_id())
     }
+
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.def_id())
+    }
 }
 
 impl<'tcx> Key for ty::Instance<'tcx> {
@@ -62,6 +71,10 @@ impl<'tcx> Key for ty::Instance<'tcx> {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         tcx.def_span(self.def_id())
     }
+
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.def_id())
+    }
 }
 
 impl Key for CrateNum {
@@ -71,6 +84,9 @@ impl Key for CrateNum {
     fn default_span(&self, _: TyCtxt) -> Span {
         DUMMY_SP
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        None
+    }
 }
 
 impl Key for DefId {
@@ -80,6 +96,9 @@ impl Key for DefId {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         tcx.def_span(*self)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(*self)
+    }
 }
 
 impl Key for (DefId, DefId) {
@@ -89,6 +108,9 @@ impl Key for (DefId, DefId) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.1.default_span(tcx)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.1)
+    }
 }
 
 impl Key for (CrateNum, DefId) {
@@ -98,6 +120,9 @@ impl Key for (CrateNum, DefId) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.1.default_span(tcx)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.1)
+    }
 }
 
 impl Key for (DefId, SimplifiedType) {
@@ -107,6 +132,9 @@ impl Key for (DefId, SimplifiedType) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.0.default_span(tcx)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.0)
+    }
 }
 
 impl<'tcx> Key for (DefId, &'tcx Substs<'tcx>) {
@@ -116,6 +144,9 @@ impl<'tcx> Key for (DefId, &'tcx Substs<'tcx>) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.0.default_span(tcx)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.0)
+    }
 }
 
 impl Key for (MirSuite, DefId) {
@@ -125,6 +156,9 @@ impl Key for (MirSuite, DefId) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.1.default_span(tcx)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.1)
+    }
 }
 
 impl Key for (MirSuite, MirPassIndex, DefId) {
@@ -134,6 +168,9 @@ impl Key for (MirSuite, MirPassIndex, DefId) {
     fn default_span(&self, tcx: TyCtxt) -> Span {
         self.2.default_span(tcx)
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        Some(self.2)
+    }
 }
 
 impl<'tcx, T: Clone + Hash + Eq + Debug> Key for ty::ParamEnvAnd<'tcx, T> {
@@ -143,6 +180,9 @@ impl<'tcx, T: Clone + Hash + Eq + Debug> Key for ty::ParamEnvAnd<'tcx, T> {
     fn default_span(&self, _: TyCtxt) -> Span {
         DUMMY_SP
     }
+    fn query_def_id(&self) -> Option<DefId> {
+        None
+    }
 }
 
 trait Value<'tcx>: Sized {
@@ -241,6 +281,87 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
 
         Ok(result)
     }
+
+    /// Records a query that missed the cache for `-Z dump-query-stats`.
+    fn record_computed_query<K: Key>(self,
+                                     query: &'static str,
+                                     key: &K,
+                                     time: Duration,
+                                     cycle_error: bool) {
+        let max_keys = self.sess.opts.debugging_opts.dump_query_stats_keys;
+        let is_top_key = self.maps.with_query_stats(query, |stats| {
+            stats.invocations += 1;
+            if cycle_error {
+                stats.cycle_errors += 1;
+                return false;
+            }
+            stats.time += time;
+            stats.is_top_key(time, max_keys)
+        });
+
+        if is_top_key {
+            // Describing the key may run queries itself, so this can't be
+            // done while the statistics are borrowed.
+            let description = match key.query_def_id() {
+                Some(def_id) => self.def_path(def_id).to_string(self),
+                None => format!("{:?}", key),
+            };
+            self.maps.with_query_stats(query, |stats| {
+                stats.add_top_key(time, description, max_keys)
+            });
+        }
+    }
+}
+
+impl<'a, 'tcx> TyCtxt<'a, 'tcx, 'tcx> {
+    /// Prints the statistics collected for `-Z dump-query-stats`.
+    pub fn print_query_stats(self) {
+        let query_stats = self.maps.query_stats.borrow();
+        let mut queries: Vec<_> = query_stats.iter().collect();
+        queries.sort_by(|a, b| b.1.time.cmp(&a.1.time).then(a.0.cmp(b.0)));
+
+        let width = queries.iter().map(|&(name, _)| name.len()).max().unwrap_or(0);
+        println!("Query stats (times include nested queries):\n");
+        println!("    {:<width$}  {:>11}  {:>10}  {:>12}  {:>8}",
+                 "query", "invocations", "cache hits", "cycle errors", "time (s)",
+                 width = width);
+        for (name, stats) in queries {
+            println!("    {:<width$}  {:>11}  {:>10}  {:>12}  {:>8}",
+                     name, stats.invocations, stats.cache_hits, stats.cycle_errors,
+                     duration_to_secs_str(stats.time),
+                     width = width);
+            for &(time, ref key) in &stats.top_keys {
+                println!("        {}  {}", duration_to_secs_str(time), key);
+            }
+        }
+    }
+}
+
+/// Statistics about one kind of query, collected for `-Z dump-query-stats`.
+#[derive(Default)]
+struct QueryStats {
+    invocations: usize,
+    cache_hits: usize,
+    cycle_errors: usize,
+    /// The time spent computing the query, including nested queries.
+    time: Duration,
+    /// The keys that took the longest to compute, longest first.
+    top_keys: Vec<(Duration, String)>,
+}
+
+impl QueryStats {
+    fn is_top_key(&self, time: Duration, max_keys: usize) -> bool {
+        self.top_keys.len() < max_keys ||
+            self.top_keys.last().map_or(false, |&(shortest, _)| time > shortest)
+    }
+
+    fn add_top_key(&mut self, time: Duration, key: String, max_keys: usize) {
+        let index = self.top_keys.iter()
+                                 .position(|&(t, _)| time > t)
+                                 .unwrap_or(self.top_keys.len());
+        self.top_keys.insert(index, (time, key));
+        self.top_keys.truncate(max_keys);
+    }
 }
 
 trait QueryDescription: DepTrackingMapConfig {
@@ -342,6 +463,12 @@ impl<'tcx> QueryDescription for queries::reachable_set<'tcx> {
     }
 }
 
+impl<'tcx> QueryDescription for queries::upstream_monomorphizations<'tcx> {
+    fn describe(_: TyCtxt, _: CrateNum) -> String {
+        format!("collecting the generic instances shared by upstream crates")
+    }
+}
+
 impl<'tcx> QueryDescription for queries::const_eval<'tcx> {
     fn describe(tcx: TyCtxt, (def_id, _): (DefId, &'tcx Substs<'tcx>)) -> String {
         format!("const-evaluating `{}`", tcx.item_path_str(def_id))
@@ -469,9 +596,16 @@ macro_rules! define_maps {
                 Maps {
                     providers,
                     query_stack: RefCell::new(vec![]),
+                    query_stats: RefCell::new(FxHashMap()),
                     $($name: RefCell::new(DepTrackingMap::new(dep_graph.clone()))),*
                 }
             }
+
+            fn with_query_stats<F, R>(&self, query: &'static str, f: F) -> R
+                where F: FnOnce(&mut QueryStats) -> R
+            {
+                f(self.query_stats.borrow_mut().entry(query).or_insert_with(QueryStats::default))
+            }
         }
 
         #[allow(bad_style)]
@@ -521,7 +655,14 @@ macro_rules! define_maps {
                        key,
                        span);
 
+                let dump_query_stats = tcx.sess.opts.debugging_opts.dump_query_stats;
                 if let Some(result) = tcx.maps.$name.borrow().get(&key) {
+                    if dump_query_stats {
+                        tcx.maps.with_query_stats(stringify!($name), |stats| {
+                            stats.invocations += 1;
+                            stats.cache_hits += 1;
+                        });
+                    }
                     return Ok(f(result));
                 }
 
@@ -534,10 +675,20 @@ macro_rules! define_maps {
 
                 let _task = tcx.dep_graph.in_task(Self::to_dep_node(&key));
 
+                let start = if dump_query_stats { Some(Instant::now()) } else { None };
                 let result = tcx.cycle_check(span, Query::$name(key), || {
                     let provider = tcx.maps.providers[key.map_crate()].$name;
-                    provider(tcx.global_tcx(), key)
-                })?;
+                    profiling::record(ProfileCategory::Query, stringify!($name), || {
+                        provider(tcx.global_tcx(), key)
+                    })
+                });
+                if let Some(start) = start {
+                    tcx.record_computed_query(stringify!($name),
+                                              &key,
+                                              start.elapsed(),
+                                              result.is_err());
+                }
+                let result = result?;
 
                 Ok(f(tcx.maps.$name.borrow_mut().entry(key).or_insert(result)))
             }
@@ -631,6 +782,7 @@ macro_rules! define_map_struct {
         pub struct Maps<$tcx> {
             providers: IndexVec<CrateNum, Providers<$tcx>>,
             query_stack: RefCell<Vec<(Span, Query<$tcx>)>>,
+            query_stats: RefCell<FxHashMap<&'static str, QueryStats>>,
             $($output)*
         }
     };
@@ -873,6 +1025,12 @@ define_maps! { <'tcx>
     [] def_symbol_name: SymbolName(DefId) -> ty::SymbolName,
     [] symbol_name: symbol_name_dep_node(ty::Instance<'tcx>) -> ty::SymbolName,
 
+    /// Maps the instance hashes of the generic instances that upstream crates
+    /// share with the local crate to the crate providing each of them, see
+    /// `-Z share-generics`.
+    [] upstream_monomorphizations: upstream_monomorphizations_dep_node(CrateNum)
+        -> Rc<FxHashMap<u64, CrateNum>>,
+
     [] describe_def: DescribeDef(DefId) -> Option<Def>,
     [] def_span: DefSpan(DefId) -> Span,
     [] stability: Stability(DefId) -> Option<attr::Stability>,
@@ -915,6 +1073,10 @@ fn reachability_dep_node(_: CrateNum) -> DepNode<DefId> {
     DepNode::Reachability
 }
 
+fn upstream_monomorphizations_dep_node(_: CrateNum) -> DepNode<DefId> {
+    DepNode::UpstreamMonomorphizations
+}
+
 fn mir_shim_dep_node(instance: ty::InstanceDef) -> DepNode<DefId> {
     instance.dep_node()
 }
@@ -19,6 +19,8 @@ use std::iter::repeat;
 use std::path::Path;
 use std::time::{Duration, Instant};
 
+use util::profiling::{self, ProfileCategory};
+
 // The name of the associated type for `Fn` return types
 pub const FN_OUTPUT_NAME: &'static str = "Output";
 
@@ -45,7 +47,7 @@ pub fn set_time_depth(depth: usize) {
 pub fn time<T, F>(do_it: bool, what: &str, f: F) -> T where
     F: FnOnce() -> T,
 {
-    if !do_it { return f(); }
+    if !do_it { return profiling::record(ProfileCategory::Pass, what, f); }
 
     let old = TIME_DEPTH.with(|slot| {
         let r = slot.get();
@@ -54,7 +56,7 @@ pub fn time<T, F>(do_it: bool, what: &str, f: F) -> T where
     });
 
     let start = Instant::now();
-    let rv = f();
+    let rv = profiling::record(ProfileCategory::Pass, what, f);
     let dur = start.elapsed();
 
     let mem_string = match get_resident() {
@@ -123,7 +125,7 @@ macro_rules! option_try(
 
 // Memory reporting
 #[cfg(unix)]
-fn get_resident() -> Option<usize> {
+pub fn get_resident() -> Option<usize> {
     use std::fs::File;
     use std::io::Read;
 
@@ -137,7 +139,7 @@ fn get_resident() -> Option<usize> {
 }
 
 #[cfg(windows)]
-fn get_resident() -> Option<usize> {
+pub fn get_resident() -> Option<usize> {
     type BOOL = i32;
     type DWORD = u32;
     type HANDLE = *mut u8;
@@ -0,0 +1,147 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for `-Z self-profile`.
+//!
+//! While profiling is enabled, every pass run through `util::common::time`
+//! and every query computed by `ty::maps` is recorded with its start time and
+//! duration. Passes additionally record the resident set size before and
+//! after they run; queries don't, as reading it is too slow to do for each
+//! of them. The events are written out in the Chrome trace event format,
+//! which trace viewers like `chrome://tracing` can load.
+//!
+//! The profiler is per thread, so only work done on the thread that called
+//! `start` is recorded.
+
+use rustc_serialize::json::{Json, ToJson};
+use util::common::get_resident;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProfileCategory {
+    Pass,
+    Query,
+}
+
+impl ProfileCategory {
+    fn as_str(self) -> &'static str {
+        match self {
+            ProfileCategory::Pass => "pass",
+            ProfileCategory::Query => "query",
+        }
+    }
+}
+
+struct Event {
+    name: String,
+    category: ProfileCategory,
+    start: Duration,
+    duration: Duration,
+    /// The resident set size before and after the event, if known.
+    rss: Option<(usize, usize)>,
+}
+
+struct SelfProfiler {
+    start: Instant,
+    events: Vec<Event>,
+}
+
+thread_local!(static PROFILER: RefCell<Option<SelfProfiler>> = RefCell::new(None));
+
+/// Starts recording events on the current thread.
+pub fn start() {
+    PROFILER.with(|profiler| {
+        *profiler.borrow_mut() = Some(SelfProfiler {
+            start: Instant::now(),
+            events: vec![],
+        });
+    });
+}
+
+pub fn is_enabled() -> bool {
+    PROFILER.with(|profiler| profiler.borrow().is_some())
+}
+
+/// Runs `f`, recording it as an event named `name` if profiling is enabled.
+pub fn record<T, F>(category: ProfileCategory, name: &str, f: F) -> T where
+    F: FnOnce() -> T,
+{
+    let profile_start = match PROFILER.with(|p| p.borrow().as_ref().map(|p| p.start)) {
+        Some(start) => start,
+        None => return f(),
+    };
+
+    let rss_start = if category == ProfileCategory::Pass { get_resident() } else { None };
+    let start = Instant::now();
+    let rv = f();
+    let duration = start.elapsed();
+    let rss = rss_start.and_then(|rss_start| get_resident().map(|rss_end| (rss_start, rss_end)));
+
+    PROFILER.with(|profiler| {
+        if let Some(ref mut profiler) = *profiler.borrow_mut() {
+            profiler.events.push(Event {
+                name: name.to_string(),
+                category: category,
+                start: start.duration_since(profile_start),
+                duration: duration,
+                rss: rss,
+            });
+        }
+    });
+
+    rv
+}
+
+/// Stops recording events on the current thread and writes the events that
+/// were recorded to `path`.
+pub fn finish(path: &Path) -> io::Result<()> {
+    let profiler = match PROFILER.with(|profiler| profiler.borrow_mut().take()) {
+        Some(profiler) => profiler,
+        None => return Ok(()),
+    };
+
+    let events = profiler.events.iter().map(|event| {
+        let mut obj = BTreeMap::new();
+        obj.insert("name".to_string(), event.name.to_json());
+        obj.insert("cat".to_string(), event.category.as_str().to_json());
+        obj.insert("ph".to_string(), "X".to_json());
+        obj.insert("ts".to_string(), micros(event.start).to_json());
+        obj.insert("dur".to_string(), micros(event.duration).to_json());
+        obj.insert("pid".to_string(), 0u32.to_json());
+        obj.insert("tid".to_string(), 0u32.to_json());
+        if let Some((rss_start, rss_end)) = event.rss {
+            let mut args = BTreeMap::new();
+            args.insert("rss_start".to_string(), rss_start.to_json());
+            args.insert("rss_end".to_string(), rss_end.to_json());
+            args.insert("rss_delta".to_string(),
+                        (rss_end as i64 - rss_start as i64).to_json());
+            obj.insert("args".to_string(), Json::Object(args));
+        }
+        Json::Object(obj)
+    }).collect();
+
+    let mut trace = BTreeMap::new();
+    trace.insert("traceEvents".to_string(), Json::Array(events));
+    trace.insert("displayTimeUnit".to_string(), "ms".to_json());
+
+    let mut file = File::create(path)?;
+    writeln!(file, "{}", Json::Object(trace))
+}
+
+/// Trace event timestamps and durations are in microseconds.
+fn micros(d: Duration) -> f64 {
+    d.as_secs() as f64 * 1_000_000.0 + d.subsec_nanos() as f64 / 1_000.0
+}
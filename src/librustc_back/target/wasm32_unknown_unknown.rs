@@ -0,0 +1,56 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// A bare WebAssembly target: no Emscripten, no JS glue and no libc. Modules
+// are linked by LLD, and whatever the module needs from its host is left as
+// an undefined symbol, which becomes an import.
+
+use {LinkerFlavor, LldFlavor, PanicStrategy};
+use super::{LinkArgs, Target, TargetOptions};
+
+pub fn target() -> Result<Target, String> {
+    let mut pre_link_args = LinkArgs::new();
+    pre_link_args.insert(LinkerFlavor::Lld(LldFlavor::Wasm),
+                         vec!["--no-entry".to_string(),
+                              "--allow-undefined".to_string()]);
+
+    let opts = TargetOptions {
+        linker: "lld".to_string(),
+        pre_link_args: pre_link_args,
+
+        dynamic_linking: false,
+        executables: true,
+        exe_suffix: ".wasm".to_string(),
+        dll_prefix: "".to_string(),
+        dll_suffix: ".wasm".to_string(),
+        linker_is_gnu: false,
+        allow_asm: false,
+        // There is nothing to unwind with yet.
+        panic_strategy: PanicStrategy::Abort,
+        max_atomic_width: Some(32),
+        relocation_model: "static".to_string(),
+        function_sections: true,
+        no_default_libraries: false,
+        exe_allocation_crate: "alloc_system".to_string(),
+        .. Default::default()
+    };
+    Ok(Target {
+        llvm_target: "wasm32-unknown-unknown".to_string(),
+        target_endian: "little".to_string(),
+        target_pointer_width: "32".to_string(),
+        target_os: "unknown".to_string(),
+        target_env: "".to_string(),
+        target_vendor: "unknown".to_string(),
+        data_layout: "e-m:e-p:32:32-i64:64-n32:64-S128".to_string(),
+        arch: "wasm32".to_string(),
+        linker_flavor: LinkerFlavor::Lld(LldFlavor::Wasm),
+        options: opts,
+    })
+}
@@ -0,0 +1,400 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The borrows of a MIR body and their regions.
+//!
+//! The region of a borrow is the set of locations at which a reference
+//! derived from it may still be used. It is computed from the liveness of
+//! the locals that may hold such a reference: the local the borrow is
+//! assigned to, and transitively every local that a value mentioning a
+//! holder is stored into, provided the type of that local can contain a
+//! reference at all. Storing into the referent of a `&mut` whose target
+//! is known makes the target a holder; storing anywhere else, like into a
+//! static or through a reference passed in as an argument, makes the
+//! borrow escape, and its region is the whole function.
+
+use rustc::mir::{self, BorrowKind, Local, Location, Lvalue, Mir, ProjectionElem};
+use rustc::mir::{Rvalue, StatementKind, TerminatorKind};
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc::util::nodemap::FxHashMap;
+use rustc_data_structures::indexed_set::IdxSetBuf;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+
+use super::liveness::LocalLiveness;
+
+use std::fmt;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BorrowIndex(usize);
+
+impl Idx for BorrowIndex {
+    fn new(idx: usize) -> Self { BorrowIndex(idx) }
+    fn index(self) -> usize { self.0 }
+}
+
+impl fmt::Debug for BorrowIndex {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "bw{}", self.0)
+    }
+}
+
+pub struct BorrowData<'tcx> {
+    pub location: Location,
+    pub kind: BorrowKind,
+    pub lvalue: Lvalue<'tcx>,
+
+    /// The locals that may hold a reference derived from this borrow.
+    holders: IdxSetBuf<Local>,
+
+    /// Whether a reference derived from this borrow may be stored
+    /// somewhere that isn't tracked.
+    escapes: bool,
+}
+
+impl<'tcx> fmt::Debug for BorrowData<'tcx> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            BorrowKind::Shared => "",
+            BorrowKind::Unique => "uniq ",
+            BorrowKind::Mut => "mut ",
+        };
+        write!(fmt, "&{}{:?} at {:?}", kind, self.lvalue, self.location)
+    }
+}
+
+pub struct BorrowSet<'tcx> {
+    pub borrows: IndexVec<BorrowIndex, BorrowData<'tcx>>,
+    pub location_map: FxHashMap<Location, BorrowIndex>,
+}
+
+impl<'tcx> BorrowSet<'tcx> {
+    pub fn build<'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>, mir: &Mir<'tcx>) -> BorrowSet<'tcx> {
+        let flows = Flows::build(tcx, mir);
+
+        let mut borrows = IndexVec::new();
+        let mut location_map = FxHashMap();
+        for (block, data) in mir.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                let (dest, kind, lvalue) = match statement.kind {
+                    StatementKind::Assign(ref dest, Rvalue::Ref(_, kind, ref lvalue)) => {
+                        (dest, kind, lvalue)
+                    }
+                    _ => continue
+                };
+                let location = Location { block: block, statement_index: statement_index };
+                let mut borrow = BorrowData {
+                    location: location,
+                    kind: kind,
+                    lvalue: lvalue.clone(),
+                    holders: IdxSetBuf::new_empty(mir.local_decls.len()),
+                    escapes: false,
+                };
+                flows.store(&mut borrow, flows.store_targets(dest));
+                flows.propagate(&mut borrow);
+                debug!("BorrowSet::build: {:?} held by {:?}, escapes: {}",
+                       borrow, borrow.holders, borrow.escapes);
+                location_map.insert(location, borrows.push(borrow));
+            }
+        }
+
+        BorrowSet {
+            borrows: borrows,
+            location_map: location_map,
+        }
+    }
+
+    /// Whether `location` is in the region of `borrow`.
+    pub fn region_contains(&self,
+                           borrow: BorrowIndex,
+                           liveness: &LocalLiveness,
+                           location: Location)
+                           -> bool {
+        let borrow = &self.borrows[borrow];
+        borrow.escapes || liveness.live_at(location).words().iter()
+            .zip(borrow.holders.words())
+            .any(|(live, holders)| live & holders != 0)
+    }
+}
+
+/// An assignment of a value computed from `sources` to `targets`, or to
+/// an untracked location if `targets` is `None`.
+struct Flow {
+    sources: Vec<Local>,
+    targets: Option<Vec<Local>>,
+}
+
+struct Flows<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    mir: &'a Mir<'tcx>,
+    flows: Vec<Flow>,
+
+    /// The locals that a local holding a `&mut` may point into.
+    mut_targets: IndexVec<Local, Vec<Local>>,
+}
+
+impl<'a, 'tcx> Flows<'a, 'tcx> {
+    fn build(tcx: TyCtxt<'a, 'tcx, 'tcx>, mir: &'a Mir<'tcx>) -> Flows<'a, 'tcx> {
+        let mut flows = Flows {
+            tcx: tcx,
+            mir: mir,
+            flows: vec![],
+            mut_targets: IndexVec::from_elem_n(vec![], mir.local_decls.len()),
+        };
+        flows.compute_mut_targets();
+
+        for (block, data) in mir.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                if let StatementKind::Assign(ref dest, ref rvalue) = statement.kind {
+                    let location = Location { block: block, statement_index: statement_index };
+                    let mut sources = vec![];
+                    Mentions(&mut sources).visit_rvalue(rvalue, location);
+                    flows.add_store(sources, dest);
+                }
+            }
+
+            let location = Location { block: block, statement_index: data.statements.len() };
+            match data.terminator().kind {
+                TerminatorKind::DropAndReplace { location: ref dest, ref value, .. } => {
+                    let mut sources = vec![];
+                    Mentions(&mut sources).visit_operand(value, location);
+                    flows.add_store(sources, dest);
+                }
+                TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                    let mut sources = vec![];
+                    Mentions(&mut sources).visit_operand(func, location);
+                    let arg_sources: Vec<_> = args.iter().map(|arg| {
+                        let mut arg_sources = sources.clone();
+                        Mentions(&mut arg_sources).visit_operand(arg, location);
+                        arg_sources
+                    }).collect();
+                    for arg in args {
+                        Mentions(&mut sources).visit_operand(arg, location);
+                    }
+                    if let Some((ref dest, _)) = *destination {
+                        flows.add_store(sources, dest);
+                    }
+
+                    // The callee can store its other arguments into what the
+                    // `&mut` ones point to, as far as its signature allows.
+                    let sig = match func.ty(mir, tcx).sty {
+                        ty::TyFnDef(def_id, ..) => tcx.type_of(def_id).fn_sig(),
+                        _ => func.ty(mir, tcx).fn_sig(),
+                    };
+                    let inputs = sig.skip_binder().inputs();
+                    for (i, arg) in args.iter().enumerate() {
+                        if !has_mut_ref(arg.ty(mir, tcx)) {
+                            continue;
+                        }
+                        let targets = match *arg {
+                            mir::Operand::Consume(Lvalue::Local(local)) => {
+                                let targets = &flows.mut_targets[local];
+                                if targets.is_empty() { None } else { Some(targets.clone()) }
+                            }
+                            _ => None
+                        };
+                        let mut sources = vec![];
+                        for (j, arg_sources) in arg_sources.iter().enumerate() {
+                            let may_store = match (inputs.get(j), inputs.get(i)) {
+                                (Some(&source), Some(&target)) => may_store_into(source, target),
+                                _ => true,
+                            };
+                            if i != j && may_store {
+                                sources.extend(arg_sources);
+                            }
+                        }
+                        flows.flows.push(Flow { sources: sources, targets: targets });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        flows
+    }
+
+    /// Computes what the `&mut`s held by locals point into, following
+    /// reborrows and moves of such references.
+    fn compute_mut_targets(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for data in self.mir.basic_blocks() {
+                for statement in &data.statements {
+                    let (dest, targets) = match statement.kind {
+                        StatementKind::Assign(_, Rvalue::Ref(_, BorrowKind::Shared, _)) => {
+                            continue;
+                        }
+                        StatementKind::Assign(Lvalue::Local(dest), Rvalue::Ref(_, _, ref lv)) => {
+                            let targets = match self.store_targets(lv) {
+                                Some(targets) => targets,
+                                None => continue
+                            };
+                            (dest, targets)
+                        }
+                        StatementKind::Assign(Lvalue::Local(dest),
+                                              Rvalue::Use(mir::Operand::Consume(
+                                                  Lvalue::Local(source)))) => {
+                            (dest, self.mut_targets[source].clone())
+                        }
+                        _ => continue
+                    };
+                    for target in targets {
+                        if !self.mut_targets[dest].contains(&target) {
+                            self.mut_targets[dest].push(target);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The locals that a store to `lvalue` writes into, or `None` if the
+    /// store is to an untracked location.
+    fn store_targets(&self, lvalue: &Lvalue<'tcx>) -> Option<Vec<Local>> {
+        match *lvalue {
+            Lvalue::Local(local) => Some(vec![local]),
+            Lvalue::Static(_) => None,
+            Lvalue::Projection(ref proj) => {
+                let base_ty = proj.base.ty(self.mir, self.tcx).to_ty(self.tcx);
+                match (&proj.elem, &proj.base) {
+                    (&ProjectionElem::Deref, _) if base_ty.is_box() => {
+                        self.store_targets(&proj.base)
+                    }
+                    (&ProjectionElem::Deref, &Lvalue::Local(local)) => {
+                        let targets = &self.mut_targets[local];
+                        if targets.is_empty() { None } else { Some(targets.clone()) }
+                    }
+                    (&ProjectionElem::Deref, _) => None,
+                    _ => self.store_targets(&proj.base)
+                }
+            }
+        }
+    }
+
+    fn add_store(&mut self, sources: Vec<Local>, dest: &Lvalue<'tcx>) {
+        let dest_ty = dest.ty(self.mir, self.tcx).to_ty(self.tcx);
+        if sources.is_empty() || !may_hold_reference(dest_ty) {
+            return;
+        }
+        let targets = self.store_targets(dest);
+        self.flows.push(Flow { sources: sources, targets: targets });
+    }
+
+    fn store(&self, borrow: &mut BorrowData<'tcx>, targets: Option<Vec<Local>>) {
+        match targets {
+            Some(targets) => for target in targets {
+                borrow.holders.add(&target);
+            },
+            None => borrow.escapes = true
+        }
+    }
+
+    /// Adds the locals the holders of `borrow` flow into to its holders.
+    fn propagate(&self, borrow: &mut BorrowData<'tcx>) {
+        let mut changed = true;
+        while changed && !borrow.escapes {
+            changed = false;
+            for flow in &self.flows {
+                if !flow.sources.iter().any(|source| borrow.holders.contains(source)) {
+                    continue;
+                }
+                match flow.targets {
+                    Some(ref targets) => for target in targets {
+                        changed |= borrow.holders.add(target);
+                    },
+                    None => {
+                        borrow.escapes = true;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether a value of type `ty` may contain a reference. Type parameters
+/// and trait objects may, as may ADTs with region parameters.
+fn may_hold_reference(ty: Ty) -> bool {
+    ty.walk().any(|ty| match ty.sty {
+        ty::TyRef(..) |
+        ty::TyDynamic(..) |
+        ty::TyClosure(..) |
+        ty::TyParam(..) |
+        ty::TyProjection(..) |
+        ty::TyAnon(..) => true,
+        ty::TyAdt(_, substs) => substs.regions().next().is_some(),
+        _ => false
+    })
+}
+
+/// Whether a function with a parameter of type `target` can store a value
+/// of type `source` into what it points to, judging from the regions and
+/// the types possibly hiding regions that they have in common.
+fn may_store_into<'tcx>(source: Ty<'tcx>, target: Ty<'tcx>) -> bool {
+    let target = match target.sty {
+        ty::TyRef(_, ty::TypeAndMut { ty, .. }) => ty,
+        _ => target,
+    };
+    let (source_regions, source_opaque) = regions_in(source);
+    let (target_regions, target_opaque) = regions_in(target);
+    if target_opaque {
+        source_opaque || !source_regions.is_empty()
+    } else {
+        source_regions.iter().any(|region| target_regions.contains(region))
+    }
+}
+
+/// The regions in `ty`, and whether it has types that may hide regions.
+fn regions_in<'tcx>(ty: Ty<'tcx>) -> (Vec<ty::Region<'tcx>>, bool) {
+    let mut regions = vec![];
+    let mut opaque = false;
+    for ty in ty.walk() {
+        match ty.sty {
+            ty::TyRef(region, _) => regions.push(region),
+            ty::TyAdt(_, substs) => regions.extend(substs.regions()),
+            ty::TyDynamic(_, region) => {
+                regions.push(region);
+                opaque = true;
+            }
+            ty::TyClosure(..) |
+            ty::TyParam(..) |
+            ty::TyProjection(..) |
+            ty::TyAnon(..) => opaque = true,
+            _ => {}
+        }
+    }
+    (regions, opaque)
+}
+
+fn has_mut_ref(ty: Ty) -> bool {
+    ty.walk().any(|ty| match ty.sty {
+        ty::TyRef(_, ty::TypeAndMut { mutbl: ::rustc::hir::MutMutable, .. }) => true,
+        _ => false
+    })
+}
+
+/// Collects the locals mentioned in what it visits.
+struct Mentions<'v>(&'v mut Vec<Local>);
+
+impl<'v, 'tcx> Visitor<'tcx> for Mentions<'v> {
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let Lvalue::Local(local) = *lvalue {
+            if !self.0.contains(&local) {
+                self.0.push(local);
+            }
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+}
@@ -0,0 +1,467 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The checks of the MIR borrow checker.
+//!
+//! Each block is walked with the state of the initialization and borrow
+//! dataflow analyses on entry to it, and every access to an lvalue is
+//! checked against the initialization state of the lvalue and against
+//! the borrows in scope. Errors have a `(Mir)` suffix so that they can be
+//! told apart from the ones of the AST borrow checker, which still runs.
+
+use syntax_pos::{Span, DUMMY_SP};
+
+use rustc::mir::{AssertMessage, BasicBlock, BorrowKind, Location, Lvalue, Operand};
+use rustc::mir::{ProjectionElem, Rvalue, Statement, StatementKind, Terminator};
+use rustc::mir::TerminatorKind;
+use rustc::ty;
+use rustc_data_structures::indexed_vec::Idx;
+
+use super::MirBorrowckCtxt;
+use super::borrows::{BorrowData, BorrowIndex};
+use super::gather_moves::{LookupResult, MovePathIndex};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Access {
+    /// Reading a `Copy` value, its length or its discriminant.
+    Read,
+    /// Taking a reference.
+    Borrow(BorrowKind),
+    /// Moving a value out.
+    Move,
+    /// Overwriting a value.
+    Write,
+    /// Dropping a value or ending its storage.
+    Drop,
+}
+
+impl<'b, 'a: 'b, 'tcx: 'a> MirBorrowckCtxt<'b, 'a, 'tcx> {
+    pub fn process_basic_block(&mut self, bb: BasicBlock) {
+        let mir = self.mir;
+        self.flow_inits.reset_to_entry_of(bb);
+        self.flow_uninits.reset_to_entry_of(bb);
+        self.flow_borrows.reset_to_entry_of(bb);
+
+        let data = &mir[bb];
+        for (statement_index, statement) in data.statements.iter().enumerate() {
+            self.process_statement(Location { block: bb, statement_index: statement_index },
+                                   statement);
+            self.apply_effects(bb, statement_index, false);
+        }
+
+        let location = Location { block: bb, statement_index: data.statements.len() };
+        self.process_terminator(location, data.terminator());
+    }
+
+    fn apply_effects(&mut self, bb: BasicBlock, idx: usize, is_terminator: bool) {
+        self.flow_inits.apply_effect(bb, idx, is_terminator);
+        self.flow_uninits.apply_effect(bb, idx, is_terminator);
+        self.flow_borrows.apply_effect(bb, idx, is_terminator);
+    }
+
+    fn process_statement(&mut self, location: Location, stmt: &Statement<'tcx>) {
+        debug!("MirBorrowckCtxt::process_statement({:?}, {:?})", location, stmt);
+        let span = stmt.source_info.span;
+        match stmt.kind {
+            StatementKind::Assign(ref lhs, ref rhs) => {
+                self.consume_rvalue(location, span, rhs);
+                // The right-hand side is evaluated before the assignment, so
+                // borrows only used by it don't conflict with the assignment.
+                let after = Location {
+                    block: location.block,
+                    statement_index: location.statement_index + 1,
+                };
+                self.access_at(location, after, span, lhs, Access::Write);
+            }
+            StatementKind::SetDiscriminant { ref lvalue, .. } => {
+                self.access(location, span, lvalue, Access::Write);
+            }
+            StatementKind::InlineAsm { ref outputs, ref inputs, .. } => {
+                for input in inputs {
+                    self.consume_operand(location, span, input);
+                }
+                for output in outputs {
+                    self.access(location, span, output, Access::Write);
+                }
+            }
+            StatementKind::StorageDead(ref lvalue) => {
+                self.access(location, span, lvalue, Access::Drop);
+            }
+            StatementKind::StorageLive(_) |
+            StatementKind::Nop => {}
+        }
+    }
+
+    fn process_terminator(&mut self, location: Location, term: &Terminator<'tcx>) {
+        debug!("MirBorrowckCtxt::process_terminator({:?}, {:?})", location, term);
+        let span = term.source_info.span;
+        match term.kind {
+            TerminatorKind::SwitchInt { ref discr, .. } => {
+                self.consume_operand(location, span, discr);
+            }
+            TerminatorKind::Drop { location: ref lvalue, .. } => {
+                self.access(location, span, lvalue, Access::Drop);
+            }
+            TerminatorKind::DropAndReplace { location: ref lvalue, ref value, .. } => {
+                self.consume_operand(location, span, value);
+                self.access(location, span, lvalue, Access::Write);
+            }
+            TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                self.consume_operand(location, span, func);
+                for arg in args {
+                    self.consume_operand(location, span, arg);
+                }
+                if let Some((ref dest, target)) = *destination {
+                    let after = Location { block: target, statement_index: 0 };
+                    self.access_at(location, after, span, dest, Access::Write);
+                }
+            }
+            TerminatorKind::Assert { ref cond, ref msg, .. } => {
+                self.consume_operand(location, span, cond);
+                if let AssertMessage::BoundsCheck { ref len, ref index } = *msg {
+                    self.consume_operand(location, span, len);
+                    self.consume_operand(location, span, index);
+                }
+            }
+            TerminatorKind::Goto { .. } |
+            TerminatorKind::Resume |
+            TerminatorKind::Return |
+            TerminatorKind::Unreachable => {}
+        }
+    }
+
+    fn consume_rvalue(&mut self, location: Location, span: Span, rvalue: &Rvalue<'tcx>) {
+        match *rvalue {
+            Rvalue::Ref(_, kind, ref lvalue) => {
+                self.access(location, span, lvalue, Access::Borrow(kind));
+            }
+            Rvalue::Len(ref lvalue) |
+            Rvalue::Discriminant(ref lvalue) => {
+                self.access(location, span, lvalue, Access::Read);
+            }
+            Rvalue::Use(ref operand) |
+            Rvalue::Repeat(ref operand, _) |
+            Rvalue::UnaryOp(_, ref operand) |
+            Rvalue::Cast(_, ref operand, _) => {
+                self.consume_operand(location, span, operand);
+            }
+            Rvalue::BinaryOp(_, ref lhs, ref rhs) |
+            Rvalue::CheckedBinaryOp(_, ref lhs, ref rhs) => {
+                self.consume_operand(location, span, lhs);
+                self.consume_operand(location, span, rhs);
+            }
+            Rvalue::Aggregate(_, ref operands) => {
+                for operand in operands {
+                    self.consume_operand(location, span, operand);
+                }
+            }
+            Rvalue::NullaryOp(..) => {}
+        }
+    }
+
+    fn consume_operand(&mut self, location: Location, span: Span, operand: &Operand<'tcx>) {
+        if let Operand::Consume(ref lvalue) = *operand {
+            let tcx = self.bcx.tcx;
+            let ty = lvalue.ty(self.mir, tcx).to_ty(tcx);
+            let access = if ty.moves_by_default(tcx, self.param_env, DUMMY_SP) {
+                Access::Move
+            } else {
+                Access::Read
+            };
+            self.access(location, span, lvalue, access);
+        }
+    }
+
+    fn access(&mut self, location: Location, span: Span, lvalue: &Lvalue<'tcx>, access: Access) {
+        self.access_at(location, location, span, lvalue, access)
+    }
+
+    /// Checks an access to `lvalue` at `location`, reporting at most one
+    /// error for it. The borrows it may conflict with are the ones whose
+    /// region contains `scope_location`.
+    fn access_at(&mut self,
+                 location: Location,
+                 scope_location: Location,
+                 span: Span,
+                 lvalue: &Lvalue<'tcx>,
+                 access: Access) {
+        debug!("MirBorrowckCtxt::access({:?}, {:?}, {:?})", location, lvalue, access);
+        match access {
+            Access::Read | Access::Borrow(_) | Access::Move => {
+                if self.check_if_initialized(span, lvalue) {
+                    return;
+                }
+            }
+            Access::Write | Access::Drop => {}
+        }
+
+        let borrow_set = self.flow_borrows.operator().borrows();
+        for index in borrow_set.borrows.indices() {
+            if !self.flow_borrows.state().contains(&index) ||
+               !self.flow_borrows.operator().in_scope_at(index, scope_location) {
+                continue;
+            }
+            let borrow = &borrow_set.borrows[index];
+            if !self.lvalues_conflict(&borrow.lvalue, lvalue) {
+                continue;
+            }
+            let is_error = match (access, borrow.kind) {
+                (Access::Read, BorrowKind::Shared) |
+                (Access::Borrow(BorrowKind::Shared), BorrowKind::Shared) => false,
+                (Access::Drop, _) => !self.is_behind_reference(&borrow.lvalue),
+                _ => true,
+            };
+            if is_error {
+                self.report_conflict(span, lvalue, access, index, borrow);
+                return;
+            }
+        }
+    }
+
+    /// Reports a use of `lvalue` while it may be uninitialized, and
+    /// returns whether there was an error.
+    fn check_if_initialized(&mut self, span: Span, lvalue: &Lvalue<'tcx>) -> bool {
+        let mpi = match self.move_data.rev_lookup.find(lvalue) {
+            LookupResult::Exact(mpi) => mpi,
+            LookupResult::Parent(Some(mpi)) => mpi,
+            LookupResult::Parent(None) => return false,
+        };
+
+        // The lvalue is uninitialized if one of its parents or children
+        // is, as moving out of a field moves out of part of the whole.
+        let mut uninit = None;
+        let mut parent = Some(mpi);
+        while let Some(path) = parent {
+            if self.flow_uninits.state().contains(&path) {
+                uninit = Some(path);
+                break;
+            }
+            parent = self.move_data.move_paths[path].parent;
+        }
+        if uninit.is_none() {
+            uninit = self.uninit_child(mpi);
+        }
+        let uninit = match uninit {
+            Some(path) => path,
+            None => return false,
+        };
+
+        let described = self.describe_lvalue(lvalue);
+        let mut err = if self.flow_inits.state().contains(&uninit) {
+            let mut err = struct_span_err!(self.bcx.tcx.sess, span, E0382,
+                                           "use of moved value: `{}` (Mir)", described);
+            err.span_label(span, "value used here after move");
+            err
+        } else {
+            let mut err = struct_span_err!(self.bcx.tcx.sess, span, E0381,
+                                           "use of possibly uninitialized variable: `{}` (Mir)",
+                                           described);
+            err.span_label(span, format!("use of possibly uninitialized `{}`", described));
+            err
+        };
+        err.emit();
+        true
+    }
+
+    fn uninit_child(&self, mpi: MovePathIndex) -> Option<MovePathIndex> {
+        let mut child = self.move_data.move_paths[mpi].first_child;
+        while let Some(path) = child {
+            if self.flow_uninits.state().contains(&path) {
+                return Some(path);
+            }
+            if let Some(path) = self.uninit_child(path) {
+                return Some(path);
+            }
+            child = self.move_data.move_paths[path].next_sibling;
+        }
+        None
+    }
+
+    fn report_conflict(&mut self,
+                       span: Span,
+                       lvalue: &Lvalue<'tcx>,
+                       access: Access,
+                       index: BorrowIndex,
+                       borrow: &BorrowData<'tcx>) {
+        debug!("MirBorrowckCtxt::report_conflict({:?}, {:?}, {:?})", lvalue, access, index);
+        let sess = self.bcx.tcx.sess;
+        let borrow_span = self.mir[borrow.location.block]
+            .statements[borrow.location.statement_index].source_info.span;
+        let described = self.describe_lvalue(lvalue);
+        let borrowed = self.describe_lvalue(&borrow.lvalue);
+        let mut err = match (access, borrow.kind) {
+            (Access::Borrow(BorrowKind::Shared), _) |
+            (Access::Borrow(_), BorrowKind::Shared) => {
+                let (new_kind, old_kind) = match access {
+                    Access::Borrow(BorrowKind::Shared) => ("immutable", "mutable"),
+                    _ => ("mutable", "immutable"),
+                };
+                let mut err = struct_span_err!(sess, span, E0502,
+                                               "cannot borrow `{}` as {} because it is also \
+                                                borrowed as {} (Mir)",
+                                               described, new_kind, old_kind);
+                err.span_label(borrow_span, format!("{} borrow occurs here", old_kind));
+                err.span_label(span, format!("{} borrow occurs here", new_kind));
+                err
+            }
+            (Access::Borrow(_), _) => {
+                let mut err = struct_span_err!(sess, span, E0499,
+                                               "cannot borrow `{}` as mutable more than once \
+                                                at a time (Mir)",
+                                               described);
+                err.span_label(borrow_span, "first mutable borrow occurs here");
+                err.span_label(span, "second mutable borrow occurs here");
+                err
+            }
+            (Access::Read, _) => {
+                let mut err = struct_span_err!(sess, span, E0503,
+                                               "cannot use `{}` because it was mutably \
+                                                borrowed (Mir)",
+                                               described);
+                err.span_label(borrow_span, format!("borrow of `{}` occurs here", borrowed));
+                err.span_label(span, format!("use of borrowed `{}`", borrowed));
+                err
+            }
+            (Access::Move, _) => {
+                let mut err = struct_span_err!(sess, span, E0505,
+                                               "cannot move out of `{}` because it is \
+                                                borrowed (Mir)",
+                                               described);
+                err.span_label(borrow_span, format!("borrow of `{}` occurs here", borrowed));
+                err.span_label(span, format!("move out of `{}` occurs here", described));
+                err
+            }
+            (Access::Write, _) => {
+                let mut err = struct_span_err!(sess, span, E0506,
+                                               "cannot assign to `{}` because it is \
+                                                borrowed (Mir)",
+                                               described);
+                err.span_label(borrow_span, format!("borrow of `{}` occurs here", borrowed));
+                err.span_label(span, format!("assignment to borrowed `{}` occurs here",
+                                             described));
+                err
+            }
+            (Access::Drop, _) => {
+                let mut err = sess.struct_span_err(
+                    borrow_span,
+                    &format!("`{}` does not live long enough (Mir)", described));
+                err.span_label(borrow_span, "borrow occurs here");
+                err.span_label(span, format!("`{}` dropped here while still borrowed",
+                                             described));
+                err
+            }
+        };
+        err.emit();
+    }
+
+    /// Whether accessing `lvalue` may touch memory that `borrowed` is a
+    /// reference to.
+    fn lvalues_conflict(&self, borrowed: &Lvalue<'tcx>, lvalue: &Lvalue<'tcx>) -> bool {
+        let borrowed_path = lvalue_path(borrowed);
+        let accessed_path = lvalue_path(lvalue);
+        match (borrowed_path[0], accessed_path[0]) {
+            (&Lvalue::Local(l1), &Lvalue::Local(l2)) if l1 == l2 => {}
+            (&Lvalue::Static(ref s1), &Lvalue::Static(ref s2)) if s1.def_id == s2.def_id => {}
+            _ => return false,
+        }
+
+        let tcx = self.bcx.tcx;
+        for (b, a) in borrowed_path[1..].iter().zip(&accessed_path[1..]) {
+            let (b_proj, a_proj) = match (*b, *a) {
+                (&Lvalue::Projection(ref b), &Lvalue::Projection(ref a)) => (b, a),
+                _ => bug!("lvalue path with a base in the middle"),
+            };
+            match (&b_proj.elem, &a_proj.elem) {
+                (&ProjectionElem::Field(f1, _), &ProjectionElem::Field(f2, _)) if f1 != f2 => {
+                    // Different fields are disjoint, except in unions.
+                    let base_ty = b_proj.base.ty(self.mir, tcx).to_ty(tcx);
+                    match base_ty.sty {
+                        ty::TyAdt(def, _) if def.is_union() => return true,
+                        _ => return false,
+                    }
+                }
+                // Elements of arrays and slices, and enum variants, are
+                // conservatively assumed to overlap.
+                _ => {}
+            }
+        }
+
+        // One of the lvalues is a prefix of the other.
+        true
+    }
+
+    /// Whether `lvalue` is behind a reference or raw pointer, so that it
+    /// outlives the storage of its base local.
+    fn is_behind_reference(&self, lvalue: &Lvalue<'tcx>) -> bool {
+        let tcx = self.bcx.tcx;
+        lvalue_path(lvalue).iter().any(|lvalue| match **lvalue {
+            Lvalue::Projection(ref proj) => match proj.elem {
+                ProjectionElem::Deref => !proj.base.ty(self.mir, tcx).to_ty(tcx).is_box(),
+                _ => false
+            },
+            _ => false
+        })
+    }
+
+    fn describe_lvalue(&self, lvalue: &Lvalue<'tcx>) -> String {
+        match *lvalue {
+            Lvalue::Local(local) => {
+                match self.mir.local_decls[local].name {
+                    Some(name) => name.to_string(),
+                    None => format!("{:?}", local),
+                }
+            }
+            Lvalue::Static(ref statik) => self.bcx.tcx.item_path_str(statik.def_id),
+            Lvalue::Projection(ref proj) => {
+                match proj.elem {
+                    ProjectionElem::Deref => format!("*{}", self.describe_lvalue(&proj.base)),
+                    ProjectionElem::Downcast(..) => self.describe_lvalue(&proj.base),
+                    ProjectionElem::Field(field, _) => {
+                        format!("{}.{}", self.describe_lvalue(&proj.base),
+                                self.describe_field(&proj.base, field.index()))
+                    }
+                    ProjectionElem::Index(_) |
+                    ProjectionElem::ConstantIndex { .. } |
+                    ProjectionElem::Subslice { .. } => {
+                        format!("{}[..]", self.describe_lvalue(&proj.base))
+                    }
+                }
+            }
+        }
+    }
+
+    fn describe_field(&self, base: &Lvalue<'tcx>, field: usize) -> String {
+        if let Lvalue::Projection(ref proj) = *base {
+            if let ProjectionElem::Downcast(def, variant) = proj.elem {
+                return def.variants[variant].fields[field].name.to_string();
+            }
+        }
+        let tcx = self.bcx.tcx;
+        match base.ty(self.mir, tcx).to_ty(tcx).sty {
+            ty::TyAdt(def, _) if !def.is_enum() => {
+                def.struct_variant().fields[field].name.to_string()
+            }
+            _ => field.to_string(),
+        }
+    }
+}
+
+/// The lvalues leading from the local or static at the base of `lvalue`
+/// to `lvalue` itself.
+fn lvalue_path<'l, 'tcx>(lvalue: &'l Lvalue<'tcx>) -> Vec<&'l Lvalue<'tcx>> {
+    let mut path = vec![lvalue];
+    let mut lvalue = lvalue;
+    while let Lvalue::Projection(ref proj) = *lvalue {
+        lvalue = &proj.base;
+        path.push(lvalue);
+    }
+    path.reverse();
+    path
+}
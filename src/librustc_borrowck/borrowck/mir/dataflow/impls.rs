@@ -16,7 +16,9 @@ use rustc_data_structures::indexed_set::{IdxSet};
 use rustc_data_structures::indexed_vec::Idx;
 use rustc_mir::util::elaborate_drops::DropFlagState;
 
+use super::super::borrows::{BorrowIndex, BorrowSet};
 use super::super::gather_moves::{HasMoveData, MoveData, MoveOutIndex, MovePathIndex};
+use super::super::liveness::LocalLiveness;
 use super::super::MoveDataParamEnv;
 use super::super::drop_flag_effects_for_function_entry;
 use super::super::drop_flag_effects_for_location;
@@ -223,6 +225,43 @@ impl<'a, 'tcx> HasMoveData<'tcx> for MovingOutStatements<'a, 'tcx> {
     fn move_data(&self) -> &MoveData<'tcx> { &self.mdpe.move_data }
 }
 
+/// `Borrows` tracks the borrows that may be in scope at a particular
+/// point in the control flow for a function. A borrow comes into scope
+/// at the statement creating it, and goes out of scope at the first
+/// statement that is not in its region, i.e. once no local that may hold
+/// a reference derived from it is live anymore.
+///
+/// The borrows killed by a statement are only known not to be in scope
+/// *after* it, so whether a borrow in the state on entry to a statement
+/// is in scope there still has to be checked against its region.
+pub struct Borrows<'a, 'tcx: 'a> {
+    borrows: &'a BorrowSet<'tcx>,
+    liveness: &'a LocalLiveness,
+}
+
+impl<'a, 'tcx: 'a> Borrows<'a, 'tcx> {
+    pub fn new(borrows: &'a BorrowSet<'tcx>, liveness: &'a LocalLiveness) -> Self {
+        Borrows { borrows: borrows, liveness: liveness }
+    }
+
+    pub fn borrows(&self) -> &'a BorrowSet<'tcx> { self.borrows }
+
+    pub fn in_scope_at(&self, borrow: BorrowIndex, location: Location) -> bool {
+        self.borrows.region_contains(borrow, self.liveness, location)
+    }
+
+    fn update_bits(&self, sets: &mut BlockSets<BorrowIndex>, location: Location) {
+        for borrow in self.borrows.borrows.indices() {
+            if !self.in_scope_at(borrow, location) {
+                sets.kill(&borrow);
+            }
+        }
+        if let Some(&borrow) = self.borrows.location_map.get(&location) {
+            sets.gen(&borrow);
+        }
+    }
+}
+
 impl<'a, 'tcx> MaybeInitializedLvals<'a, 'tcx> {
     fn update_bits(sets: &mut BlockSets<MovePathIndex>, path: MovePathIndex,
                    state: DropFlagState)
@@ -516,6 +555,44 @@ impl<'a, 'tcx> BitDenotation for MovingOutStatements<'a, 'tcx> {
     }
 }
 
+impl<'a, 'tcx> BitDenotation for Borrows<'a, 'tcx> {
+    type Idx = BorrowIndex;
+    fn name() -> &'static str { "borrows" }
+    fn bits_per_block(&self) -> usize {
+        self.borrows.borrows.len()
+    }
+
+    fn start_block_effect(&self, _sets: &mut BlockSets<BorrowIndex>) {
+        // no borrows have been created prior to function execution,
+        // so this method has no effect on `_sets`.
+    }
+
+    fn statement_effect(&self,
+                        sets: &mut BlockSets<BorrowIndex>,
+                        bb: mir::BasicBlock,
+                        idx: usize)
+    {
+        self.update_bits(sets, Location { block: bb, statement_index: idx });
+    }
+
+    fn terminator_effect(&self,
+                         sets: &mut BlockSets<BorrowIndex>,
+                         bb: mir::BasicBlock,
+                         statements_len: usize)
+    {
+        self.update_bits(sets, Location { block: bb, statement_index: statements_len });
+    }
+
+    fn propagate_call_return(&self,
+                             _in_out: &mut IdxSet<BorrowIndex>,
+                             _call_bb: mir::BasicBlock,
+                             _dest_bb: mir::BasicBlock,
+                             _dest_lval: &mir::Lvalue) {
+        // a returning call creates no borrows; the references it returns
+        // are accounted for by the regions of the borrows passed to it.
+    }
+}
+
 fn zero_to_one(bitvec: &mut [usize], move_index: MoveOutIndex) {
     let retval = bitvec.set_bit(move_index.index());
     assert!(retval);
@@ -528,6 +605,13 @@ impl<'a, 'tcx> BitwiseOperator for MovingOutStatements<'a, 'tcx> {
     }
 }
 
+impl<'a, 'tcx> BitwiseOperator for Borrows<'a, 'tcx> {
+    #[inline]
+    fn join(&self, pred1: usize, pred2: usize) -> usize {
+        pred1 | pred2 // borrows from both preds are in scope
+    }
+}
+
 impl<'a, 'tcx> BitwiseOperator for MaybeInitializedLvals<'a, 'tcx> {
     #[inline]
     fn join(&self, pred1: usize, pred2: usize) -> usize {
@@ -566,6 +650,13 @@ impl<'a, 'tcx> DataflowOperator for MovingOutStatements<'a, 'tcx> {
     }
 }
 
+impl<'a, 'tcx> DataflowOperator for Borrows<'a, 'tcx> {
+    #[inline]
+    fn bottom_value() -> bool {
+        false // bottom = no borrows in scope by default
+    }
+}
+
 impl<'a, 'tcx> DataflowOperator for MaybeInitializedLvals<'a, 'tcx> {
     #[inline]
     fn bottom_value() -> bool {
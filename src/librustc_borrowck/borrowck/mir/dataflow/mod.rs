@@ -26,6 +26,7 @@ use super::MirBorrowckCtxtPreDataflow;
 pub use self::sanity_check::sanity_check_via_rustc_peek;
 pub use self::impls::{MaybeInitializedLvals, MaybeUninitializedLvals};
 pub use self::impls::{DefinitelyInitializedLvals, MovingOutStatements};
+pub use self::impls::Borrows;
 
 mod graphviz;
 mod sanity_check;
@@ -201,6 +202,65 @@ impl<O: BitDenotation> DataflowResults<O> {
     pub fn sets(&self) -> &AllSets<O::Idx> {
         &self.0.sets
     }
+
+    pub fn operator(&self) -> &O {
+        &self.0.operator
+    }
+}
+
+/// The state of a dataflow analysis at a point inside a basic block,
+/// reconstructed from the results by replaying the effects of the
+/// statements of the block one at a time.
+pub struct FlowInProgress<BD> where BD: BitDenotation {
+    results: DataflowResults<BD>,
+    curr_state: IdxSetBuf<BD::Idx>,
+    stmt_gen: IdxSetBuf<BD::Idx>,
+    stmt_kill: IdxSetBuf<BD::Idx>,
+}
+
+impl<BD> FlowInProgress<BD> where BD: BitDenotation {
+    pub fn new(results: DataflowResults<BD>) -> Self {
+        let bits_per_block = results.sets().bits_per_block();
+        FlowInProgress {
+            results: results,
+            curr_state: IdxSetBuf::new_empty(bits_per_block),
+            stmt_gen: IdxSetBuf::new_empty(bits_per_block),
+            stmt_kill: IdxSetBuf::new_empty(bits_per_block),
+        }
+    }
+
+    pub fn reset_to_entry_of(&mut self, bb: mir::BasicBlock) {
+        self.curr_state.clone_from(self.results.sets().on_entry_set_for(bb.index()));
+    }
+
+    /// Applies the effect of the statement at `idx` in `bb`, or of the
+    /// terminator of `bb` if there is no such statement, to the state.
+    pub fn apply_effect(&mut self, bb: mir::BasicBlock, idx: usize, is_terminator: bool) {
+        for word in self.stmt_gen.words_mut() { *word = 0; }
+        for word in self.stmt_kill.words_mut() { *word = 0; }
+        {
+            let mut sets = BlockSets {
+                on_entry: &mut self.curr_state,
+                gen_set: &mut self.stmt_gen,
+                kill_set: &mut self.stmt_kill,
+            };
+            if is_terminator {
+                self.results.operator().terminator_effect(&mut sets, bb, idx);
+            } else {
+                self.results.operator().statement_effect(&mut sets, bb, idx);
+            }
+        }
+        self.curr_state.union(&self.stmt_gen);
+        self.curr_state.subtract(&self.stmt_kill);
+    }
+
+    pub fn state(&self) -> &IdxSet<BD::Idx> {
+        &self.curr_state
+    }
+
+    pub fn operator(&self) -> &BD {
+        self.results.operator()
+    }
 }
 
 // FIXME: This type shouldn't be public, but the graphviz::MirWithFlowState trait
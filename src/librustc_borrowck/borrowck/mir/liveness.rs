@@ -0,0 +1,139 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Liveness of MIR locals, which the regions of borrows are computed from.
+//!
+//! A local is live at a location if its current value may be used later
+//! on: there is a path from the location to a use of the local that does
+//! not pass through an assignment to the whole local or its `StorageDead`.
+//! A borrow stays in scope as long as a local which may hold it is live,
+//! so a reference that is never used again no longer restricts what can
+//! be done with the borrowed lvalue, whatever its lexical scope is.
+
+use rustc::mir::{BasicBlock, Local, Location, Lvalue, Mir, RETURN_POINTER, TerminatorKind};
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc_data_structures::indexed_set::{IdxSet, IdxSetBuf};
+use rustc_data_structures::indexed_vec::IndexVec;
+
+pub struct LocalLiveness {
+    /// The locals live on entry to each statement of each block, with the
+    /// terminator last.
+    live: IndexVec<BasicBlock, Vec<IdxSetBuf<Local>>>,
+}
+
+impl LocalLiveness {
+    pub fn compute(mir: &Mir) -> LocalLiveness {
+        let num_locals = mir.local_decls.len();
+        let mut entry_sets: IndexVec<BasicBlock, IdxSetBuf<Local>> =
+            mir.basic_blocks().indices().map(|_| IdxSetBuf::new_empty(num_locals)).collect();
+
+        // Iterate the backwards analysis to a fixed point. Visiting the
+        // blocks in reverse order converges faster, as most edges point
+        // forwards.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for bb in mir.basic_blocks().indices().rev() {
+                let mut live = exit_set(mir, &entry_sets, bb, num_locals);
+                for index in (0..mir[bb].statements.len() + 1).rev() {
+                    transfer(mir, Location { block: bb, statement_index: index }, &mut live);
+                }
+                if live.words() != entry_sets[bb].words() {
+                    entry_sets[bb] = live;
+                    changed = true;
+                }
+            }
+        }
+
+        let live = mir.basic_blocks().indices().map(|bb| {
+            let len = mir[bb].statements.len() + 1;
+            let mut sets = Vec::with_capacity(len);
+            let mut live = exit_set(mir, &entry_sets, bb, num_locals);
+            for index in (0..len).rev() {
+                transfer(mir, Location { block: bb, statement_index: index }, &mut live);
+                sets.push(live.clone());
+            }
+            sets.reverse();
+            sets
+        }).collect();
+
+        LocalLiveness { live: live }
+    }
+
+    /// The locals live on entry to the statement or terminator at `location`.
+    pub fn live_at(&self, location: Location) -> &IdxSet<Local> {
+        &self.live[location.block][location.statement_index]
+    }
+}
+
+/// The locals live on exit from `bb`, which are the ones live on entry to
+/// any of its successors, including the unwind ones.
+fn exit_set(mir: &Mir,
+            entry_sets: &IndexVec<BasicBlock, IdxSetBuf<Local>>,
+            bb: BasicBlock,
+            num_locals: usize)
+            -> IdxSetBuf<Local> {
+    let mut live = IdxSetBuf::new_empty(num_locals);
+    for succ in mir[bb].terminator().successors().iter() {
+        live.union(&entry_sets[*succ]);
+    }
+    live
+}
+
+/// Turns the locals live after the statement at `location` into the ones
+/// live before it.
+fn transfer(mir: &Mir, location: Location, live: &mut IdxSet<Local>) {
+    let mut defs_uses = DefsUses {
+        defs: IdxSetBuf::new_empty(mir.local_decls.len()),
+        uses: IdxSetBuf::new_empty(mir.local_decls.len()),
+    };
+    let data = &mir[location.block];
+    match data.statements.get(location.statement_index) {
+        Some(statement) => defs_uses.visit_statement(location.block, statement, location),
+        None => {
+            // The return value is used by the caller.
+            if let TerminatorKind::Return = data.terminator().kind {
+                defs_uses.uses.add(&RETURN_POINTER);
+            }
+            defs_uses.visit_terminator(location.block, data.terminator(), location)
+        }
+    }
+    live.subtract(&defs_uses.defs);
+    live.union(&defs_uses.uses);
+}
+
+struct DefsUses {
+    defs: IdxSetBuf<Local>,
+    uses: IdxSetBuf<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for DefsUses {
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let Lvalue::Local(local) = *lvalue {
+            match context {
+                // Overwriting or ending the storage of the whole local ends
+                // the lifetime of its previous value.
+                LvalueContext::Store |
+                LvalueContext::Call |
+                LvalueContext::StorageLive |
+                LvalueContext::StorageDead => {
+                    self.defs.add(&local);
+                }
+                _ => {
+                    self.uses.add(&local);
+                }
+            }
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+}
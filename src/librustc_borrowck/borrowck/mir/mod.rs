@@ -13,24 +13,29 @@ use borrowck::BorrowckCtxt;
 use syntax::ast::{self, MetaItem};
 use syntax_pos::DUMMY_SP;
 
-use rustc::mir::{self, BasicBlock, BasicBlockData, Mir, Statement, Terminator, Location};
+use rustc::mir::{self, BasicBlock, Mir, Location};
 use rustc::session::Session;
 use rustc::ty::{self, TyCtxt};
 use rustc_mir::util::elaborate_drops::DropFlagState;
 use rustc_data_structures::indexed_set::{IdxSet, IdxSetBuf};
 
 mod abs_domain;
+mod borrows;
+mod check;
 pub mod elaborate_drops;
 mod dataflow;
 mod gather_moves;
+mod liveness;
 // mod graphviz;
 
 use self::dataflow::{BitDenotation};
 use self::dataflow::{DataflowOperator};
 use self::dataflow::{Dataflow, DataflowAnalysis, DataflowResults};
 use self::dataflow::{MaybeInitializedLvals, MaybeUninitializedLvals};
-use self::dataflow::{DefinitelyInitializedLvals};
+use self::dataflow::{DefinitelyInitializedLvals, Borrows, FlowInProgress};
+use self::borrows::BorrowSet;
 use self::gather_moves::{HasMoveData, MoveData, MovePathIndex, LookupResult};
+use self::liveness::LocalLiveness;
 
 use std::fmt;
 
@@ -59,7 +64,7 @@ pub fn borrowck_mir(bcx: &mut BorrowckCtxt,
                     attributes: &[ast::Attribute]) {
     let tcx = bcx.tcx;
     let def_id = tcx.hir.local_def_id(id);
-    debug!("borrowck_mir({:?}) UNIMPLEMENTED", def_id);
+    debug!("borrowck_mir({:?})", def_id);
 
     // It is safe for us to borrow `mir_validated()`: `optimized_mir`
     // steals it, but it forces the `borrowck` query.
@@ -82,6 +87,13 @@ pub fn borrowck_mir(bcx: &mut BorrowckCtxt,
                     DefinitelyInitializedLvals::new(tcx, mir, &mdpe),
                     |bd, i| &bd.move_data().move_paths[i]);
 
+    let liveness = LocalLiveness::compute(mir);
+    let borrows = BorrowSet::build(tcx, mir);
+    let flow_borrows =
+        do_dataflow(tcx, mir, id, attributes, &dead_unwinds,
+                    Borrows::new(&borrows, &liveness),
+                    |bd, i| &bd.borrows().borrows[i]);
+
     if has_rustc_mir_with(attributes, "rustc_peek_maybe_init").is_some() {
         dataflow::sanity_check_via_rustc_peek(bcx.tcx, mir, id, attributes, &flow_inits);
     }
@@ -101,8 +113,10 @@ pub fn borrowck_mir(bcx: &mut BorrowckCtxt,
         mir: mir,
         node_id: id,
         move_data: &mdpe.move_data,
-        flow_inits: flow_inits,
-        flow_uninits: flow_uninits,
+        param_env: param_env,
+        flow_inits: FlowInProgress::new(flow_inits),
+        flow_uninits: FlowInProgress::new(flow_uninits),
+        flow_borrows: FlowInProgress::new(flow_borrows),
     };
 
     for bb in mir.basic_blocks().indices() {
@@ -120,7 +134,7 @@ fn do_dataflow<'a, 'tcx, BD, P>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                 bd: BD,
                                 p: P)
                                 -> DataflowResults<BD>
-    where BD: BitDenotation<Idx=MovePathIndex> + DataflowOperator,
+    where BD: BitDenotation + DataflowOperator,
           P: Fn(&BD, BD::Idx) -> &fmt::Debug
 {
     let name_found = |sess: &Session, attrs: &[ast::Attribute], name| -> Option<String> {
@@ -168,28 +182,10 @@ pub struct MirBorrowckCtxt<'b, 'a: 'b, 'tcx: 'a> {
     mir: &'b Mir<'tcx>,
     node_id: ast::NodeId,
     move_data: &'b MoveData<'tcx>,
-    flow_inits: DataflowResults<MaybeInitializedLvals<'b, 'tcx>>,
-    flow_uninits: DataflowResults<MaybeUninitializedLvals<'b, 'tcx>>
-}
-
-impl<'b, 'a: 'b, 'tcx: 'a> MirBorrowckCtxt<'b, 'a, 'tcx> {
-    fn process_basic_block(&mut self, bb: BasicBlock) {
-        let BasicBlockData { ref statements, ref terminator, is_cleanup: _ } =
-            self.mir[bb];
-        for stmt in statements {
-            self.process_statement(bb, stmt);
-        }
-
-        self.process_terminator(bb, terminator);
-    }
-
-    fn process_statement(&mut self, bb: BasicBlock, stmt: &Statement<'tcx>) {
-        debug!("MirBorrowckCtxt::process_statement({:?}, {:?}", bb, stmt);
-    }
-
-    fn process_terminator(&mut self, bb: BasicBlock, term: &Option<Terminator<'tcx>>) {
-        debug!("MirBorrowckCtxt::process_terminator({:?}, {:?})", bb, term);
-    }
+    param_env: ty::ParamEnv<'tcx>,
+    flow_inits: FlowInProgress<MaybeInitializedLvals<'b, 'tcx>>,
+    flow_uninits: FlowInProgress<MaybeUninitializedLvals<'b, 'tcx>>,
+    flow_borrows: FlowInProgress<Borrows<'b, 'tcx>>,
 }
 
 fn move_path_children_matching<'tcx, F>(move_data: &MoveData<'tcx>,
@@ -46,7 +46,7 @@ use std::rc::Rc;
 use std::hash::{Hash, Hasher};
 use syntax::ast;
 use syntax_pos::{MultiSpan, Span};
-use errors::DiagnosticBuilder;
+use errors::{Applicability, DiagnosticBuilder};
 
 use rustc::hir;
 use rustc::hir::intravisit::{self, Visitor};
@@ -109,7 +109,8 @@ fn borrowck<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, owner_def_id: DefId) {
 
     let body = bccx.tcx.hir.body(body_id);
 
-    if bccx.tcx.has_attr(owner_def_id, "rustc_mir_borrowck") {
+    if bccx.tcx.has_attr(owner_def_id, "rustc_mir_borrowck") ||
+       bccx.tcx.sess.opts.debugging_opts.borrowck_mir {
         mir::borrowck_mir(bccx, owner_id, &attributes);
     } else {
         // Eventually, borrowck will always read the MIR, but at the
@@ -975,12 +976,17 @@ impl<'a, 'tcx> BorrowckCtxt<'a, 'tcx> {
     {
         let cmt_path_or_string = self.cmt_to_path_or_string(&err.cmt);
 
-        let suggestion =
+        let (suggestion, applicability) =
             match self.tcx.sess.codemap().span_to_snippet(err.span) {
-                Ok(string) => format!("move {}", string),
-                Err(_) => format!("move |<args>| <body>")
+                Ok(string) => (format!("move {}", string), Applicability::MachineApplicable),
+                Err(_) => (format!("move |<args>| <body>"), Applicability::HasPlaceholders)
             };
 
+        let suggestion_msg = format!("to force the closure to take ownership of {} \
+                                      (and any other referenced variables), \
+                                      use the `move` keyword, as shown:",
+                                     cmt_path_or_string);
+
         struct_span_err!(self.tcx.sess, err.span, E0373,
                          "closure may outlive the current function, \
                           but it borrows {}, \
@@ -992,12 +998,10 @@ impl<'a, 'tcx> BorrowckCtxt<'a, 'tcx> {
             .span_label(err.span,
                        format!("may outlive borrowed value {}",
                                 cmt_path_or_string))
-            .span_suggestion(err.span,
-                             &format!("to force the closure to take ownership of {} \
-                                       (and any other referenced variables), \
-                                       use the `move` keyword, as shown:",
-                                       cmt_path_or_string),
-                             suggestion)
+            .span_suggestion_with_applicability(err.span,
+                                                &suggestion_msg,
+                                                suggestion,
+                                                applicability)
             .emit();
     }
 
@@ -13,7 +13,6 @@ use rustc::middle::const_val::ErrKind::*;
 use rustc::middle::const_val::{ConstVal, ConstEvalErr, EvalResult, ErrKind};
 
 use rustc::hir::map as hir_map;
-use rustc::hir::map::blocks::FnLikeNode;
 use rustc::traits;
 use rustc::hir::def::{Def, CtorKind};
 use rustc::hir::def_id::DefId;
@@ -23,7 +22,6 @@ use rustc::ty::util::IntTypeExt;
 use rustc::ty::subst::{Substs, Subst};
 use rustc::traits::Reveal;
 use rustc::util::common::ErrorReported;
-use rustc::util::nodemap::DefIdMap;
 
 use syntax::ast;
 use rustc::hir::{self, Expr};
@@ -89,7 +87,6 @@ pub struct ConstContext<'a, 'tcx: 'a> {
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
     tables: &'a ty::TypeckTables<'tcx>,
     substs: &'tcx Substs<'tcx>,
-    fn_args: Option<DefIdMap<ConstVal<'tcx>>>
 }
 
 impl<'a, 'tcx> ConstContext<'a, 'tcx> {
@@ -98,7 +95,6 @@ impl<'a, 'tcx> ConstContext<'a, 'tcx> {
             tcx: tcx,
             tables: tables,
             substs: tcx.intern_substs(&[]),
-            fn_args: None
         }
     }
 
@@ -322,14 +318,6 @@ fn eval_const_expr_partial<'a, 'tcx>(cx: &ConstContext<'a, 'tcx>,
               Def::StructCtor(_, CtorKind::Fn) => {
                   signal!(e, UnimplementedConstVal("tuple struct constructors"))
               }
-              Def::Local(def_id) => {
-                  debug!("Def::Local({:?}): {:?}", def_id, cx.fn_args);
-                  if let Some(val) = cx.fn_args.as_ref().and_then(|args| args.get(&def_id)) {
-                      val.clone()
-                  } else {
-                      signal!(e, NonConstPath);
-                  }
-              },
               Def::Method(id) | Def::Fn(id) => Function(id, substs),
               Def::Err => span_bug!(e.span, "typeck error"),
               _ => signal!(e, NonConstPath),
@@ -341,46 +329,8 @@ fn eval_const_expr_partial<'a, 'tcx>(cx: &ConstContext<'a, 'tcx>,
               _ => signal!(e, TypeckError),
           };
 
-          let body = if let Some(node_id) = tcx.hir.as_local_node_id(def_id) {
-            if let Some(fn_like) = FnLikeNode::from_node(tcx.hir.get(node_id)) {
-                if fn_like.constness() == hir::Constness::Const {
-                    tcx.hir.body(fn_like.body())
-                } else {
-                    signal!(e, TypeckError)
-                }
-            } else {
-                signal!(e, TypeckError)
-            }
-          } else {
-            if tcx.sess.cstore.is_const_fn(def_id) {
-                tcx.sess.cstore.item_body(tcx, def_id)
-            } else {
-                signal!(e, TypeckError)
-            }
-          };
-
-          let arg_defs = body.arguments.iter().map(|arg| match arg.pat.node {
-               hir::PatKind::Binding(_, def_id, _, _) => Some(def_id),
-               _ => None
-           }).collect::<Vec<_>>();
-          assert_eq!(arg_defs.len(), args.len());
-
-          let mut call_args = DefIdMap();
-          for (arg, arg_expr) in arg_defs.into_iter().zip(args.iter()) {
-              let arg_val = cx.eval(arg_expr)?;
-              debug!("const call arg: {:?}", arg);
-              if let Some(def_id) = arg {
-                assert!(call_args.insert(def_id, arg_val).is_none());
-              }
-          }
-          debug!("const call({:?})", call_args);
-          let callee_cx = ConstContext {
-            tcx: tcx,
-            tables: tcx.typeck_tables_of(def_id),
-            substs: substs,
-            fn_args: Some(call_args)
-          };
-          callee_cx.eval(&body.value)?
+          let args = args.iter().map(|arg| cx.eval(arg)).collect::<Result<Vec<_>, _>>()?;
+          ::interpret::eval_const_fn_call(tcx, e.span, def_id, substs, args)?
       },
       hir::ExprLit(ref lit) => match lit_to_const(&lit.node, tcx, ety) {
           Ok(val) => val,
@@ -597,10 +547,10 @@ fn cast_const_float<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     }
 }
 
-fn cast_const<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
-                        val: ConstVal<'tcx>,
-                        ty: Ty<'tcx>)
-                        -> CastResult<'tcx> {
+pub fn cast_const<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                            val: ConstVal<'tcx>,
+                            ty: Ty<'tcx>)
+                            -> CastResult<'tcx> {
     match val {
         Integral(i) => cast_const_int(tcx, i, ty),
         Bool(b) => cast_const_int(tcx, U8(b as u8), ty),
@@ -765,11 +715,24 @@ fn const_eval<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         });
     };
 
+    // Named constants are evaluated on their MIR, the anonymous constants
+    // of array lengths and enum discriminants can't be, as building MIR
+    // requires the types those constants are part of.
+    if is_const_item(tcx, def_id) && tcx.is_mir_available(def_id) {
+        if tcx.hir.as_local_node_id(def_id).is_some() &&
+           tcx.typeck_tables_of(def_id).tainted_by_errors {
+            return Err(ConstEvalErr {
+                span: tcx.def_span(def_id),
+                kind: TypeckError
+            });
+        }
+        return ::interpret::eval_const(tcx, def_id, substs);
+    }
+
     let cx = ConstContext {
         tcx,
         tables: tcx.typeck_tables_of(def_id),
         substs: substs,
-        fn_args: None
     };
 
     let body = if let Some(id) = tcx.hir.as_local_node_id(def_id) {
@@ -780,3 +743,23 @@ fn const_eval<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     };
     cx.eval(&body.value)
 }
+
+fn is_const_item<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> bool {
+    if let Some(node_id) = tcx.hir.as_local_node_id(def_id) {
+        match tcx.hir.find(node_id) {
+            Some(hir_map::NodeItem(&hir::Item { node: hir::ItemConst(..), .. })) |
+            Some(hir_map::NodeTraitItem(&hir::TraitItem {
+                node: hir::TraitItemKind::Const(..), ..
+            })) |
+            Some(hir_map::NodeImplItem(&hir::ImplItem {
+                node: hir::ImplItemKind::Const(..), ..
+            })) => true,
+            _ => false
+        }
+    } else {
+        match tcx.describe_def(def_id) {
+            Some(Def::Const(_)) | Some(Def::AssociatedConst(_)) => true,
+            _ => false
+        }
+    }
+}
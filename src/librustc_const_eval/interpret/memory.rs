@@ -0,0 +1,195 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The memory of the MIR interpreter.
+//!
+//! Memory is a set of allocations, one for each storage of a local and one
+//! for each value that is only reachable through a reference, like the
+//! contents of a byte string literal. Allocations hold value trees instead
+//! of bytes, so a pointer is an allocation together with the path of fields
+//! and enum variants leading from the allocation's value to its target.
+
+use rustc::middle::const_val::{ConstVal, ErrKind};
+
+#[derive(Clone, Debug)]
+pub enum Value<'tcx> {
+    /// An uninitialized value.
+    Undef,
+
+    /// A scalar, a string or byte string literal or a function.
+    Const(ConstVal<'tcx>),
+
+    /// A struct, enum, union, tuple, array or closure. Fields missing from
+    /// the end of `fields` are uninitialized, which is how partially
+    /// initialized values and unions are represented.
+    Aggregate { variant: usize, fields: Vec<Value<'tcx>> },
+
+    /// A reference or raw pointer.
+    Ptr(Pointer),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AllocId(usize);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PathElem {
+    Field(usize),
+    Downcast(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pointer {
+    pub alloc: AllocId,
+    pub path: Vec<PathElem>,
+}
+
+impl Pointer {
+    pub fn new(alloc: AllocId) -> Pointer {
+        Pointer {
+            alloc: alloc,
+            path: vec![],
+        }
+    }
+
+    pub fn project(&self, elem: PathElem) -> Pointer {
+        let mut path = self.path.clone();
+        path.push(elem);
+        Pointer {
+            alloc: self.alloc,
+            path: path,
+        }
+    }
+}
+
+struct Allocation<'tcx> {
+    value: Value<'tcx>,
+    /// Whether the allocation wasn't deallocated yet.
+    live: bool,
+}
+
+pub struct Memory<'tcx> {
+    allocs: Vec<Allocation<'tcx>>,
+    /// Returned when reading a field that was never written.
+    undef: Value<'tcx>,
+}
+
+impl<'tcx> Memory<'tcx> {
+    pub fn new() -> Memory<'tcx> {
+        Memory {
+            allocs: vec![],
+            undef: Value::Undef,
+        }
+    }
+
+    pub fn allocate(&mut self, value: Value<'tcx>) -> AllocId {
+        self.allocs.push(Allocation {
+            value: value,
+            live: true,
+        });
+        AllocId(self.allocs.len() - 1)
+    }
+
+    /// Marks `alloc` as dead. Its value is dropped, but the allocation is
+    /// kept around so that uses of dangling pointers can be detected.
+    pub fn deallocate(&mut self, alloc: AllocId) {
+        let alloc = &mut self.allocs[alloc.0];
+        alloc.value = Value::Undef;
+        alloc.live = false;
+    }
+
+    fn live_value(&self, alloc: AllocId) -> Result<&Value<'tcx>, ErrKind<'tcx>> {
+        let alloc = &self.allocs[alloc.0];
+        if alloc.live {
+            Ok(&alloc.value)
+        } else {
+            Err(ErrKind::UndefinedBehavior("use of a dangling reference"))
+        }
+    }
+
+    pub fn read(&self, ptr: &Pointer) -> Result<&Value<'tcx>, ErrKind<'tcx>> {
+        let mut value = self.live_value(ptr.alloc)?;
+        for &elem in &ptr.path {
+            value = match (value, elem) {
+                (&Value::Undef, _) => return Ok(&self.undef),
+                (&Value::Aggregate { ref fields, .. }, PathElem::Field(i)) => {
+                    fields.get(i).unwrap_or(&self.undef)
+                }
+                (&Value::Aggregate { variant, .. }, PathElem::Downcast(v)) => {
+                    if variant != v {
+                        let msg = "read of an inactive enum variant";
+                        return Err(ErrKind::UndefinedBehavior(msg));
+                    }
+                    value
+                }
+                _ => bug!("projecting {:?} with {:?}", value, elem)
+            };
+        }
+        Ok(value)
+    }
+
+    pub fn write(&mut self, ptr: &Pointer, value: Value<'tcx>) -> Result<(), ErrKind<'tcx>> {
+        *self.target_mut(ptr)? = value;
+        Ok(())
+    }
+
+    /// Makes `variant` the active variant of the enum `ptr` points to.
+    pub fn set_variant(&mut self, ptr: &Pointer, variant: usize) -> Result<(), ErrKind<'tcx>> {
+        let target = self.target_mut(ptr)?;
+        project_mut(target, PathElem::Downcast(variant));
+        Ok(())
+    }
+
+    /// Returns the value `ptr` points to, initializing the aggregates on
+    /// the way to it.
+    fn target_mut(&mut self, ptr: &Pointer) -> Result<&mut Value<'tcx>, ErrKind<'tcx>> {
+        let alloc = &mut self.allocs[ptr.alloc.0];
+        if !alloc.live {
+            return Err(ErrKind::UndefinedBehavior("use of a dangling reference"));
+        }
+        let mut target = &mut alloc.value;
+        for &elem in &ptr.path {
+            target = project_mut(target, elem);
+        }
+        Ok(target)
+    }
+}
+
+fn project_mut<'v, 'tcx>(value: &'v mut Value<'tcx>, elem: PathElem) -> &'v mut Value<'tcx> {
+    match elem {
+        PathElem::Field(i) => {
+            if let Value::Undef = *value {
+                *value = Value::Aggregate { variant: 0, fields: vec![] };
+            }
+            match *value {
+                Value::Aggregate { ref mut fields, .. } => {
+                    while fields.len() <= i {
+                        fields.push(Value::Undef);
+                    }
+                    &mut fields[i]
+                }
+                _ => bug!("projecting field {} of a scalar", i)
+            }
+        }
+        PathElem::Downcast(v) => {
+            match *value {
+                Value::Undef => *value = Value::Aggregate { variant: v, fields: vec![] },
+                Value::Aggregate { ref mut variant, ref mut fields } => {
+                    // Writing to another variant ends the lifetime of the old one.
+                    if *variant != v {
+                        *variant = v;
+                        fields.clear();
+                    }
+                }
+                _ => bug!("downcasting a scalar to variant {}", v)
+            }
+            value
+        }
+    }
+}
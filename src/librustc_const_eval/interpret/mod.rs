@@ -0,0 +1,852 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An interpreter for MIR, evaluating constants and `const fn` calls.
+//!
+//! Bodies are executed statement by statement on a virtual memory, with a
+//! Rust stack frame for each MIR call frame. Every storage of a local is a
+//! separate allocation, so reading an uninitialized value or a local whose
+//! storage is dead is reported as undefined behavior instead of producing
+//! some arbitrary value. Errors inside of a `const fn` are wrapped in
+//! `ErrKind::InConstFn` for each call they happened in, which gives their
+//! diagnostic a backtrace of the calls.
+
+use rustc::hir;
+use rustc::hir::def::CtorKind;
+use rustc::hir::def_id::DefId;
+use rustc::hir::map::blocks::FnLikeNode;
+use rustc::infer::TransNormalize;
+use rustc::middle::const_val::{ConstEvalErr, ConstVal, ErrKind, EvalResult};
+use rustc::mir::{self, Mir, Local, Lvalue, Operand, Rvalue};
+use rustc::mir::{AggregateKind, BinOp, CastKind, NullOp, UnOp};
+use rustc::mir::{ProjectionElem, StatementKind, TerminatorKind};
+use rustc::traits::Reveal;
+use rustc::ty::{self, Ty, TyCtxt, TypeFoldable};
+use rustc::ty::subst::{Substs, Subst};
+use rustc_const_math::{ConstInt, ConstMathErr, ConstUsize};
+use rustc_data_structures::indexed_vec::IndexVec;
+use syntax_pos::Span;
+
+use std::cmp::Ordering;
+
+use eval::{cast_const, lookup_const_by_id};
+
+mod memory;
+
+pub use self::memory::{AllocId, Memory, PathElem, Pointer, Value};
+
+macro_rules! signal {
+    ($span:expr, $kind:expr) => {
+        return Err(ConstEvalErr { span: $span, kind: $kind })
+    }
+}
+
+/// The number of statements and terminators an evaluation can execute,
+/// which stops evaluations that don't terminate.
+const STEP_LIMIT: usize = 1_000_000;
+
+/// The length of the largest array a repeat expression is evaluated to.
+const REPEAT_LIMIT: u64 = 1 << 20;
+
+/// Evaluates the constant `def_id`, which must have MIR.
+pub fn eval_const<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                            def_id: DefId,
+                            substs: &'tcx Substs<'tcx>)
+                            -> EvalResult<'tcx> {
+    let mir = tcx.optimized_mir(def_id);
+    let mut evaluator = Evaluator::new(tcx);
+    let value = evaluator.eval_body(mir, substs, false, vec![])?;
+    let ty = evaluator.monomorphize(substs, &mir.return_ty);
+    spanned(mir.span, evaluator.value_to_const(value, ty))
+}
+
+/// Evaluates a call to `def_id` at `span`, with arguments that were
+/// evaluated on the HIR.
+pub fn eval_const_fn_call<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                    span: Span,
+                                    def_id: DefId,
+                                    substs: &'tcx Substs<'tcx>,
+                                    args: Vec<ConstVal<'tcx>>)
+                                    -> EvalResult<'tcx> {
+    if !is_const_fn(tcx, def_id) {
+        signal!(span, ErrKind::TypeckError);
+    }
+    if !tcx.is_mir_available(def_id) {
+        signal!(span, ErrKind::UnimplementedConstVal("calls to functions without MIR"));
+    }
+    let mir = tcx.optimized_mir(def_id);
+    let mut evaluator = Evaluator::new(tcx);
+    let args = mir.args_iter().zip(args).map(|(local, arg)| {
+        let ty = evaluator.monomorphize(substs, &mir.local_decls[local].ty);
+        evaluator.const_to_value(arg, ty)
+    }).collect::<Result<Vec<_>, _>>();
+    let args = spanned(span, args)?;
+    let value = evaluator.eval_call(def_id, substs, args, span)?;
+    let ty = evaluator.monomorphize(substs, &mir.return_ty);
+    spanned(span, evaluator.value_to_const(value, ty))
+}
+
+/// Evaluates `mir`, the body of a constant or of a promoted expression,
+/// returning its value and the memory its pointers point into.
+pub fn eval_mir<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          mir: &Mir<'tcx>,
+                          substs: &'tcx Substs<'tcx>)
+                          -> Result<(Value<'tcx>, Memory<'tcx>), ConstEvalErr<'tcx>> {
+    let mut evaluator = Evaluator::new(tcx);
+    let value = evaluator.eval_body(mir, substs, false, vec![])?;
+    Ok((value, evaluator.memory))
+}
+
+fn is_const_fn<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> bool {
+    if let Some(node_id) = tcx.hir.as_local_node_id(def_id) {
+        match FnLikeNode::from_node(tcx.hir.get(node_id)) {
+            Some(fn_like) => fn_like.constness() == hir::Constness::Const,
+            None => false
+        }
+    } else {
+        tcx.sess.cstore.is_const_fn(def_id)
+    }
+}
+
+fn spanned<'tcx, T>(span: Span, result: Result<T, ErrKind<'tcx>>)
+                    -> Result<T, ConstEvalErr<'tcx>> {
+    result.map_err(|kind| ConstEvalErr { span: span, kind: kind })
+}
+
+fn usize_value<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, n: u64) -> Value<'tcx> {
+    let n = ConstUsize::new_truncating(n as u128, tcx.sess.target.uint_type);
+    Value::Const(ConstVal::Integral(ConstInt::Usize(n)))
+}
+
+struct Frame<'m, 'tcx: 'm> {
+    mir: &'m Mir<'tcx>,
+    substs: &'tcx Substs<'tcx>,
+
+    /// Whether this is a `const fn` call rather than the body of a constant,
+    /// whose locals outlive it as its value can borrow from them.
+    is_call: bool,
+
+    /// The allocation for the current storage of each local.
+    locals: IndexVec<Local, AllocId>,
+}
+
+struct Evaluator<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    memory: Memory<'tcx>,
+    steps: usize,
+    /// The number of `const fn` calls being evaluated.
+    depth: usize,
+}
+
+impl<'a, 'tcx> Evaluator<'a, 'tcx> {
+    fn new(tcx: TyCtxt<'a, 'tcx, 'tcx>) -> Evaluator<'a, 'tcx> {
+        Evaluator {
+            tcx: tcx,
+            memory: Memory::new(),
+            steps: 0,
+            depth: 0,
+        }
+    }
+
+    fn monomorphize<T>(&self, substs: &'tcx Substs<'tcx>, value: &T) -> T
+        where T: TransNormalize<'tcx>
+    {
+        self.normalize(&value.subst(self.tcx, substs))
+    }
+
+    /// Normalizes `value`, which can still mention type parameters when
+    /// evaluating on behalf of a generic item.
+    fn normalize<T>(&self, value: &T) -> T
+        where T: TransNormalize<'tcx>
+    {
+        if value.has_param_types() || value.has_self_ty() {
+            self.tcx.erase_regions(value)
+        } else {
+            self.tcx.normalize_associated_type(value)
+        }
+    }
+
+    fn step(&mut self, span: Span) -> Result<(), ConstEvalErr<'tcx>> {
+        self.steps += 1;
+        if self.steps > STEP_LIMIT {
+            signal!(span, ErrKind::LimitReached("step"));
+        }
+        Ok(())
+    }
+
+    fn eval_call(&mut self,
+                 def_id: DefId,
+                 substs: &'tcx Substs<'tcx>,
+                 args: Vec<Value<'tcx>>,
+                 span: Span)
+                 -> Result<Value<'tcx>, ConstEvalErr<'tcx>> {
+        if !is_const_fn(self.tcx, def_id) {
+            signal!(span, ErrKind::TypeckError);
+        }
+        if !self.tcx.is_mir_available(def_id) {
+            signal!(span, ErrKind::UnimplementedConstVal("calls to functions without MIR"));
+        }
+        if self.depth >= self.tcx.sess.recursion_limit.get() {
+            signal!(span, ErrKind::LimitReached("recursion"));
+        }
+
+        debug!("eval_call({:?}, {:?}, {:?})", def_id, substs, args);
+        let mir = self.tcx.optimized_mir(def_id);
+        self.depth += 1;
+        let result = self.eval_body(mir, substs, true, args);
+        self.depth -= 1;
+        result.map_err(|err| ConstEvalErr {
+            span: span,
+            kind: ErrKind::InConstFn(def_id, box err)
+        })
+    }
+
+    fn eval_body(&mut self,
+                 mir: &Mir<'tcx>,
+                 substs: &'tcx Substs<'tcx>,
+                 is_call: bool,
+                 args: Vec<Value<'tcx>>)
+                 -> Result<Value<'tcx>, ConstEvalErr<'tcx>> {
+        let mut args = args.into_iter();
+        let locals = mir.local_decls.indices().map(|local| {
+            let value = match mir.local_kind(local) {
+                mir::LocalKind::Arg => args.next().unwrap_or(Value::Undef),
+                _ => Value::Undef
+            };
+            self.memory.allocate(value)
+        }).collect();
+
+        let mut frame = Frame {
+            mir: mir,
+            substs: substs,
+            is_call: is_call,
+            locals: locals,
+        };
+        let result = self.run(&mut frame);
+        if is_call {
+            for &alloc in &frame.locals {
+                self.memory.deallocate(alloc);
+            }
+        }
+        result
+    }
+
+    fn run<'m>(&mut self, frame: &mut Frame<'m, 'tcx>) -> Result<Value<'tcx>, ConstEvalErr<'tcx>> {
+        let mir = frame.mir;
+        let mut bb = mir::START_BLOCK;
+        loop {
+            let data = &mir[bb];
+            for statement in &data.statements {
+                let span = statement.source_info.span;
+                self.step(span)?;
+                self.eval_statement(frame, &statement.kind, span)?;
+            }
+
+            let terminator = data.terminator();
+            let span = terminator.source_info.span;
+            self.step(span)?;
+            bb = match terminator.kind {
+                TerminatorKind::Goto { target } |
+                // Values don't own any resources, dropping them is a no-op.
+                TerminatorKind::Drop { target, .. } => target,
+                TerminatorKind::DropAndReplace { ref location, ref value, target, .. } => {
+                    let value = self.eval_operand(frame, value, span)?;
+                    let dest = self.eval_lvalue(frame, location, span)?;
+                    spanned(span, self.memory.write(&dest, value))?;
+                    target
+                }
+                TerminatorKind::SwitchInt { ref discr, ref values, ref targets, .. } => {
+                    let discr = match self.eval_scalar(frame, discr, span)?.to_const_int() {
+                        Some(discr) => discr.to_u128_unchecked(),
+                        None => span_bug!(span, "switching on a non-integer")
+                    };
+                    match values.iter().position(|v| v.to_u128_unchecked() == discr) {
+                        Some(i) => targets[i],
+                        None => *targets.last().unwrap()
+                    }
+                }
+                TerminatorKind::Assert { ref cond, expected, ref msg, target, .. } => {
+                    let cond = match self.eval_scalar(frame, cond, span)? {
+                        ConstVal::Bool(cond) => cond,
+                        _ => span_bug!(span, "asserting a non-boolean")
+                    };
+                    if cond != expected {
+                        let kind = match *msg {
+                            mir::AssertMessage::BoundsCheck { ref len, ref index } => {
+                                ErrKind::IndexOutOfBounds {
+                                    len: self.eval_usize(frame, len, span)?,
+                                    index: self.eval_usize(frame, index, span)?
+                                }
+                            }
+                            mir::AssertMessage::Math(ref err) => ErrKind::Math(err.clone())
+                        };
+                        signal!(span, kind);
+                    }
+                    target
+                }
+                TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                    let (def_id, substs) = match self.eval_operand(frame, func, span)? {
+                        Value::Const(ConstVal::Function(def_id, substs)) => (def_id, substs),
+                        _ => signal!(span, ErrKind::UnimplementedConstVal("indirect calls"))
+                    };
+                    let (dest, target) = match *destination {
+                        Some((ref dest, target)) => (dest, target),
+                        None => signal!(span, ErrKind::UnimplementedConstVal("diverging calls"))
+                    };
+                    let args = args.iter().map(|arg| {
+                        self.eval_operand(frame, arg, span)
+                    }).collect::<Result<Vec<_>, _>>()?;
+                    let value = self.eval_call(def_id, substs, args, span)?;
+                    let dest = self.eval_lvalue(frame, dest, span)?;
+                    spanned(span, self.memory.write(&dest, value))?;
+                    target
+                }
+                TerminatorKind::Return => {
+                    let ret = Pointer::new(frame.locals[mir::RETURN_POINTER]);
+                    return Ok(spanned(span, self.memory.read(&ret))?.clone());
+                }
+                TerminatorKind::Unreachable => {
+                    signal!(span, ErrKind::UndefinedBehavior("entering unreachable code"))
+                }
+                TerminatorKind::Resume => {
+                    signal!(span, ErrKind::UnimplementedConstVal("unwinding"))
+                }
+            };
+        }
+    }
+
+    fn eval_statement<'m>(&mut self,
+                          frame: &mut Frame<'m, 'tcx>,
+                          kind: &StatementKind<'tcx>,
+                          span: Span)
+                          -> Result<(), ConstEvalErr<'tcx>> {
+        match *kind {
+            StatementKind::Assign(ref lvalue, ref rvalue) => {
+                let value = self.eval_rvalue(frame, rvalue, span)?;
+                let dest = self.eval_lvalue(frame, lvalue, span)?;
+                spanned(span, self.memory.write(&dest, value))?;
+            }
+            StatementKind::SetDiscriminant { ref lvalue, variant_index } => {
+                let dest = self.eval_lvalue(frame, lvalue, span)?;
+                spanned(span, self.memory.set_variant(&dest, variant_index))?;
+            }
+            // The locals of a constant live on in its value, so only the
+            // storage of locals in calls begins and ends.
+            StatementKind::StorageLive(Lvalue::Local(local)) if frame.is_call => {
+                self.memory.deallocate(frame.locals[local]);
+                frame.locals[local] = self.memory.allocate(Value::Undef);
+            }
+            StatementKind::StorageDead(Lvalue::Local(local)) if frame.is_call => {
+                self.memory.deallocate(frame.locals[local]);
+            }
+            StatementKind::StorageLive(_) |
+            StatementKind::StorageDead(_) |
+            StatementKind::Nop => {}
+            StatementKind::InlineAsm { .. } => {
+                signal!(span, ErrKind::UnimplementedConstVal("inline assembly"))
+            }
+        }
+        Ok(())
+    }
+
+    fn eval_lvalue<'m>(&mut self,
+                       frame: &Frame<'m, 'tcx>,
+                       lvalue: &Lvalue<'tcx>,
+                       span: Span)
+                       -> Result<Pointer, ConstEvalErr<'tcx>> {
+        let proj = match *lvalue {
+            Lvalue::Local(local) => return Ok(Pointer::new(frame.locals[local])),
+            Lvalue::Static(_) => signal!(span, ErrKind::UnimplementedConstVal("statics")),
+            Lvalue::Projection(ref proj) => proj
+        };
+
+        let base = self.eval_lvalue(frame, &proj.base, span)?;
+        let elem = match proj.elem {
+            ProjectionElem::Deref => return self.deref(&base, span),
+            ProjectionElem::Field(field, _) => PathElem::Field(field.index()),
+            ProjectionElem::Downcast(_, variant) => PathElem::Downcast(variant),
+            ProjectionElem::Index(ref index) => {
+                let index = self.eval_usize(frame, index, span)?;
+                let len = self.len(frame, &proj.base, &base, span)?;
+                if index >= len {
+                    signal!(span, ErrKind::IndexOutOfBounds { len: len, index: index });
+                }
+                PathElem::Field(index as usize)
+            }
+            ProjectionElem::ConstantIndex { offset, from_end, .. } => {
+                let offset = offset as u64;
+                let index = if from_end {
+                    self.len(frame, &proj.base, &base, span)? - offset
+                } else {
+                    offset
+                };
+                PathElem::Field(index as usize)
+            }
+            ProjectionElem::Subslice { .. } => {
+                signal!(span, ErrKind::UnimplementedConstVal("subslice patterns"))
+            }
+        };
+        Ok(base.project(elem))
+    }
+
+    fn deref(&mut self, ptr: &Pointer, span: Span) -> Result<Pointer, ConstEvalErr<'tcx>> {
+        let bytes = match *spanned(span, self.memory.read(ptr))? {
+            Value::Ptr(ref target) => return Ok(target.clone()),
+            Value::Const(ConstVal::ByteStr(ref bytes)) => bytes.clone(),
+            Value::Undef => {
+                signal!(span, ErrKind::UndefinedBehavior("use of an uninitialized value"))
+            }
+            _ => signal!(span, ErrKind::UnimplementedConstVal("dereferencing string literals"))
+        };
+
+        // The bytes of a byte string literal only get an address once used.
+        let fields = bytes.iter().map(|&b| {
+            Value::Const(ConstVal::Integral(ConstInt::U8(b)))
+        }).collect();
+        let alloc = self.memory.allocate(Value::Aggregate { variant: 0, fields: fields });
+        Ok(Pointer::new(alloc))
+    }
+
+    /// The length of the array or slice `lvalue`, which `ptr` points to.
+    fn len<'m>(&self,
+               frame: &Frame<'m, 'tcx>,
+               lvalue: &Lvalue<'tcx>,
+               ptr: &Pointer,
+               span: Span)
+               -> Result<u64, ConstEvalErr<'tcx>> {
+        if let ty::TyArray(_, n) = lvalue.ty(frame.mir, self.tcx).to_ty(self.tcx).sty {
+            return Ok(n as u64);
+        }
+        match *spanned(span, self.memory.read(ptr))? {
+            Value::Aggregate { ref fields, .. } => Ok(fields.len() as u64),
+            _ => signal!(span, ErrKind::UndefinedBehavior("use of an uninitialized value"))
+        }
+    }
+
+    fn eval_operand<'m>(&mut self,
+                        frame: &Frame<'m, 'tcx>,
+                        operand: &Operand<'tcx>,
+                        span: Span)
+                        -> Result<Value<'tcx>, ConstEvalErr<'tcx>> {
+        let constant = match *operand {
+            Operand::Consume(ref lvalue) => {
+                let ptr = self.eval_lvalue(frame, lvalue, span)?;
+                return Ok(spanned(span, self.memory.read(&ptr))?.clone());
+            }
+            Operand::Constant(ref constant) => constant
+        };
+
+        match constant.literal {
+            mir::Literal::Item { def_id, substs } => {
+                let substs = self.monomorphize(frame.substs, &substs);
+                self.eval_const_item(def_id, substs, constant.span)
+            }
+            mir::Literal::Promoted { index } => {
+                self.eval_body(&frame.mir.promoted[index], frame.substs, false, vec![])
+            }
+            mir::Literal::Value { value: ConstVal::Function(def_id, substs) } => {
+                let substs = self.monomorphize(frame.substs, &substs);
+                Ok(Value::Const(ConstVal::Function(def_id, substs)))
+            }
+            mir::Literal::Value { ref value } => {
+                let ty = self.monomorphize(frame.substs, &constant.ty);
+                spanned(constant.span, self.const_to_value(value.clone(), ty))
+            }
+        }
+    }
+
+    fn eval_const_item(&mut self,
+                       def_id: DefId,
+                       substs: &'tcx Substs<'tcx>,
+                       span: Span)
+                       -> Result<Value<'tcx>, ConstEvalErr<'tcx>> {
+        let (def_id, substs) = match lookup_const_by_id(self.tcx, def_id, substs) {
+            Some(resolved) => resolved,
+            None => signal!(span, ErrKind::TypeckError)
+        };
+
+        let result = if self.tcx.is_mir_available(def_id) {
+            let mir = self.tcx.optimized_mir(def_id);
+            self.eval_body(mir, substs, false, vec![])
+        } else {
+            let ty = self.monomorphize(substs, &self.tcx.type_of(def_id));
+            self.tcx.at(span).const_eval((def_id, substs)).and_then(|value| {
+                spanned(span, self.const_to_value(value, ty))
+            })
+        };
+        result.map_err(|err| match err.kind {
+            ErrKind::TypeckError => err,
+            _ => ConstEvalErr {
+                span: span,
+                kind: ErrKind::ErroneousReferencedConstant(box err)
+            }
+        })
+    }
+
+    fn eval_scalar<'m>(&mut self,
+                       frame: &Frame<'m, 'tcx>,
+                       operand: &Operand<'tcx>,
+                       span: Span)
+                       -> Result<ConstVal<'tcx>, ConstEvalErr<'tcx>> {
+        match self.eval_operand(frame, operand, span)? {
+            Value::Const(value) => Ok(value),
+            Value::Undef => {
+                signal!(span, ErrKind::UndefinedBehavior("use of an uninitialized value"))
+            }
+            Value::Aggregate { .. } | Value::Ptr(_) => {
+                signal!(span, ErrKind::UnimplementedConstVal("operations on pointers"))
+            }
+        }
+    }
+
+    fn eval_usize<'m>(&mut self,
+                      frame: &Frame<'m, 'tcx>,
+                      operand: &Operand<'tcx>,
+                      span: Span)
+                      -> Result<u64, ConstEvalErr<'tcx>> {
+        match self.eval_scalar(frame, operand, span)? {
+            ConstVal::Integral(ConstInt::Usize(n)) => {
+                Ok(n.as_u64(self.tcx.sess.target.uint_type))
+            }
+            _ => span_bug!(span, "expected a `usize` operand")
+        }
+    }
+
+    fn eval_rvalue<'m>(&mut self,
+                       frame: &Frame<'m, 'tcx>,
+                       rvalue: &Rvalue<'tcx>,
+                       span: Span)
+                       -> Result<Value<'tcx>, ConstEvalErr<'tcx>> {
+        let tcx = self.tcx;
+        let value = match *rvalue {
+            Rvalue::Use(ref operand) => self.eval_operand(frame, operand, span)?,
+
+            Rvalue::Repeat(ref operand, count) => {
+                let count = count.as_u64(tcx.sess.target.uint_type);
+                if count > REPEAT_LIMIT {
+                    signal!(span, ErrKind::UnimplementedConstVal("large repeat expressions"));
+                }
+                let value = self.eval_operand(frame, operand, span)?;
+                Value::Aggregate { variant: 0, fields: vec![value; count as usize] }
+            }
+
+            Rvalue::Ref(_, _, ref lvalue) => Value::Ptr(self.eval_lvalue(frame, lvalue, span)?),
+
+            Rvalue::Len(ref lvalue) => {
+                let ptr = self.eval_lvalue(frame, lvalue, span)?;
+                usize_value(tcx, self.len(frame, lvalue, &ptr, span)?)
+            }
+
+            Rvalue::Cast(kind, ref operand, cast_ty) => {
+                let value = self.eval_operand(frame, operand, span)?;
+                let cast_ty = self.monomorphize(frame.substs, &cast_ty);
+                match kind {
+                    CastKind::ReifyFnPointer | CastKind::UnsafeFnPointer => value,
+                    CastKind::ClosureFnPointer => {
+                        signal!(span, ErrKind::UnimplementedConstVal("closures"))
+                    }
+                    CastKind::Unsize => self.unsize(value, cast_ty, span)?,
+                    CastKind::Misc => {
+                        let from_ty = operand.ty(frame.mir, tcx);
+                        let from_ty = self.monomorphize(frame.substs, &from_ty);
+                        self.cast(value, from_ty, cast_ty, span)?
+                    }
+                }
+            }
+
+            Rvalue::BinaryOp(op, ref lhs, ref rhs) => {
+                let lhs = self.eval_scalar(frame, lhs, span)?;
+                let rhs = self.eval_scalar(frame, rhs, span)?;
+                Value::Const(spanned(span, binary_op(op, lhs, rhs))?)
+            }
+
+            Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs) => {
+                let lhs = self.eval_scalar(frame, lhs, span)?;
+                let rhs = self.eval_scalar(frame, rhs, span)?;
+                let (value, overflowed) = match binary_op(op, lhs, rhs) {
+                    Ok(value) => (Value::Const(value), false),
+                    // The overflow is reported by the `Assert` that follows.
+                    Err(ErrKind::Math(ConstMathErr::Overflow(_))) |
+                    Err(ErrKind::Math(ConstMathErr::ShiftNegative)) => (Value::Undef, true),
+                    Err(kind) => signal!(span, kind)
+                };
+                Value::Aggregate {
+                    variant: 0,
+                    fields: vec![value, Value::Const(ConstVal::Bool(overflowed))]
+                }
+            }
+
+            Rvalue::UnaryOp(op, ref operand) => {
+                let value = self.eval_scalar(frame, operand, span)?;
+                Value::Const(spanned(span, unary_op(op, value))?)
+            }
+
+            Rvalue::NullaryOp(NullOp::SizeOf, ty) => {
+                let ty = self.monomorphize(frame.substs, &ty);
+                let size = tcx.infer_ctxt((), Reveal::All).enter(|infcx| {
+                    ty.layout(&infcx).ok().map(|layout| layout.size(&tcx.data_layout).bytes())
+                });
+                match size {
+                    Some(size) => usize_value(tcx, size),
+                    None => signal!(span, ErrKind::UnimplementedConstVal("size of this type"))
+                }
+            }
+
+            Rvalue::NullaryOp(NullOp::Box, _) => {
+                signal!(span, ErrKind::UnimplementedConstVal("box"))
+            }
+
+            Rvalue::Discriminant(ref lvalue) => {
+                let ty = lvalue.ty(frame.mir, tcx).to_ty(tcx);
+                let ptr = self.eval_lvalue(frame, lvalue, span)?;
+                let variant = match *spanned(span, self.memory.read(&ptr))? {
+                    Value::Aggregate { variant, .. } => variant,
+                    _ => signal!(span, ErrKind::UndefinedBehavior("use of an uninitialized value"))
+                };
+                match ty.sty {
+                    ty::TyAdt(adt, _) if adt.is_enum() => {
+                        let discr = adt.discriminant_for_variant(tcx, variant);
+                        Value::Const(ConstVal::Integral(discr))
+                    }
+                    _ => span_bug!(span, "discriminant of non-enum `{}`", ty)
+                }
+            }
+
+            Rvalue::Aggregate(ref kind, ref operands) => {
+                let mut fields = operands.iter().map(|operand| {
+                    self.eval_operand(frame, operand, span)
+                }).collect::<Result<Vec<_>, _>>()?;
+                match **kind {
+                    AggregateKind::Adt(_, _, _, Some(active_field)) => {
+                        // Only the active field of a union is initialized.
+                        let value = fields.pop().unwrap();
+                        let mut fields = vec![Value::Undef; active_field];
+                        fields.push(value);
+                        Value::Aggregate { variant: 0, fields: fields }
+                    }
+                    AggregateKind::Adt(_, variant, _, None) => {
+                        Value::Aggregate { variant: variant, fields: fields }
+                    }
+                    AggregateKind::Array(_) |
+                    AggregateKind::Tuple |
+                    AggregateKind::Closure(..) => {
+                        Value::Aggregate { variant: 0, fields: fields }
+                    }
+                }
+            }
+        };
+        Ok(value)
+    }
+
+    fn unsize(&mut self, value: Value<'tcx>, cast_ty: Ty<'tcx>, span: Span)
+              -> Result<Value<'tcx>, ConstEvalErr<'tcx>> {
+        let pointee = cast_ty.builtin_deref(true, ty::NoPreference).map(|mt| mt.ty);
+        match (value, pointee.map(|ty| &ty.sty)) {
+            // The length of a slice is that of its array.
+            (Value::Ptr(ptr), Some(&ty::TySlice(_))) => Ok(Value::Ptr(ptr)),
+            (value @ Value::Const(ConstVal::ByteStr(_)), Some(&ty::TySlice(_))) => {
+                let alloc = self.memory.allocate(value);
+                self.deref(&Pointer::new(alloc), span).map(Value::Ptr)
+            }
+            _ => signal!(span, ErrKind::UnimplementedConstVal("unsizing casts"))
+        }
+    }
+
+    fn cast(&self, value: Value<'tcx>, from_ty: Ty<'tcx>, cast_ty: Ty<'tcx>, span: Span)
+            -> Result<Value<'tcx>, ConstEvalErr<'tcx>> {
+        let value = match (value, &from_ty.sty) {
+            (Value::Const(value), _) => value,
+            (Value::Aggregate { variant, .. }, &ty::TyAdt(adt, _)) if adt.is_enum() => {
+                ConstVal::Variant(adt.variants[variant].did)
+            }
+            (Value::Ptr(ptr), _) if cast_ty.is_unsafe_ptr() => return Ok(Value::Ptr(ptr)),
+            (Value::Undef, _) => {
+                signal!(span, ErrKind::UndefinedBehavior("use of an uninitialized value"))
+            }
+            _ => signal!(span, ErrKind::UnimplementedConstVal("casting this value"))
+        };
+        Ok(Value::Const(spanned(span, cast_const(self.tcx, value, cast_ty))?))
+    }
+
+    /// The types of the fields of `variant` of `ty`.
+    fn field_tys(&self, ty: Ty<'tcx>, variant: usize) -> Vec<Ty<'tcx>> {
+        match ty.sty {
+            ty::TyAdt(adt, substs) => {
+                adt.variants[variant].fields.iter().map(|field| {
+                    self.normalize(&field.ty(self.tcx, substs))
+                }).collect()
+            }
+            ty::TyTuple(tys, _) => tys.to_vec(),
+            ty::TyClosure(def_id, substs) => substs.upvar_tys(def_id, self.tcx).collect(),
+            ty::TyArray(ty, n) => vec![ty; n],
+            _ => bug!("field types of `{}`", ty)
+        }
+    }
+
+    /// Converts a constant evaluated on the HIR, which represents aggregates
+    /// differently, to a value.
+    fn const_to_value(&mut self, value: ConstVal<'tcx>, ty: Ty<'tcx>)
+                      -> Result<Value<'tcx>, ErrKind<'tcx>> {
+        let fields = match value {
+            ConstVal::Variant(def_id) => {
+                let variant = match ty.sty {
+                    ty::TyAdt(adt, _) => adt.variant_index_with_id(def_id),
+                    _ => bug!("enum variant of type `{}`", ty)
+                };
+                return Ok(Value::Aggregate { variant: variant, fields: vec![] });
+            }
+            ConstVal::Struct(mut fields) => {
+                let names = match ty.sty {
+                    ty::TyAdt(adt, _) => adt.struct_variant().fields.iter().map(|f| f.name),
+                    _ => bug!("struct of type `{}`", ty)
+                };
+                names.map(|name| fields.remove(&name)).collect::<Vec<_>>()
+            }
+            ConstVal::Tuple(fields) |
+            ConstVal::Array(fields) => fields.into_iter().map(Some).collect(),
+            ConstVal::Repeat(value, n) => {
+                if n > REPEAT_LIMIT {
+                    return Err(ErrKind::UnimplementedConstVal("large repeat expressions"));
+                }
+                vec![Some(*value); n as usize]
+            }
+            value => return Ok(Value::Const(value))
+        };
+
+        let field_tys = self.field_tys(ty, 0);
+        let fields = fields.into_iter().zip(field_tys).map(|(field, ty)| {
+            match field {
+                Some(field) => self.const_to_value(field, ty),
+                None => Ok(Value::Undef)
+            }
+        }).collect::<Result<_, _>>()?;
+        Ok(Value::Aggregate { variant: 0, fields: fields })
+    }
+
+    /// Converts a value to its representation as a HIR constant.
+    fn value_to_const(&self, value: Value<'tcx>, ty: Ty<'tcx>)
+                      -> Result<ConstVal<'tcx>, ErrKind<'tcx>> {
+        let (variant, mut fields) = match value {
+            Value::Const(value) => return Ok(value),
+            Value::Aggregate { variant, fields } => (variant, fields),
+            Value::Ptr(_) => return Err(ErrKind::UnimplementedConstVal("references")),
+            Value::Undef => {
+                return Err(ErrKind::UndefinedBehavior("use of an uninitialized value"))
+            }
+        };
+
+        let field_tys = self.field_tys(ty, variant);
+        let is_union = match ty.sty {
+            ty::TyAdt(adt, _) => adt.is_union(),
+            _ => false
+        };
+        if !is_union {
+            while fields.len() < field_tys.len() {
+                fields.push(Value::Undef);
+            }
+        }
+        let mut fields = fields.into_iter().zip(field_tys).map(|(field, ty)| {
+            match field {
+                // Only the initialized fields of a union are constants.
+                Value::Undef if is_union => Ok(None),
+                field => self.value_to_const(field, ty).map(Some)
+            }
+        }).collect::<Result<Vec<_>, _>>()?.into_iter();
+
+        match ty.sty {
+            ty::TyArray(..) => Ok(ConstVal::Array(fields.map(Option::unwrap).collect())),
+            ty::TyTuple(..) => Ok(ConstVal::Tuple(fields.map(Option::unwrap).collect())),
+            ty::TyAdt(adt, _) if adt.is_enum() => {
+                let variant = &adt.variants[variant];
+                if variant.fields.is_empty() {
+                    Ok(ConstVal::Variant(variant.did))
+                } else {
+                    Err(ErrKind::UnimplementedConstVal("enum variants"))
+                }
+            }
+            ty::TyAdt(adt, _) => {
+                let variant = adt.struct_variant();
+                if variant.ctor_kind == CtorKind::Fn {
+                    return Ok(ConstVal::Tuple(fields.map(Option::unwrap).collect()));
+                }
+                Ok(ConstVal::Struct(variant.fields.iter().zip(&mut fields).filter_map(|(f, v)| {
+                    v.map(|v| (f.name, v))
+                }).collect()))
+            }
+            _ => Err(ErrKind::UnimplementedConstVal("closures"))
+        }
+    }
+}
+
+/// Applies `op` to two scalar constants. Overflow is reported as an
+/// `ErrKind::Math` error, which MIR passes rely on to tell it apart.
+pub fn binary_op<'tcx>(op: BinOp, lhs: ConstVal<'tcx>, rhs: ConstVal<'tcx>)
+                       -> Result<ConstVal<'tcx>, ErrKind<'tcx>> {
+    use rustc::middle::const_val::ConstVal::*;
+
+    let value = match (lhs, rhs) {
+        (Integral(a), Integral(b)) => match op {
+            BinOp::Add => Integral((a + b)?),
+            BinOp::Sub => Integral((a - b)?),
+            BinOp::Mul => Integral((a * b)?),
+            BinOp::Div => Integral((a / b)?),
+            BinOp::Rem => Integral((a % b)?),
+            BinOp::BitAnd => Integral((a & b)?),
+            BinOp::BitOr => Integral((a | b)?),
+            BinOp::BitXor => Integral((a ^ b)?),
+            BinOp::Shl => Integral((a << b)?),
+            BinOp::Shr => Integral((a >> b)?),
+            BinOp::Offset => return Err(ErrKind::UnimplementedConstVal("pointer offsets")),
+            _ => Bool(compare(op, a.try_cmp(b)?))
+        },
+        (Float(a), Float(b)) => match op {
+            BinOp::Add => Float((a + b)?),
+            BinOp::Sub => Float((a - b)?),
+            BinOp::Mul => Float((a * b)?),
+            BinOp::Div => Float((a / b)?),
+            BinOp::Rem => Float((a % b)?),
+            _ => Bool(compare(op, a.try_cmp(b)?))
+        },
+        (Bool(a), Bool(b)) => match op {
+            BinOp::BitAnd => Bool(a & b),
+            BinOp::BitOr => Bool(a | b),
+            BinOp::BitXor => Bool(a ^ b),
+            _ => Bool(compare(op, a.cmp(&b)))
+        },
+        (Char(a), Char(b)) => Bool(compare(op, a.cmp(&b))),
+        _ => return Err(ErrKind::MiscBinaryOp)
+    };
+    Ok(value)
+}
+
+/// Applies `op` to a scalar constant.
+pub fn unary_op<'tcx>(op: UnOp, value: ConstVal<'tcx>) -> Result<ConstVal<'tcx>, ErrKind<'tcx>> {
+    let value = match (op, value) {
+        (UnOp::Not, ConstVal::Integral(i)) => ConstVal::Integral((!i)?),
+        (UnOp::Not, ConstVal::Bool(b)) => ConstVal::Bool(!b),
+        (UnOp::Neg, ConstVal::Integral(i)) => ConstVal::Integral((-i)?),
+        (UnOp::Neg, ConstVal::Float(f)) => ConstVal::Float(-f),
+        (UnOp::Not, value) => return Err(ErrKind::NotOn(value)),
+        (UnOp::Neg, value) => return Err(ErrKind::NegateOn(value))
+    };
+    Ok(value)
+}
+
+fn compare(op: BinOp, ordering: Ordering) -> bool {
+    match op {
+        BinOp::Eq => ordering == Ordering::Equal,
+        BinOp::Ne => ordering != Ordering::Equal,
+        BinOp::Lt => ordering == Ordering::Less,
+        BinOp::Le => ordering != Ordering::Greater,
+        BinOp::Gt => ordering == Ordering::Greater,
+        BinOp::Ge => ordering != Ordering::Less,
+        _ => bug!("`{:?}` is not a comparison", op)
+    }
+}
@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-//! constant evaluation on the HIR and code to validate patterns/matches
+//! constant evaluation on the HIR and the MIR, and code to validate patterns/matches
 //!
 //! # Note
 //!
@@ -48,6 +48,7 @@ extern crate syntax_pos;
 pub mod diagnostics;
 
 mod eval;
+pub mod interpret;
 mod _match;
 pub mod check_match;
 pub mod pattern;
@@ -0,0 +1,153 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for `-Z apply-suggestions`: once compilation is over, every
+//! machine-applicable suggestion that was emitted is spliced into the
+//! source file it points into, and the file is rewritten in place.
+//!
+//! Suggestions are applied all-or-nothing: if one of the spans of a
+//! suggestion comes from a macro expansion, is not in a real file, or
+//! overlaps an edit that was already accepted, the whole suggestion is
+//! skipped.
+//!
+//! The edits are made to the file as it is on disk rather than to the
+//! source the compiler read, which has had any byte order mark removed.
+
+use errors::CodeSuggestion;
+use rustc::session::Session;
+use syntax::codemap::CodeMap;
+use syntax_pos::{FileMap, Pos, NO_EXPANSION};
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+
+/// A single replacement of the bytes `lo..hi` of a file.
+struct Edit {
+    lo: usize,
+    hi: usize,
+    replacement: String,
+}
+
+impl Edit {
+    fn overlaps(&self, other: &Edit) -> bool {
+        // Two insertions at the same point are ambiguous as well.
+        self.lo < other.hi && other.lo < self.hi ||
+            self.lo == other.lo
+    }
+}
+
+struct FileEdits {
+    src: Rc<String>,
+    edits: Vec<Edit>,
+}
+
+pub fn apply_suggestions(sess: &Session) {
+    let codemap = sess.codemap();
+    let mut files: BTreeMap<String, FileEdits> = BTreeMap::new();
+
+    for suggestion in sess.diagnostic().take_applicable_suggestions() {
+        let edits = match suggestion_edits(codemap, &suggestion) {
+            Some(edits) => edits,
+            None => continue,
+        };
+
+        let conflicts = edits.iter().any(|&(ref fm, ref edit)| {
+            files.get(&fm.name).map_or(false, |file| {
+                file.edits.iter().any(|accepted| accepted.overlaps(edit))
+            })
+        });
+        if conflicts {
+            debug!("apply_suggestions: skipping conflicting suggestion `{}`", suggestion.msg);
+            continue;
+        }
+
+        for (fm, edit) in edits {
+            files.entry(fm.name.clone()).or_insert_with(|| {
+                FileEdits {
+                    src: fm.src.clone().unwrap(),
+                    edits: vec![],
+                }
+            }).edits.push(edit);
+        }
+    }
+
+    for (name, mut file) in files {
+        let result = apply_edits(&name, &mut file).and_then(|src| {
+            File::create(&name).and_then(|mut f| f.write_all(src.as_bytes()))
+        });
+        match result {
+            Ok(()) => {
+                sess.note_without_error(&format!("applied {} suggestion(s) to `{}`",
+                                                 file.edits.len(),
+                                                 name));
+            }
+            Err(e) => {
+                sess.err(&format!("failed to apply suggestions to `{}`: {}", name, e));
+            }
+        }
+    }
+}
+
+/// Reads `name` from disk and returns its contents with the edits applied.
+fn apply_edits(name: &str, file: &mut FileEdits) -> io::Result<String> {
+    let mut src = String::new();
+    File::open(name)?.read_to_string(&mut src)?;
+
+    // The offsets of the edits don't count the byte order mark.
+    let bom_len = if src.starts_with("\u{feff}") { "\u{feff}".len() } else { 0 };
+    if src[bom_len..] != file.src[..] {
+        return Err(io::Error::new(io::ErrorKind::Other,
+                                  "the file has changed since it was compiled"));
+    }
+
+    // Splice from the back so that earlier offsets stay valid.
+    file.edits.sort_by(|a, b| b.lo.cmp(&a.lo));
+    for edit in &file.edits {
+        let (lo, hi) = (bom_len + edit.lo, bom_len + edit.hi);
+        src = format!("{}{}{}", &src[..lo], edit.replacement, &src[hi..]);
+    }
+    Ok(src)
+}
+
+/// Converts a suggestion into per-file edits, or returns `None` if it cannot
+/// be applied safely.
+fn suggestion_edits(codemap: &CodeMap,
+                    suggestion: &CodeSuggestion)
+                    -> Option<Vec<(Rc<FileMap>, Edit)>> {
+    let mut edits = vec![];
+    for part in &suggestion.substitution_parts {
+        let span = part.span;
+        if span.ctxt != NO_EXPANSION {
+            return None;
+        }
+
+        let lo = codemap.lookup_byte_offset(span.lo);
+        let hi = codemap.lookup_byte_offset(span.hi);
+        if !lo.fm.is_real_file() ||
+           lo.fm.name_was_remapped ||
+           lo.fm.src.is_none() ||
+           lo.fm.name != hi.fm.name {
+            return None;
+        }
+
+        let edit = Edit {
+            lo: lo.pos.to_usize(),
+            hi: hi.pos.to_usize(),
+            replacement: part.substitutions[0].clone(),
+        };
+        if edits.iter().any(|&(ref fm, ref other)| fm.name == lo.fm.name && edit.overlaps(other)) {
+            return None;
+        }
+        edits.push((lo.fm, edit));
+    }
+    Some(edits)
+}
@@ -0,0 +1,117 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Expansion of `@path` argument files.
+//!
+//! An argument of the form `@path` is replaced by the arguments read from
+//! the file at `path`, one argument per line. A line is taken verbatim,
+//! except that:
+//!
+//! * a line that is empty or only whitespace is skipped,
+//! * a line that starts with `"` is a quoted argument which must end with
+//!   `"`; inside it, `\"`, `\\`, `\n` and `\t` are escapes, so quoted lines
+//!   can express empty arguments, surrounding whitespace or newlines.
+//!
+//! Argument files are not expanded recursively: an `@path` line is passed on
+//! as is.
+
+use std::fs::File;
+use std::io::Read;
+
+/// Expands the `@path` arguments of `args`, which doesn't include the name
+/// of the binary. Returns whether any argument was expanded.
+pub fn expand(args: &[String]) -> Result<(Vec<String>, bool), String> {
+    let mut expanded = Vec::with_capacity(args.len());
+    let mut any = false;
+    for arg in args {
+        if arg.starts_with("@") && arg.len() > 1 {
+            let path = &arg[1..];
+            let mut contents = String::new();
+            File::open(path).and_then(|mut f| f.read_to_string(&mut contents)).map_err(|e| {
+                format!("failed to read argument file `{}`: {}", path, e)
+            })?;
+            for (i, line) in contents.lines().enumerate() {
+                if let Some(arg) = parse_line(line).map_err(|e| {
+                    format!("invalid argument file `{}`: line {}: {}", path, i + 1, e)
+                })? {
+                    expanded.push(arg);
+                }
+            }
+            any = true;
+        } else {
+            expanded.push(arg.clone());
+        }
+    }
+    Ok((expanded, any))
+}
+
+fn parse_line(line: &str) -> Result<Option<String>, String> {
+    // Tolerate files written with CRLF line endings.
+    let line = line.trim_right_matches('\r');
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+    if !line.starts_with("\"") {
+        return Ok(Some(line.to_string()));
+    }
+
+    let mut arg = String::new();
+    let mut chars = line[1..].chars();
+    loop {
+        match chars.next() {
+            None => return Err(format!("unterminated quoted argument")),
+            Some('"') => break,
+            Some('\\') => {
+                match chars.next() {
+                    Some('"') => arg.push('"'),
+                    Some('\\') => arg.push('\\'),
+                    Some('n') => arg.push('\n'),
+                    Some('t') => arg.push('\t'),
+                    Some(c) => return Err(format!("unknown escape `\\{}`", c)),
+                    None => return Err(format!("unterminated quoted argument")),
+                }
+            }
+            Some(c) => arg.push(c),
+        }
+    }
+    if chars.as_str().trim().is_empty() {
+        Ok(Some(arg))
+    } else {
+        Err(format!("unexpected text after quoted argument"))
+    }
+}
+
+/// Formats `args` for display, quoting the arguments that need it.
+pub fn display(args: &[String]) -> String {
+    args.iter().map(|arg| {
+        if arg.is_empty() || arg.chars().any(|c| c.is_whitespace() || c == '"') {
+            format!("{:?}", arg)
+        } else {
+            arg.clone()
+        }
+    }).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_line;
+
+    #[test]
+    fn lines() {
+        assert_eq!(parse_line("--extern"), Ok(Some("--extern".to_string())));
+        assert_eq!(parse_line("-L native=/a b/c\r"), Ok(Some("-L native=/a b/c".to_string())));
+        assert_eq!(parse_line("   "), Ok(None));
+        assert_eq!(parse_line("\"\""), Ok(Some(String::new())));
+        assert_eq!(parse_line("\" a \\\"b\\\" \\\\\"  "), Ok(Some(" a \"b\" \\".to_string())));
+        assert!(parse_line("\"abc").is_err());
+        assert!(parse_line("\"a\" b").is_err());
+        assert!(parse_line("\"\\q\"").is_err());
+    }
+}
@@ -62,7 +62,7 @@ extern crate syntax;
 extern crate syntax_ext;
 extern crate syntax_pos;
 
-use driver::CompileController;
+use driver::{CompileController, CompileState};
 use pretty::{PpMode, UserIdentifiedItem};
 
 use rustc_resolve as resolve;
@@ -80,16 +80,21 @@ use rustc::lint;
 use rustc_metadata::locator;
 use rustc_metadata::cstore::CStore;
 use rustc::util::common::time;
+use rustc::util::profiling;
 
-use serialize::json::ToJson;
+use serialize::json::{self, ToJson};
 
 use std::any::Any;
+use std::cell::RefCell;
 use std::cmp::max;
 use std::cmp::Ordering::Equal;
+use std::collections::BTreeMap;
 use std::default::Default;
 use std::env;
 use std::io::{self, Read, Write};
 use std::iter::repeat;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
 use std::process;
 use std::rc::Rc;
@@ -109,6 +114,8 @@ pub mod test;
 pub mod driver;
 pub mod pretty;
 pub mod target_features;
+mod apply_suggestions;
+mod args;
 mod derive_registrar;
 
 const BUG_REPORT_URL: &'static str = "https://github.com/rust-lang/rust/blob/master/CONTRIBUTING.\
@@ -136,13 +143,18 @@ pub fn run<F>(run_compiler: F) -> isize
 {
     monitor(move || {
         let (result, session) = run_compiler();
+        if let Some(ref sess) = session {
+            sess.diagnostic().report_suppressed_diagnostics();
+        }
         if let Err(err_count) = result {
             if err_count > 0 {
                 match session {
                     Some(sess) => sess.fatal(&abort_msg(err_count)),
                     None => {
                         let emitter =
-                            errors::emitter::EmitterWriter::stderr(errors::ColorConfig::Auto, None);
+                            errors::emitter::EmitterWriter::stderr(errors::ColorConfig::Auto,
+                                                                   None,
+                                                                   false);
                         let handler = errors::Handler::with_emitter(true, false, Box::new(emitter));
                         handler.emit(&MultiSpan::new(),
                                      &abort_msg(err_count),
@@ -219,9 +231,52 @@ pub fn run_compiler<'a>(args: &[String],
     do_or_return!(callbacks.late_callback(&matches, &sess, &input, &odir, &ofile), Some(sess));
 
     let plugins = sess.opts.debugging_opts.extra_plugins.clone();
-    let control = callbacks.build_controller(&sess, &matches);
-    (driver::compile_input(&sess, &cstore, &input, &odir, &ofile, Some(plugins), &control),
-     Some(sess))
+    let mut control = callbacks.build_controller(&sess, &matches);
+    let self_profile = sess.opts.debugging_opts.self_profile;
+    if !sess.opts.debugging_opts.apply_suggestions && !self_profile {
+        return (driver::compile_input(&sess, &cstore, &input, &odir, &ofile, Some(plugins),
+                                      &control),
+                Some(sess));
+    }
+
+    // The trace is written next to the other outputs, named like them, once
+    // those are known.
+    let trace_path = Rc::new(RefCell::new(None));
+    if self_profile {
+        profiling::start();
+
+        let callback = mem::replace(&mut control.after_hir_lowering.callback, box |_| {});
+        let trace_path = trace_path.clone();
+        control.after_hir_lowering.callback = box move |state: &mut CompileState| {
+            *trace_path.borrow_mut() = state.output_filenames.map(|outputs| {
+                outputs.with_extension("trace.json")
+            });
+            callback(state);
+        };
+    }
+
+    // Errors abort compilation by unwinding, but their suggestions and the
+    // profile so far are worth writing out as well, so catch the unwinding
+    // and resume it afterwards.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        driver::compile_input(&sess, &cstore, &input, &odir, &ofile, Some(plugins), &control)
+    }));
+    if sess.opts.debugging_opts.apply_suggestions {
+        apply_suggestions::apply_suggestions(&sess);
+    }
+    if self_profile {
+        // Compilation may have stopped before the outputs were known.
+        let path = trace_path.borrow_mut().take().unwrap_or_else(|| {
+            odir.clone().unwrap_or_default().join(format!("{}.trace.json", input.filestem()))
+        });
+        if let Err(e) = profiling::finish(&path) {
+            sess.warn(&format!("failed to write self-profile to `{}`: {}", path.display(), e));
+        }
+    }
+    match result {
+        Ok(result) => (result, Some(sess)),
+        Err(payload) => panic::resume_unwind(payload),
+    }
 }
 
 // Extract output directory and file from matches.
@@ -353,12 +408,31 @@ pub struct RustcDefaultCalls;
 fn handle_explain(code: &str,
                   descriptions: &errors::registry::Registry,
                   output: ErrorOutputType) {
+    if code == "list" {
+        print_explanation_list(descriptions.all_descriptions(), output);
+        return;
+    }
+    if code.starts_with("search:") {
+        let keyword = code["search:".len()..].to_lowercase();
+        let found: Vec<_> = descriptions.all_descriptions().into_iter().filter(|&(c, d)| {
+            c.to_lowercase().contains(&keyword) || d.to_lowercase().contains(&keyword)
+        }).collect();
+        if found.is_empty() {
+            early_error(output, &format!("no extended information matches `{}`", keyword));
+        }
+        print_explanation_list(found, output);
+        return;
+    }
+
     let normalised = if code.starts_with("E") {
         code.to_string()
     } else {
         format!("E{0:0>4}", code)
     };
     match descriptions.find_description(&normalised) {
+        Some(description) if output == ErrorOutputType::Json => {
+            println!("{}", explanation_to_json(&normalised, description, true).pretty());
+        }
         Some(ref description) => {
             // Slice off the leading newline and print.
             print!("{}", &(&description[1..]).split("\n").map(|x| {
@@ -375,6 +449,46 @@ fn handle_explain(code: &str,
     }
 }
 
+/// Prints the given error codes with a one-line summary of each.
+fn print_explanation_list(descriptions: Vec<(&'static str, &'static str)>,
+                          output: ErrorOutputType) {
+    if output == ErrorOutputType::Json {
+        let list = descriptions.into_iter().map(|(code, description)| {
+            explanation_to_json(code, description, false)
+        }).collect();
+        println!("{}", json::Json::Array(list).pretty());
+        return;
+    }
+
+    for (code, description) in descriptions {
+        println!("{}  {}", code, explanation_summary(description));
+    }
+}
+
+fn explanation_to_json(code: &str, description: &str, full: bool) -> json::Json {
+    let mut obj = BTreeMap::new();
+    obj.insert("code".to_string(), code.to_json());
+    obj.insert("summary".to_string(), explanation_summary(description).to_json());
+    if full {
+        obj.insert("explanation".to_string(), description.trim().to_json());
+    }
+    json::Json::Object(obj)
+}
+
+/// The first sentence of the first paragraph of an error explanation.
+fn explanation_summary(description: &str) -> String {
+    let paragraph = description.trim_left()
+                               .lines()
+                               .take_while(|line| !line.trim().is_empty())
+                               .map(|line| line.trim())
+                               .collect::<Vec<_>>()
+                               .join(" ");
+    match paragraph.find(". ") {
+        Some(end) => paragraph[..end + 1].to_string(),
+        None => paragraph,
+    }
+}
+
 impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
     fn early_callback(&mut self,
                       matches: &getopts::Matches,
@@ -404,7 +518,7 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
                 if sopts.describe_lints {
                     let mut ls = lint::LintStore::new();
                     rustc_lint::register_builtins(&mut ls, None);
-                    describe_lints(&ls, false);
+                    describe_lints(&ls, sopts.lint_config.as_ref(), false);
                     return None;
                 }
                 let dep_graph = DepGraph::new(sopts.build_dep_graph());
@@ -518,6 +632,20 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
             control.make_glob_map = resolve::MakeGlobMap::Yes;
         }
 
+        if sess.opts.prints.contains(&PrintRequest::ExportedSymbols) {
+            control.after_analysis.stop = Compilation::Stop;
+            let old_callback = control.after_analysis.callback;
+            control.after_analysis.callback = box move |state| {
+                old_callback(state);
+                RustcDefaultCalls::print_crate_metadata(state);
+            };
+        } else if sess.opts.prints.iter().any(|&req| is_crate_metadata_print(req)) {
+            control.after_hir_lowering.stop = Compilation::Stop;
+            control.after_hir_lowering.callback = box |state| {
+                RustcDefaultCalls::print_crate_metadata(state);
+            };
+        }
+
         if sess.print_fuel_crate.is_some() {
             let old_callback = control.compilation_done.callback;
             control.compilation_done.callback = box move |state| {
@@ -532,6 +660,15 @@ impl<'a> CompilerCalls<'a> for RustcDefaultCalls {
     }
 }
 
+/// Whether `req` is handled by `print_crate_metadata` rather than by
+/// `print_crate_info`.
+fn is_crate_metadata_print(req: PrintRequest) -> bool {
+    match req {
+        PrintRequest::Deps | PrintRequest::Features | PrintRequest::ExportedSymbols => true,
+        _ => false,
+    }
+}
+
 fn save_analysis(sess: &Session) -> bool {
     sess.opts.debugging_opts.save_analysis ||
     sess.opts.debugging_opts.save_analysis_csv ||
@@ -686,10 +823,65 @@ impl RustcDefaultCalls {
                 PrintRequest::TargetCPUs | PrintRequest::TargetFeatures => {
                     rustc_trans::print(*req, sess);
                 }
+                // These need the crate to be expanded and resolved, see
+                // `print_crate_metadata`.
+                PrintRequest::Deps |
+                PrintRequest::Features |
+                PrintRequest::ExportedSymbols => {}
             }
         }
+        if sess.opts.prints.iter().any(|&req| is_crate_metadata_print(req)) {
+            return Compilation::Continue;
+        }
         return Compilation::Stop;
     }
+
+    /// Handles the print requests that need to look into the crate. Called
+    /// once the crate has been analyzed if exported symbols are requested,
+    /// or right after HIR lowering otherwise.
+    fn print_crate_metadata(state: &CompileState) {
+        let sess = state.session;
+        for &req in &sess.opts.prints {
+            match req {
+                PrintRequest::Deps => {
+                    let cstore = &sess.cstore;
+                    let mut deps: Vec<_> = cstore.crates().into_iter().map(|cnum| {
+                        let source = cstore.used_crate_source(cnum);
+                        let path = source.rlib.or(source.dylib).or(source.rmeta)
+                                         .map(|(path, _)| path.display().to_string());
+                        (cstore.crate_name(cnum).to_string(), path)
+                    }).collect();
+                    deps.sort();
+                    for (name, path) in deps {
+                        match path {
+                            Some(path) => println!("{}={}", name, path),
+                            None => println!("{}", name),
+                        }
+                    }
+                }
+                PrintRequest::Features => {
+                    let features = sess.features.borrow();
+                    let mut names: Vec<_> = features.declared_lang_features.iter()
+                        .chain(&features.declared_stable_lang_features)
+                        .chain(&features.declared_lib_features)
+                        .map(|&(name, _)| name.to_string())
+                        .collect();
+                    names.sort();
+                    names.dedup();
+                    for name in names {
+                        println!("{}", name);
+                    }
+                }
+                PrintRequest::ExportedSymbols => {
+                    let reachable = &state.analysis.unwrap().reachable;
+                    for name in rustc_trans::exported_symbol_names(state.tcx.unwrap(), reachable) {
+                        println!("{}", name);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
 }
 
 /// Returns a version string such as "0.12.0-dev".
@@ -753,7 +945,9 @@ fn usage(verbose: bool, include_unstable_options: bool) {
              extra_help);
 }
 
-fn describe_lints(lint_store: &lint::LintStore, loaded_plugins: bool) {
+fn describe_lints(lint_store: &lint::LintStore,
+                  lint_config: Option<&lint::LintConfig>,
+                  loaded_plugins: bool) {
     println!("
 Available lint options:
     -W <foo>           Warn about <foo>
@@ -885,6 +1079,48 @@ Available lint options:
             }
         }
     }
+
+    if let Some(config) = lint_config {
+        describe_lint_config(config);
+    }
+}
+
+fn describe_lint_config(config: &lint::LintConfig) {
+    let max_name_len = config.levels
+                             .iter()
+                             .chain(config.overrides.iter().flat_map(|o| &o.levels))
+                             .map(|&(ref s, _)| s.chars().count())
+                             .chain(Some("name".len()))
+                             .max()
+                             .unwrap_or(0);
+    let padded = |x: &str| {
+        let mut s = repeat(" ")
+                        .take(max_name_len - x.chars().count())
+                        .collect::<String>();
+        s.push_str(x);
+        s
+    };
+    let print_levels = |levels: &[(String, lint::Level)]| {
+        println!("    {}  {}", padded("name"), "level");
+        println!("    {}  {}", padded("----"), "-----");
+        for &(ref name, level) in levels {
+            println!("    {}  {}", padded(&name.replace("_", "-")), level.as_str());
+        }
+        println!("\n");
+    };
+
+    println!("\nLint levels set by the lint configuration `{}`:\n", config.path.display());
+    print_levels(&config.levels);
+
+    for path_override in &config.overrides {
+        let paths = path_override.paths
+                                 .iter()
+                                 .map(|p| format!("`{}`", p))
+                                 .collect::<Vec<_>>()
+                                 .join(", ");
+        println!("Lint levels for files matching {}:\n", paths);
+        print_levels(&path_override.levels);
+    }
 }
 
 fn describe_debug_flags() {
@@ -952,7 +1188,10 @@ fn print_flag_list<T>(cmdline_opt: &str,
 /// contortions done here to get things to work out correctly.
 pub fn handle_options(args: &[String]) -> Option<getopts::Matches> {
     // Throw away the first argument, the name of the binary
-    let args = &args[1..];
+    let (args, expanded) = args::expand(&args[1..]).unwrap_or_else(|e| {
+        early_error(ErrorOutputType::default(), &e)
+    });
+    let args = &args[..];
 
     if args.is_empty() {
         // user did not write `-v` nor `-Z unstable-options`, so do not
@@ -972,6 +1211,10 @@ pub fn handle_options(args: &[String]) -> Option<getopts::Matches> {
         Err(f) => early_error(ErrorOutputType::default(), &f.to_string()),
     };
 
+    if expanded && matches.opt_strs("Z").iter().any(|x| *x == "verbose") {
+        let _ = writeln!(io::stderr(), "note: expanded command line: {}", args::display(args));
+    }
+
     // For all options we just parsed, we check a few aspects:
     //
     // * If the option is stable, we're all good
@@ -1089,7 +1332,9 @@ pub fn monitor<F: FnOnce() + Send + 'static>(f: F) {
         // Thread panicked without emitting a fatal diagnostic
         if !value.is::<errors::FatalError>() {
             let emitter =
-                Box::new(errors::emitter::EmitterWriter::stderr(errors::ColorConfig::Auto, None));
+                Box::new(errors::emitter::EmitterWriter::stderr(errors::ColorConfig::Auto,
+                                                                None,
+                                                                false));
             let handler = errors::Handler::with_emitter(true, false, emitter);
 
             // a .span_bug or .bug call has already printed what
@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use Applicability;
 use CodeSuggestion;
 use Substitution;
 use Level;
@@ -22,6 +23,8 @@ pub struct Diagnostic {
     pub level: Level,
     pub message: Vec<(String, Style)>,
     pub code: Option<String>,
+    /// The name of the lint that produced this diagnostic, if any.
+    pub lint: Option<String>,
     pub span: MultiSpan,
     pub children: Vec<SubDiagnostic>,
     pub suggestions: Vec<CodeSuggestion>,
@@ -86,6 +89,7 @@ impl Diagnostic {
             level: level,
             message: vec![(message.to_owned(), Style::NoStyle)],
             code: code,
+            lint: None,
             span: MultiSpan::new(),
             children: vec![],
             suggestions: vec![],
@@ -205,23 +209,37 @@ impl Diagnostic {
     ///
     /// See `diagnostic::CodeSuggestion` for more information.
     pub fn span_suggestion(&mut self, sp: Span, msg: &str, suggestion: String) -> &mut Self {
-        self.suggestions.push(CodeSuggestion {
-            substitution_parts: vec![Substitution {
-                span: sp,
-                substitutions: vec![suggestion],
-            }],
-            msg: msg.to_owned(),
-        });
-        self
+        self.span_suggestion_with_applicability(sp, msg, suggestion, Applicability::Unspecified)
+    }
+
+    /// Like `span_suggestion`, but also records how safe it is for a tool
+    /// to apply the suggestion without review.
+    pub fn span_suggestion_with_applicability(&mut self,
+                                              sp: Span,
+                                              msg: &str,
+                                              suggestion: String,
+                                              applicability: Applicability)
+                                              -> &mut Self {
+        self.span_suggestions_with_applicability(sp, msg, vec![suggestion], applicability)
     }
 
     pub fn span_suggestions(&mut self, sp: Span, msg: &str, suggestions: Vec<String>) -> &mut Self {
+        self.span_suggestions_with_applicability(sp, msg, suggestions, Applicability::Unspecified)
+    }
+
+    pub fn span_suggestions_with_applicability(&mut self,
+                                               sp: Span,
+                                               msg: &str,
+                                               suggestions: Vec<String>,
+                                               applicability: Applicability)
+                                               -> &mut Self {
         self.suggestions.push(CodeSuggestion {
             substitution_parts: vec![Substitution {
                 span: sp,
                 substitutions: suggestions,
             }],
             msg: msg.to_owned(),
+            applicability: applicability,
         });
         self
     }
@@ -236,6 +254,11 @@ impl Diagnostic {
         self
     }
 
+    pub fn lint(&mut self, name: String) -> &mut Self {
+        self.lint = Some(name);
+        self
+    }
+
     pub fn message(&self) -> String {
         self.message.iter().map(|i| i.0.to_owned()).collect::<String>()
     }
@@ -253,6 +276,7 @@ impl Diagnostic {
     pub fn copy_details_not_message(&mut self, from: &Diagnostic) {
         self.span = from.span.clone();
         self.code = from.code.clone();
+        self.lint = from.lint.clone();
         self.children.extend(from.children.iter().cloned())
     }
 
@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use Applicability;
 use Diagnostic;
 use DiagnosticStyledString;
 
@@ -97,7 +98,10 @@ impl<'a> DiagnosticBuilder<'a> {
             }
         }
 
-        self.handler.emitter.borrow_mut().emit(&self);
+        if self.handler.should_print(&self) {
+            self.handler.record_suggestions(&self.suggestions);
+            self.handler.emitter.borrow_mut().emit(&self);
+        }
         self.cancel();
 
         if self.level == Level::Error {
@@ -156,8 +160,21 @@ impl<'a> DiagnosticBuilder<'a> {
                                      msg: &str,
                                      suggestions: Vec<String>)
                                      -> &mut Self);
+    forward!(pub fn span_suggestion_with_applicability(&mut self,
+                                                       sp: Span,
+                                                       msg: &str,
+                                                       suggestion: String,
+                                                       applicability: Applicability)
+                                                       -> &mut Self);
+    forward!(pub fn span_suggestions_with_applicability(&mut self,
+                                                        sp: Span,
+                                                        msg: &str,
+                                                        suggestions: Vec<String>,
+                                                        applicability: Applicability)
+                                                        -> &mut Self);
     forward!(pub fn set_span<S: Into<MultiSpan>>(&mut self, sp: S) -> &mut Self);
     forward!(pub fn code(&mut self, s: String) -> &mut Self);
+    forward!(pub fn lint(&mut self, name: String) -> &mut Self);
 
     /// Convenience function for internal use, clients should use one of the
     /// struct_* methods on Handler.
@@ -100,6 +100,9 @@ impl ColorConfig {
 pub struct EmitterWriter {
     dst: Destination,
     cm: Option<Rc<CodeMapper>>,
+    /// Only print a `file:line:col: level[code]: message` line for each
+    /// diagnostic, without source snippets or sub-diagnostics.
+    short_message: bool,
 }
 
 struct FileWithAnnotatedLines {
@@ -109,25 +112,34 @@ struct FileWithAnnotatedLines {
 }
 
 impl EmitterWriter {
-    pub fn stderr(color_config: ColorConfig, code_map: Option<Rc<CodeMapper>>) -> EmitterWriter {
+    pub fn stderr(color_config: ColorConfig,
+                  code_map: Option<Rc<CodeMapper>>,
+                  short_message: bool)
+                  -> EmitterWriter {
         if color_config.use_color() {
             let dst = Destination::from_stderr();
             EmitterWriter {
                 dst: dst,
                 cm: code_map,
+                short_message: short_message,
             }
         } else {
             EmitterWriter {
                 dst: Raw(Box::new(io::stderr())),
                 cm: code_map,
+                short_message: short_message,
             }
         }
     }
 
-    pub fn new(dst: Box<Write + Send>, code_map: Option<Rc<CodeMapper>>) -> EmitterWriter {
+    pub fn new(dst: Box<Write + Send>,
+               code_map: Option<Rc<CodeMapper>>,
+               short_message: bool)
+               -> EmitterWriter {
         EmitterWriter {
             dst: Raw(dst),
             cm: code_map,
+            short_message: short_message,
         }
     }
 
@@ -1095,12 +1107,55 @@ impl EmitterWriter {
         }
         Ok(())
     }
+
+    /// Emits the diagnostic as a single gcc-style line:
+    /// `file:line:col: level[code]: message`.
+    fn emit_message_short(&mut self,
+                          msp: &MultiSpan,
+                          msg: &Vec<(String, Style)>,
+                          code: &Option<String>,
+                          level: &Level)
+                          -> io::Result<()> {
+        let mut buffer = StyledBuffer::new();
+
+        if let (Some(ref cm), Some(primary_span)) = (self.cm.as_ref(), msp.primary_span()) {
+            if primary_span != DUMMY_SP {
+                let loc = cm.lookup_char_pos(primary_span.lo);
+                buffer.append(0,
+                              &format!("{}:{}:{}: ", loc.file.name, loc.line, loc.col.0 + 1),
+                              Style::LineAndColumn);
+            }
+        }
+
+        buffer.append(0, &level.to_string(), Style::Level(level.clone()));
+        if let Some(ref code) = *code {
+            buffer.append(0, "[", Style::Level(level.clone()));
+            buffer.append(0, &code, Style::Level(level.clone()));
+            buffer.append(0, "]", Style::Level(level.clone()));
+        }
+        buffer.append(0, ": ", Style::HeaderMsg);
+        for &(ref text, _) in msg.iter() {
+            // keep the whole diagnostic on one line
+            buffer.append(0, &text.replace("\n", " "), Style::HeaderMsg);
+        }
+
+        emit_to_destination(&buffer.render(), level, &mut self.dst)
+    }
+
     fn emit_messages_default(&mut self,
                              level: &Level,
                              message: &Vec<(String, Style)>,
                              code: &Option<String>,
                              span: &MultiSpan,
                              children: &Vec<SubDiagnostic>) {
+        if self.short_message {
+            match self.emit_message_short(span, message, code, level) {
+                Err(e) => panic!("failed to emit error: {}", e),
+                _ => (),
+            }
+            return;
+        }
+
         let max_line_num = self.get_max_line_num(span, children);
         let max_line_num_len = max_line_num.to_string().len();
 
@@ -38,6 +38,9 @@ use self::Level::*;
 use emitter::{Emitter, EmitterWriter};
 
 use std::cell::{RefCell, Cell};
+use std::collections::{BTreeMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::{error, fmt};
 use std::rc::Rc;
 
@@ -87,6 +90,30 @@ pub struct CodeSuggestion {
     /// ```
     pub substitution_parts: Vec<Substitution>,
     pub msg: String,
+    /// How confident the author of the suggestion is that applying it
+    /// verbatim produces correct code.
+    pub applicability: Applicability,
+}
+
+/// Indicates whether a suggestion can be applied by a tool without any
+/// human review of the result.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable)]
+pub enum Applicability {
+    /// The suggestion is definitely what the user intended. This suggestion
+    /// should be automatically applied.
+    MachineApplicable,
+
+    /// The suggestion contains placeholders like `(...)` and can't be
+    /// applied verbatim.
+    HasPlaceholders,
+
+    /// The suggestion may be what the user intended, but it is uncertain.
+    /// The suggestion should compile, but the result may not be the
+    /// intended meaning.
+    MaybeIncorrect,
+
+    /// The applicability of the suggestion is unknown.
+    Unspecified,
 }
 
 #[derive(Clone, Debug, PartialEq, RustcEncodable, RustcDecodable)]
@@ -105,6 +132,15 @@ pub trait CodeMapper {
 }
 
 impl CodeSuggestion {
+    /// Whether this suggestion can be applied without human review: it must
+    /// be marked as `MachineApplicable` and offer exactly one replacement
+    /// for each of its spans.
+    pub fn is_machine_applicable(&self) -> bool {
+        self.applicability == Applicability::MachineApplicable &&
+            !self.substitution_parts.is_empty() &&
+            self.substitution_parts.iter().all(|sub| sub.substitutions.len() == 1)
+    }
+
     /// Returns the number of substitutions
     fn substitutions(&self) -> usize {
         self.substitution_parts[0].substitutions.len()
@@ -262,6 +298,18 @@ pub struct Handler {
     treat_err_as_bug: bool,
     continue_after_error: Cell<bool>,
     delayed_span_bug: RefCell<Option<(MultiSpan, String)>>,
+    /// If `Some`, the machine-applicable suggestions of every emitted
+    /// diagnostic are recorded here for later rewriting of the sources.
+    applicable_suggestions: RefCell<Option<Vec<CodeSuggestion>>>,
+    /// Hashes of the lint diagnostics printed so far, used to print
+    /// identical lint diagnostics only once.
+    emitted_diagnostics: RefCell<HashSet<u64>>,
+    /// If `Some`, at most this many diagnostics are printed for each lint
+    /// (and level); see `report_suppressed_diagnostics`.
+    max_diagnostics_per_lint: Cell<Option<usize>>,
+    /// The number of diagnostics emitted for each lint (and level), and how
+    /// many of the suppressed ones have been reported already.
+    lint_diagnostic_counts: RefCell<BTreeMap<(String, &'static str), (usize, usize)>>,
 }
 
 impl Handler {
@@ -270,7 +318,7 @@ impl Handler {
                             treat_err_as_bug: bool,
                             cm: Option<Rc<CodeMapper>>)
                             -> Handler {
-        let emitter = Box::new(EmitterWriter::stderr(color_config, cm));
+        let emitter = Box::new(EmitterWriter::stderr(color_config, cm, false));
         Handler::with_emitter(can_emit_warnings, treat_err_as_bug, emitter)
     }
 
@@ -285,6 +333,35 @@ impl Handler {
             treat_err_as_bug: treat_err_as_bug,
             continue_after_error: Cell::new(true),
             delayed_span_bug: RefCell::new(None),
+            applicable_suggestions: RefCell::new(None),
+            emitted_diagnostics: RefCell::new(HashSet::new()),
+            max_diagnostics_per_lint: Cell::new(None),
+            lint_diagnostic_counts: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    /// Start recording the machine-applicable suggestions of all diagnostics
+    /// emitted from now on. See `take_applicable_suggestions`.
+    pub fn record_applicable_suggestions(&self) {
+        let mut suggestions = self.applicable_suggestions.borrow_mut();
+        if suggestions.is_none() {
+            *suggestions = Some(vec![]);
+        }
+    }
+
+    /// Returns the suggestions recorded so far and clears the record.
+    pub fn take_applicable_suggestions(&self) -> Vec<CodeSuggestion> {
+        self.applicable_suggestions.borrow_mut()
+            .as_mut()
+            .map(|suggestions| suggestions.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    fn record_suggestions(&self, suggestions: &[CodeSuggestion]) {
+        if let Some(ref mut recorded) = *self.applicable_suggestions.borrow_mut() {
+            recorded.extend(suggestions.iter()
+                                       .filter(|sugg| sugg.is_machine_applicable())
+                                       .cloned());
         }
     }
 
@@ -292,6 +369,69 @@ impl Handler {
         self.continue_after_error.set(continue_after_error);
     }
 
+    pub fn set_max_diagnostics_per_lint(&self, max: Option<usize>) {
+        self.max_diagnostics_per_lint.set(max);
+    }
+
+    /// Decides whether an emitted diagnostic should actually be printed.
+    /// Lint diagnostics identical to one printed before (same level,
+    /// message, code and primary spans) are dropped, as are the ones past the
+    /// limit set with `set_max_diagnostics_per_lint`. Other diagnostics are
+    /// always printed.
+    fn should_print(&self, diagnostic: &Diagnostic) -> bool {
+        let lint = match diagnostic.lint {
+            Some(ref lint) => lint,
+            None => return true,
+        };
+
+        let mut hasher = DefaultHasher::new();
+        diagnostic.level.hash(&mut hasher);
+        diagnostic.message().hash(&mut hasher);
+        diagnostic.code.hash(&mut hasher);
+        for span in diagnostic.span.primary_spans() {
+            // Copies of a warning produced by different expansions of the
+            // same macro only differ in their syntax context.
+            (span.lo, span.hi).hash(&mut hasher);
+        }
+        if !self.emitted_diagnostics.borrow_mut().insert(hasher.finish()) {
+            return false;
+        }
+
+        if let Some(max) = self.max_diagnostics_per_lint.get() {
+            let mut counts = self.lint_diagnostic_counts.borrow_mut();
+            let &mut (ref mut count, _) =
+                counts.entry((lint.to_string(), diagnostic.level.to_str())).or_insert((0, 0));
+            *count += 1;
+            return *count <= max;
+        }
+        true
+    }
+
+    /// Prints a note for each lint that had diagnostics suppressed because
+    /// of `set_max_diagnostics_per_lint` since the last call.
+    pub fn report_suppressed_diagnostics(&self) {
+        let max = match self.max_diagnostics_per_lint.get() {
+            Some(max) => max,
+            None => return,
+        };
+        let mut notes = vec![];
+        for (&(ref lint, level), &mut (count, ref mut reported)) in
+                self.lint_diagnostic_counts.borrow_mut().iter_mut() {
+            let suppressed = count.saturating_sub(max);
+            if suppressed > *reported {
+                let kind = if level == "warning" { "warnings" } else { "errors" };
+                notes.push(format!("{} more `{}` {} of this kind suppressed",
+                                   suppressed - *reported, lint, kind));
+                *reported = suppressed;
+            }
+        }
+        // The counts are borrowed while iterating, and printing a note may
+        // need them.
+        for note in notes {
+            self.note_without_error(&note);
+        }
+    }
+
     pub fn struct_dummy<'a>(&'a self) -> DiagnosticBuilder<'a> {
         DiagnosticBuilder::new(self, Level::Cancelled, "")
     }
@@ -504,6 +644,8 @@ impl Handler {
             _ => s = "aborting due to previous error(s)".to_string(),
         }
 
+        self.report_suppressed_diagnostics();
+
         panic!(self.fatal(&s));
     }
     pub fn emit(&self, msp: &MultiSpan, msg: &str, lvl: Level) {
@@ -531,7 +673,7 @@ impl Handler {
 }
 
 
-#[derive(Copy, PartialEq, Clone, Debug, RustcEncodable, RustcDecodable)]
+#[derive(Copy, PartialEq, Eq, Clone, Debug, Hash, RustcEncodable, RustcDecodable)]
 pub enum Level {
     Bug,
     Fatal,
@@ -23,4 +23,13 @@ impl Registry {
     pub fn find_description(&self, code: &str) -> Option<&'static str> {
         self.descriptions.get(code).cloned()
     }
+
+    /// All the error codes with their descriptions, sorted by code.
+    pub fn all_descriptions(&self) -> Vec<(&'static str, &'static str)> {
+        let mut descriptions: Vec<_> = self.descriptions.iter()
+                                                        .map(|(&c, &d)| (c, d))
+                                                        .collect();
+        descriptions.sort();
+        descriptions
+    }
 }
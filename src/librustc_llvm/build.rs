@@ -88,7 +88,7 @@ fn main() {
 
     let mut optional_components =
         vec!["x86", "arm", "aarch64", "mips", "powerpc", "pnacl",
-             "systemz", "jsbackend", "msp430", "sparc", "nvptx"];
+             "systemz", "jsbackend", "webassembly", "msp430", "sparc", "nvptx"];
 
     let mut version_cmd = Command::new(&llvm_config);
     version_cmd.arg("--version");
@@ -104,6 +104,10 @@ fn main() {
 
     if major > 3 {
         optional_components.push("hexagon");
+        // ThinLTO support in rustllvm.
+        optional_components.push("lto");
+        // Coverage mapping support for `-Z instrument-coverage`.
+        optional_components.push("coverage");
     }
 
     // FIXME: surely we don't need all these components, right? Stuff like mcjit
@@ -164,6 +168,7 @@ fn main() {
     cfg.file("../rustllvm/PassWrapper.cpp")
        .file("../rustllvm/RustWrapper.cpp")
        .file("../rustllvm/ArchiveWrapper.cpp")
+       .file("../rustllvm/CoverageMappingWrapper.cpp")
        .cpp(true)
        .cpp_link_stdlib(None) // we handle this below
        .compile("librustllvm.a");
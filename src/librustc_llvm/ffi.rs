@@ -421,6 +421,29 @@ pub type RustArchiveMemberRef = *mut RustArchiveMember_opaque;
 #[allow(missing_copy_implementations)]
 pub enum OperandBundleDef_opaque {}
 pub type OperandBundleDefRef = *mut OperandBundleDef_opaque;
+#[allow(missing_copy_implementations)]
+pub enum ThinLTOData {}
+#[allow(missing_copy_implementations)]
+pub enum ThinLTOBuffer {}
+
+/// A serialized module passed to `LLVMRustCreateThinLTOData`.
+#[repr(C)]
+pub struct ThinLTOModule {
+    pub identifier: *const c_char,
+    pub data: *const u8,
+    pub len: usize,
+}
+
+/// A code region of a function, passed to `LLVMRustCoverageWriteMappingToBuffer`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CoverageRegion {
+    pub counter_index: u32,
+    pub line_start: u32,
+    pub column_start: u32,
+    pub line_end: u32,
+    pub column_end: u32,
+}
 
 pub type DiagnosticHandler = unsafe extern "C" fn(DiagnosticInfoRef, *mut c_void);
 pub type InlineAsmDiagHandler = unsafe extern "C" fn(SMDiagnosticRef, *const c_void, c_uint);
@@ -1582,7 +1605,9 @@ extern "C" {
                                                OptLevel: CodeGenOptLevel,
                                                MergeFunctions: bool,
                                                SLPVectorize: bool,
-                                               LoopVectorize: bool);
+                                               LoopVectorize: bool,
+                                               PGOGenPath: *const c_char,
+                                               PGOUsePath: *const c_char);
     pub fn LLVMRustAddLibraryInfo(PM: PassManagerRef,
                                   M: ModuleRef,
                                   DisableSimplifyLibCalls: bool);
@@ -1601,6 +1626,7 @@ extern "C" {
     pub fn LLVMRustLinkInExternalBitcode(M: ModuleRef, bc: *const c_char, len: size_t) -> bool;
     pub fn LLVMRustRunRestrictionPass(M: ModuleRef, syms: *const *const c_char, len: size_t);
     pub fn LLVMRustMarkAllFunctionsNounwind(M: ModuleRef);
+    pub fn LLVMRustAddInstrProfilingPass(PM: PassManagerRef, Output: *const c_char);
 
     pub fn LLVMRustOpenArchive(path: *const c_char) -> ArchiveRef;
     pub fn LLVMRustArchiveIteratorNew(AR: ArchiveRef) -> ArchiveIteratorRef;
@@ -1666,4 +1692,41 @@ extern "C" {
     pub fn LLVMRustSetComdat(M: ModuleRef, V: ValueRef, Name: *const c_char);
     pub fn LLVMRustUnsetComdat(V: ValueRef);
     pub fn LLVMRustSetModulePIELevel(M: ModuleRef);
+
+    pub fn LLVMRustThinLTOAvailable() -> bool;
+    pub fn LLVMRustPassManagerBuilderPopulateThinLTOPassManager(PMB: PassManagerBuilderRef,
+                                                                PM: PassManagerRef);
+    pub fn LLVMRustThinLTOBufferCreate(M: ModuleRef) -> *mut ThinLTOBuffer;
+    pub fn LLVMRustThinLTOBufferFree(M: *mut ThinLTOBuffer);
+    pub fn LLVMRustThinLTOBufferPtr(M: *const ThinLTOBuffer) -> *const c_char;
+    pub fn LLVMRustThinLTOBufferLen(M: *const ThinLTOBuffer) -> size_t;
+    pub fn LLVMRustCreateThinLTOData(Modules: *const ThinLTOModule,
+                                     NumModules: c_uint,
+                                     PreservedSymbols: *const *const c_char,
+                                     PreservedSymbolsLen: c_uint)
+                                     -> *mut ThinLTOData;
+    pub fn LLVMRustPrepareThinLTORename(Data: *const ThinLTOData, Module: ModuleRef) -> bool;
+    pub fn LLVMRustPrepareThinLTOResolveWeak(Data: *const ThinLTOData,
+                                             Module: ModuleRef)
+                                             -> bool;
+    pub fn LLVMRustPrepareThinLTOInternalize(Data: *const ThinLTOData,
+                                             Module: ModuleRef)
+                                             -> bool;
+    pub fn LLVMRustPrepareThinLTOImport(Data: *const ThinLTOData, Module: ModuleRef) -> bool;
+    pub fn LLVMRustFreeThinLTOData(Data: *mut ThinLTOData);
+    pub fn LLVMRustParseBitcodeForThinLTO(Context: ContextRef,
+                                          Data: *const u8,
+                                          len: usize,
+                                          Identifier: *const c_char)
+                                          -> ModuleRef;
+
+    pub fn LLVMRustCoverageWriteFilenamesSectionToBuffer(Filenames: *const *const c_char,
+                                                         FilenamesLen: size_t,
+                                                         BufferOut: RustStringRef);
+    pub fn LLVMRustCoverageWriteMappingToBuffer(FileID: c_uint,
+                                                Regions: *const CoverageRegion,
+                                                NumRegions: c_uint,
+                                                BufferOut: RustStringRef);
+    pub fn LLVMRustCoverageComputeHash(Name: *const c_char) -> u64;
+    pub fn LLVMRustCoverageMappingVersion() -> u32;
 }
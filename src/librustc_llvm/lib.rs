@@ -291,10 +291,16 @@ pub fn get_params(llfn: ValueRef) -> Vec<ValueRef> {
 
 pub fn build_string<F>(f: F) -> Option<String>
     where F: FnOnce(RustStringRef)
+{
+    String::from_utf8(build_byte_buffer(f)).ok()
+}
+
+pub fn build_byte_buffer<F>(f: F) -> Vec<u8>
+    where F: FnOnce(RustStringRef)
 {
     let mut buf = RefCell::new(Vec::new());
     f(&mut buf as RustStringRepr as RustStringRef);
-    String::from_utf8(buf.into_inner()).ok()
+    buf.into_inner()
 }
 
 pub unsafe fn twine_to_string(tr: TwineRef) -> String {
@@ -367,6 +373,11 @@ pub fn initialize_available_targets() {
                  LLVMInitializeJSBackendTargetInfo,
                  LLVMInitializeJSBackendTarget,
                  LLVMInitializeJSBackendTargetMC);
+    init_target!(llvm_component = "webassembly",
+                 LLVMInitializeWebAssemblyTargetInfo,
+                 LLVMInitializeWebAssemblyTarget,
+                 LLVMInitializeWebAssemblyTargetMC,
+                 LLVMInitializeWebAssemblyAsmPrinter);
     init_target!(llvm_component = "msp430",
                  LLVMInitializeMSP430TargetInfo,
                  LLVMInitializeMSP430Target,
@@ -299,6 +299,11 @@ impl CrateStore for cstore::CStore {
         self.get_crate_data(cnum).get_exported_symbols(&self.dep_graph)
     }
 
+    fn exported_generics(&self, cnum: CrateNum) -> Vec<(u64, String)>
+    {
+        self.get_crate_data(cnum).get_exported_generics(&self.dep_graph)
+    }
+
     fn is_no_builtins(&self, cnum: CrateNum) -> bool {
         self.get_crate_data(cnum).is_no_builtins(&self.dep_graph)
     }
@@ -438,10 +443,11 @@ impl CrateStore for cstore::CStore {
     fn encode_metadata<'a, 'tcx>(&self,
                                  tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                  link_meta: &LinkMeta,
-                                 reachable: &NodeSet)
+                                 reachable: &NodeSet,
+                                 exported_generics: &[(u64, String)])
                                  -> EncodedMetadata
     {
-        encoder::encode_metadata(tcx, link_meta, reachable)
+        encoder::encode_metadata(tcx, link_meta, reachable, exported_generics)
     }
 
     fn metadata_encoding_version(&self) -> &[u8]
@@ -1040,6 +1040,15 @@ impl<'a, 'tcx> CrateMetadata {
             .collect()
     }
 
+    pub fn get_exported_generics(&self, dep_graph: &DepGraph) -> Vec<(u64, String)> {
+        let dep_node = self.metadata_dep_node(GlobalMetaDataKind::ExportedGenerics);
+        self.root
+            .exported_generics
+            .get(dep_graph, dep_node)
+            .decode(self)
+            .collect()
+    }
+
     pub fn get_macro(&self, id: DefIndex) -> (ast::Name, MacroDef) {
         let entry = self.entry(id);
         match entry.kind {
@@ -53,6 +53,7 @@ pub struct EncodeContext<'a, 'tcx: 'a> {
     pub tcx: TyCtxt<'a, 'tcx, 'tcx>,
     link_meta: &'a LinkMeta,
     exported_symbols: &'a NodeSet,
+    exported_generics: &'a [(u64, String)],
 
     lazy_state: LazyState,
     type_shorthands: FxHashMap<Ty<'tcx>, usize>,
@@ -379,6 +380,14 @@ impl<'a, 'tcx> EncodeContext<'a, 'tcx> {
             self.exported_symbols);
         let exported_symbols_bytes = self.position() - i;
 
+        // Encode the generic instances shared with downstream crates.
+        i = self.position();
+        let exported_generics = self.tracked(
+            DepNode::GlobalMetaData((), GlobalMetaDataKind::ExportedGenerics),
+            IsolatedEncoder::encode_exported_generics,
+            self.exported_generics);
+        let exported_generics_bytes = self.position() - i;
+
         // Encode and index the items.
         i = self.position();
         let items = self.encode_info_for_items();
@@ -417,6 +426,7 @@ impl<'a, 'tcx> EncodeContext<'a, 'tcx> {
             def_path_table: def_path_table,
             impls: impls,
             exported_symbols: exported_symbols,
+            exported_generics: exported_generics,
             index: index,
         });
 
@@ -442,6 +452,7 @@ impl<'a, 'tcx> EncodeContext<'a, 'tcx> {
             println!("         codemap bytes: {}", codemap_bytes);
             println!("            impl bytes: {}", impl_bytes);
             println!("    exp. symbols bytes: {}", exported_symbols_bytes);
+            println!("   exp. generics bytes: {}", exported_generics_bytes);
             println!("  def-path table bytes: {}", def_path_table_bytes);
             println!("            item bytes: {}", item_bytes);
             println!("           index bytes: {}", index_bytes);
@@ -1326,6 +1337,12 @@ impl<'a, 'b: 'a, 'tcx: 'b> IsolatedEncoder<'a, 'b, 'tcx> {
         self.lazy_seq(exported_symbols.iter().map(|&id| tcx.hir.local_def_id(id).index))
     }
 
+    fn encode_exported_generics(&mut self,
+                                exported_generics: &[(u64, String)])
+                                -> LazySeq<(u64, String)> {
+        self.lazy_seq_from_slice(exported_generics)
+    }
+
     fn encode_dylib_dependency_formats(&mut self, _: ()) -> LazySeq<Option<LinkagePreference>> {
         match self.tcx.sess.dependency_formats.borrow().get(&config::CrateTypeDylib) {
             Some(arr) => {
@@ -1585,7 +1602,8 @@ impl<'a, 'tcx, 'v> ItemLikeVisitor<'v> for ImplVisitor<'a, 'tcx> {
 
 pub fn encode_metadata<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                  link_meta: &LinkMeta,
-                                 exported_symbols: &NodeSet)
+                                 exported_symbols: &NodeSet,
+                                 exported_generics: &[(u64, String)])
                                  -> EncodedMetadata
 {
     let mut cursor = Cursor::new(vec![]);
@@ -1604,6 +1622,7 @@ pub fn encode_metadata<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             tcx: tcx,
             link_meta: link_meta,
             exported_symbols: exported_symbols,
+            exported_generics: exported_generics,
             lazy_state: LazyState::NoNode,
             type_shorthands: Default::default(),
             predicate_shorthands: Default::default(),
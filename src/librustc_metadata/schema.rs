@@ -255,6 +255,9 @@ pub struct CrateRoot {
     pub def_path_table: Lazy<hir::map::definitions::DefPathTable>,
     pub impls: Tracked<LazySeq<TraitImpls>>,
     pub exported_symbols: Tracked<LazySeq<DefIndex>>,
+    /// The generic instances shared with downstream crates, as pairs of
+    /// instance hash and symbol name, see `-Z share-generics`.
+    pub exported_generics: Tracked<LazySeq<(u64, String)>>,
     pub index: LazySeq<index::Index>,
 }
 
@@ -0,0 +1,553 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Constant propagation.
+//!
+//! A local which is assigned exactly once, and never borrowed mutably, holds
+//! the same value at every use. When that value is computed from constants
+//! alone, it can be evaluated at compile time:
+//!
+//!     _1 = const 4u8
+//!     _2 = Add(_1, const 1u8)
+//!     switchInt(_2) -> ...
+//!
+//! becomes
+//!
+//!     _1 = const 4u8
+//!     _2 = const 5u8
+//!     switchInt(const 5u8) -> ...
+//!
+//! which `SimplifyBranches` then turns into a `goto`. Only scalars are
+//! propagated, and the arithmetic is done by the constant evaluator.
+//!
+//! The same analysis finds the `Assert`s that are known to fail, such as an
+//! addition that always overflows or an index that is always out of bounds,
+//! and the arithmetic which always overflows when it is built without
+//! overflow checks, and so without an `Assert`.
+//! `ConstPropLint` reports those through the `const_err` lint; it runs on
+//! the validated MIR so that it is done before lints are emitted, and does
+//! not modify the MIR. `ConstProp` does the rewriting on the optimized MIR.
+
+use rustc::lint::builtin::CONST_ERR;
+use rustc::middle::const_val::{ConstVal, ErrKind};
+use rustc::mir::{AssertMessage, BasicBlock, BinOp, BorrowKind, CastKind, Constant, Literal, Local};
+use rustc::mir::{LocalKind, Location, Lvalue, Mir, Operand, Projection, ProjectionElem, Rvalue};
+use rustc::mir::{StatementKind, TerminatorKind, START_BLOCK};
+use rustc::mir::transform::{MirPass, MirSource};
+use rustc::mir::visit::{LvalueContext, MutVisitor};
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc_const_eval::cast_const;
+use rustc_const_eval::interpret::{binary_op, unary_op};
+use rustc_const_math::{ConstInt, ConstMathErr, ConstUsize, Op};
+use rustc_data_structures::bitvec::BitVector;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use syntax_pos::Span;
+use transform::qualify_consts;
+use util::def_use::{DefUseAnalysis, Use};
+
+/// Reports the arithmetic overflows and out of bounds indexing which are
+/// known to happen whenever the code is reached.
+pub struct ConstPropLint;
+
+impl MirPass for ConstPropLint {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        let node_id = match source {
+            MirSource::Fn(node_id) => node_id,
+            // Constants are evaluated, and their errors reported, as a whole.
+            _ => return
+        };
+
+        let known = KnownValues::compute(tcx, mir);
+
+        // Only look at the code which can be reached given the known
+        // values, so that an overflow in `if false { ... }` is not reported.
+        let mut visited = BitVector::new(mir.basic_blocks().len());
+        let mut stack = vec![START_BLOCK];
+        while let Some(bb) = stack.pop() {
+            if !visited.insert(bb.index()) {
+                continue;
+            }
+            for statement in &mir[bb].statements {
+                if let StatementKind::Assign(_, Rvalue::BinaryOp(op, ref lhs, ref rhs)) =
+                        statement.kind {
+                    if let Some(err) = known.binary_op_overflow(op, lhs, rhs) {
+                        tcx.sess.add_lint(CONST_ERR,
+                                          node_id,
+                                          statement.source_info.span,
+                                          err.description().to_string());
+                    }
+                }
+            }
+            let terminator = mir[bb].terminator();
+            match terminator.kind {
+                TerminatorKind::SwitchInt { ref discr, ref values, ref targets, .. } => {
+                    if let Some(target) = known.switch_target(discr, values, targets) {
+                        stack.push(target);
+                        continue;
+                    }
+                }
+                TerminatorKind::Assert { ref cond, expected, ref msg, .. } => {
+                    if let Some((ConstVal::Bool(cond), from_var)) = known.eval_operand(cond) {
+                        if cond != expected {
+                            // An error in an expression made up only of
+                            // constants was already reported by the
+                            // constant checker.
+                            if from_var {
+                                tcx.sess.add_lint(CONST_ERR,
+                                                  node_id,
+                                                  terminator.source_info.span,
+                                                  known.describe_assert(msg));
+                            }
+                            continue;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            stack.extend(terminator.successors().iter().cloned());
+        }
+    }
+}
+
+/// Replaces the locals which have a known value by constants.
+pub struct ConstProp;
+
+impl MirPass for ConstProp {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        match source {
+            MirSource::Fn(function_node_id) => {
+                if qualify_consts::is_const_fn(tcx, tcx.hir.local_def_id(function_node_id)) {
+                    // Trans might not be able to evaluate the optimized IR
+                    // of const functions.
+                    return
+                }
+            }
+            // Constants are evaluated as a whole anyway.
+            _ => return
+        }
+
+        if tcx.sess.opts.debugging_opts.mir_opt_level == 0 {
+            return;
+        }
+
+        let known = KnownValues::compute(tcx, mir);
+
+        // Fold the assignments computed from known values, so that the
+        // operands they read are no longer needed...
+        let mut folded = vec![];
+        for (bb, data) in mir.basic_blocks().iter_enumerated() {
+            for (index, statement) in data.statements.iter().enumerate() {
+                if let StatementKind::Assign(ref lvalue, ref rvalue) = statement.kind {
+                    if let Rvalue::Use(Operand::Constant(_)) = *rvalue {
+                        continue;
+                    }
+                    if let Some((KnownValue::Scalar(value), _)) =
+                            known.eval_rvalue(tcx, mir, rvalue) {
+                        let constant = Constant {
+                            span: statement.source_info.span,
+                            ty: lvalue.ty(mir, tcx).to_ty(tcx),
+                            literal: Literal::Value { value: value },
+                        };
+                        folded.push((Location { block: bb, statement_index: index }, constant));
+                    }
+                }
+            }
+        }
+        for (location, constant) in folded {
+            let statement = &mut mir[location.block].statements[location.statement_index];
+            if let StatementKind::Assign(_, ref mut rvalue) = statement.kind {
+                debug!("ConstProp: folding {:?} into {:?}", rvalue, constant);
+                *rvalue = Rvalue::Use(Operand::Constant(box constant));
+            }
+        }
+
+        // ...and replace every read of them.
+        ReplaceKnownOperands { known: &known }.visit_mir(mir);
+    }
+}
+
+#[derive(Clone)]
+enum KnownValue<'tcx> {
+    Scalar(ConstVal<'tcx>),
+    /// The result of a `CheckedBinaryOp`: the value, unless the operation
+    /// overflowed, and whether it did.
+    Checked(Option<ConstVal<'tcx>>, bool),
+}
+
+#[derive(Clone)]
+struct Known<'tcx> {
+    value: KnownValue<'tcx>,
+    ty: Ty<'tcx>,
+    span: Span,
+    /// Whether the value was computed from a user variable, rather than
+    /// only from the temporaries of a single expression.
+    from_var: bool,
+}
+
+struct KnownValues<'tcx> {
+    values: IndexVec<Local, Option<Known<'tcx>>>,
+}
+
+impl<'tcx> KnownValues<'tcx> {
+    fn compute<'a>(tcx: TyCtxt<'a, 'tcx, 'tcx>, mir: &Mir<'tcx>) -> KnownValues<'tcx> {
+        let mut def_use_analysis = DefUseAnalysis::new(mir);
+        def_use_analysis.analyze(mir);
+
+        // The only assignment to each local which may have a known value.
+        let defs: IndexVec<Local, _> = mir.local_decls.indices().map(|local| {
+            match mir.local_kind(local) {
+                LocalKind::Var | LocalKind::Temp => {}
+                LocalKind::Arg | LocalKind::ReturnPointer => return None,
+            }
+            single_assignment(mir, local, &def_use_analysis.local_info(local).defs_and_uses)
+        }).collect();
+
+        let mut known = KnownValues {
+            values: IndexVec::from_elem_n(None, mir.local_decls.len()),
+        };
+
+        // A value may only become known once the values it is computed
+        // from are, so iterate until nothing changes.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for local in mir.local_decls.indices() {
+                if known.values[local].is_some() {
+                    continue;
+                }
+                let location = match defs[local] {
+                    Some(location) => location,
+                    None => continue
+                };
+                let statement = &mir[location.block].statements[location.statement_index];
+                let rvalue = match statement.kind {
+                    StatementKind::Assign(_, ref rvalue) => rvalue,
+                    _ => bug!("unexpected definition of {:?}: {:?}", local, statement)
+                };
+                if let Some((value, from_var)) = known.eval_rvalue(tcx, mir, rvalue) {
+                    debug!("KnownValues: {:?} is known at {:?}", local, location);
+                    known.values[local] = Some(Known {
+                        value: value,
+                        ty: mir.local_decls[local].ty,
+                        span: statement.source_info.span,
+                        from_var: from_var || mir.local_kind(local) == LocalKind::Var,
+                    });
+                    changed = true;
+                }
+            }
+        }
+        known
+    }
+
+    fn eval_rvalue<'a>(&self,
+                       tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                       mir: &Mir<'tcx>,
+                       rvalue: &Rvalue<'tcx>)
+                       -> Option<(KnownValue<'tcx>, bool)> {
+        match *rvalue {
+            Rvalue::Use(ref operand) => {
+                self.eval_operand(operand).map(|(value, from_var)| {
+                    (KnownValue::Scalar(value), from_var)
+                })
+            }
+            Rvalue::Len(ref lvalue) => {
+                match lvalue.ty(mir, tcx).to_ty(tcx).sty {
+                    ty::TyArray(_, len) => {
+                        let len = ConstUsize::new_truncating(len as u128,
+                                                             tcx.sess.target.uint_type);
+                        let value = ConstVal::Integral(ConstInt::Usize(len));
+                        Some((KnownValue::Scalar(value), is_user_variable(mir, lvalue)))
+                    }
+                    _ => None
+                }
+            }
+            Rvalue::BinaryOp(op, ref lhs, ref rhs) => {
+                let ((lhs, lhs_from_var), (rhs, rhs_from_var)) =
+                    match (self.eval_operand(lhs), self.eval_operand(rhs)) {
+                        (Some(lhs), Some(rhs)) => (lhs, rhs),
+                        _ => return None
+                    };
+                binary_op(op, lhs, rhs).ok().map(|value| {
+                    (KnownValue::Scalar(value), lhs_from_var || rhs_from_var)
+                })
+            }
+            Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs) => {
+                self.eval_checked_binary_op(op, lhs, rhs)
+            }
+            Rvalue::UnaryOp(op, ref operand) => {
+                self.eval_operand(operand).and_then(|(value, from_var)| {
+                    unary_op(op, value).ok().map(|value| (KnownValue::Scalar(value), from_var))
+                })
+            }
+            Rvalue::Cast(CastKind::Misc, ref operand, cast_ty) if is_scalar_ty(cast_ty) => {
+                self.eval_operand(operand).and_then(|(value, from_var)| {
+                    cast_const(tcx, value, cast_ty).ok().map(|value| {
+                        (KnownValue::Scalar(value), from_var)
+                    })
+                })
+            }
+            _ => None
+        }
+    }
+
+    fn eval_checked_binary_op(&self, op: BinOp, lhs: &Operand<'tcx>, rhs: &Operand<'tcx>)
+                              -> Option<(KnownValue<'tcx>, bool)> {
+        let ((lhs, lhs_from_var), (rhs, rhs_from_var)) =
+            match (self.eval_operand(lhs), self.eval_operand(rhs)) {
+                (Some(lhs), Some(rhs)) => (lhs, rhs),
+                _ => return None
+            };
+        match binary_op(op, lhs, rhs) {
+            Ok(value) => {
+                Some((KnownValue::Checked(Some(value), false), lhs_from_var || rhs_from_var))
+            }
+            Err(ErrKind::Math(ConstMathErr::Overflow(_))) |
+            Err(ErrKind::Math(ConstMathErr::ShiftNegative)) => {
+                Some((KnownValue::Checked(None, true),
+                      overflow_from_var(op, lhs_from_var, rhs_from_var)))
+            }
+            Err(_) => None
+        }
+    }
+
+    /// The overflow of an unchecked operation on operands computed from a
+    /// user variable, if it always happens.
+    fn binary_op_overflow(&self, op: BinOp, lhs: &Operand<'tcx>, rhs: &Operand<'tcx>)
+                          -> Option<ConstMathErr> {
+        let ((lhs, lhs_from_var), (rhs, rhs_from_var)) =
+            match (self.eval_operand(lhs), self.eval_operand(rhs)) {
+                (Some(lhs), Some(rhs)) => (lhs, rhs),
+                _ => return None
+            };
+        if !overflow_from_var(op, lhs_from_var, rhs_from_var) {
+            return None;
+        }
+        match binary_op(op, lhs, rhs) {
+            // A division always has an `Assert`, which reports its overflow.
+            Err(ErrKind::Math(err)) => match err {
+                ConstMathErr::Overflow(Op::Add) |
+                ConstMathErr::Overflow(Op::Sub) |
+                ConstMathErr::Overflow(Op::Mul) |
+                ConstMathErr::Overflow(Op::Shl) |
+                ConstMathErr::Overflow(Op::Shr) |
+                ConstMathErr::ShiftNegative => Some(err),
+                _ => None
+            },
+            _ => None
+        }
+    }
+
+    /// The value of `operand`, if it is a known scalar, and whether it was
+    /// computed from a user variable.
+    fn eval_operand(&self, operand: &Operand<'tcx>) -> Option<(ConstVal<'tcx>, bool)> {
+        match *operand {
+            Operand::Constant(box Constant { literal: Literal::Value { ref value }, .. }) => {
+                if is_scalar(value) {
+                    Some((value.clone(), false))
+                } else {
+                    None
+                }
+            }
+            Operand::Consume(ref lvalue) => {
+                self.eval_lvalue(lvalue).map(|(value, _, from_var)| (value, from_var))
+            }
+            _ => None
+        }
+    }
+
+    /// The value of `lvalue`, if it is a known scalar, with its type and
+    /// whether it was computed from a user variable.
+    fn eval_lvalue(&self, lvalue: &Lvalue<'tcx>) -> Option<(ConstVal<'tcx>, Ty<'tcx>, bool)> {
+        match *lvalue {
+            Lvalue::Local(local) => match self.values[local] {
+                Some(Known { value: KnownValue::Scalar(ref value), ty, from_var, .. }) => {
+                    Some((value.clone(), ty, from_var))
+                }
+                _ => None
+            },
+            Lvalue::Projection(ref projection) => {
+                let (local, field, ty) = match (&projection.base, &projection.elem) {
+                    (&Lvalue::Local(local), &ProjectionElem::Field(field, ty)) => {
+                        (local, field, ty)
+                    }
+                    _ => return None
+                };
+                match self.values[local] {
+                    Some(Known {
+                        value: KnownValue::Checked(ref value, overflowed), from_var, ..
+                    }) => match field.index() {
+                        0 => value.clone().map(|value| (value, ty, from_var)),
+                        1 => Some((ConstVal::Bool(overflowed), ty, from_var)),
+                        _ => None
+                    },
+                    _ => None
+                }
+            }
+            Lvalue::Static(..) => None
+        }
+    }
+
+    /// The target a `SwitchInt` on `discr` always branches to, if known.
+    fn switch_target(&self,
+                     discr: &Operand<'tcx>,
+                     values: &[ConstInt],
+                     targets: &[BasicBlock])
+                     -> Option<BasicBlock> {
+        let discr = match self.eval_operand(discr).and_then(|(value, _)| value.to_const_int()) {
+            Some(discr) => discr,
+            None => return None
+        };
+        let (otherwise, targets) = targets.split_last().unwrap();
+        Some(values.iter().zip(targets).find(|&(value, _)| *value == discr)
+                   .map_or(*otherwise, |(_, &target)| target))
+    }
+
+    fn describe_assert(&self, msg: &AssertMessage<'tcx>) -> String {
+        match *msg {
+            AssertMessage::Math(ref err) => err.description().to_string(),
+            AssertMessage::BoundsCheck { ref len, ref index } => {
+                let len = self.eval_operand(len).and_then(|(len, _)| len.to_const_int());
+                let index = self.eval_operand(index).and_then(|(index, _)| index.to_const_int());
+                match (len.and_then(|len| len.to_u64()), index.and_then(|index| index.to_u64())) {
+                    (Some(len), Some(index)) => {
+                        format!("index out of bounds: the len is {} but the index is {}",
+                                len, index)
+                    }
+                    _ => "index out of bounds".to_string()
+                }
+            }
+        }
+    }
+}
+
+/// The location of the assignment to `local`, if it is the only way the
+/// local is ever written to or mutably borrowed.
+fn single_assignment(mir: &Mir, local: Local, defs_and_uses: &[Use]) -> Option<Location> {
+    let mut def = None;
+    for lvalue_use in defs_and_uses {
+        match lvalue_use.context {
+            LvalueContext::Store => {
+                if def.is_some() {
+                    return None;
+                }
+                def = Some(lvalue_use.location);
+            }
+            // A unique borrow may be used to mutate the local through a
+            // closure.
+            LvalueContext::Borrow { kind: BorrowKind::Unique, .. } => return None,
+            ref context if context.is_mutating_use() => return None,
+            _ => {}
+        }
+    }
+
+    // Only a plain assignment of the whole local can be evaluated.
+    let location = match def {
+        Some(location) => location,
+        None => return None
+    };
+    let statement = &mir[location.block].statements[location.statement_index];
+    match statement.kind {
+        StatementKind::Assign(Lvalue::Local(assigned), _) if assigned == local => Some(location),
+        _ => None
+    }
+}
+
+/// Whether an overflow of `op` on the given operands is caused by a user
+/// variable. Otherwise it was reported by the constant checker, or, for a
+/// shift by a constant amount, by the `exceeding_bitshifts` lint: whether a
+/// shift overflows only depends on its amount.
+fn overflow_from_var(op: BinOp, lhs_from_var: bool, rhs_from_var: bool) -> bool {
+    match op {
+        BinOp::Shl | BinOp::Shr => rhs_from_var,
+        _ => lhs_from_var || rhs_from_var
+    }
+}
+
+fn is_user_variable(mir: &Mir, lvalue: &Lvalue) -> bool {
+    match *lvalue {
+        Lvalue::Local(local) => match mir.local_kind(local) {
+            LocalKind::Var | LocalKind::Arg => true,
+            LocalKind::Temp | LocalKind::ReturnPointer => false,
+        },
+        Lvalue::Projection(ref projection) => is_user_variable(mir, &projection.base),
+        Lvalue::Static(..) => true,
+    }
+}
+
+fn is_scalar(value: &ConstVal) -> bool {
+    match *value {
+        ConstVal::Integral(_) | ConstVal::Float(_) | ConstVal::Bool(_) | ConstVal::Char(_) => true,
+        _ => false
+    }
+}
+
+fn is_scalar_ty(ty: Ty) -> bool {
+    match ty.sty {
+        ty::TyInt(_) | ty::TyUint(_) | ty::TyFloat(_) | ty::TyBool | ty::TyChar => true,
+        _ => false
+    }
+}
+
+struct ReplaceKnownOperands<'a, 'tcx: 'a> {
+    known: &'a KnownValues<'tcx>,
+}
+
+// The operations known to overflow, and the checks of their overflow flag,
+// are left alone: `ConstPropLint` has already reported them, and trans
+// would warn about them again if their operands became constants.
+impl<'a, 'tcx> MutVisitor<'tcx> for ReplaceKnownOperands<'a, 'tcx> {
+    fn visit_rvalue(&mut self, rvalue: &mut Rvalue<'tcx>, location: Location) {
+        if let Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs) = *rvalue {
+            if let Some((KnownValue::Checked(_, true), _)) =
+                    self.known.eval_checked_binary_op(op, lhs, rhs) {
+                return;
+            }
+        }
+        self.super_rvalue(rvalue, location);
+    }
+
+    fn visit_operand(&mut self, operand: &mut Operand<'tcx>, location: Location) {
+        let constant = match *operand {
+            Operand::Consume(ref lvalue) => {
+                let known = match *lvalue {
+                    Lvalue::Local(local) | Lvalue::Projection(box Projection {
+                        base: Lvalue::Local(local), ..
+                    }) => self.known.values[local].as_ref(),
+                    _ => None
+                };
+                match known {
+                    Some(&Known { value: KnownValue::Checked(_, true), .. }) | None => None,
+                    Some(known) => self.known.eval_lvalue(lvalue).map(|(value, ty, _)| {
+                        Constant {
+                            span: known.span,
+                            ty: ty,
+                            literal: Literal::Value { value: value },
+                        }
+                    })
+                }
+            }
+            Operand::Constant(_) => None
+        };
+        match constant {
+            Some(constant) => {
+                debug!("ConstProp: replacing {:?} with {:?} at {:?}",
+                       operand, constant, location);
+                *operand = Operand::Constant(box constant);
+            }
+            None => self.super_operand(operand, location)
+        }
+    }
+}
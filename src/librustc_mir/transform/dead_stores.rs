@@ -0,0 +1,90 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A pass that removes the assignments to temporaries which are never read.
+//!
+//! Those are mostly left behind by constant propagation, which replaces the
+//! reads of temporaries with constants. Removing an assignment may in turn
+//! leave the temporaries it read unused, so this runs until nothing
+//! changes. The declarations and storage markers of the temporaries that
+//! end up unused are removed by `SimplifyLocals`.
+//!
+//! User variables are kept, so that they can still be inspected in a
+//! debugger, and so are borrows, which keep their referent in memory for
+//! as long as the temporary holding them is live.
+
+use rustc::mir::{LocalKind, Lvalue, Mir, Rvalue, StatementKind};
+use rustc::mir::transform::{MirPass, MirSource};
+use rustc::mir::visit::LvalueContext;
+use rustc::session::config::OptLevel;
+use rustc::ty::TyCtxt;
+use util::def_use::DefUseAnalysis;
+
+pub struct RemoveDeadStores;
+
+impl MirPass for RemoveDeadStores {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          _source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        let enabled = match tcx.sess.opts.debugging_opts.mir_opt_level {
+            0 => false,
+            1 => tcx.sess.opts.optimize != OptLevel::No,
+            _ => true,
+        };
+        if !enabled {
+            return;
+        }
+
+        loop {
+            let mut def_use_analysis = DefUseAnalysis::new(mir);
+            def_use_analysis.analyze(mir);
+
+            let mut dead_stores = vec![];
+            for local in mir.local_decls.indices() {
+                if mir.local_kind(local) != LocalKind::Temp {
+                    continue;
+                }
+                let defs_and_uses = &def_use_analysis.local_info(local).defs_and_uses;
+                let is_dead = defs_and_uses.iter().all(|lvalue_use| {
+                    match lvalue_use.context {
+                        LvalueContext::StorageLive | LvalueContext::StorageDead => true,
+                        LvalueContext::Store => {
+                            let location = lvalue_use.location;
+                            let block = &mir[location.block];
+                            match block.statements[location.statement_index].kind {
+                                StatementKind::Assign(_, Rvalue::Ref(..)) => false,
+                                StatementKind::Assign(Lvalue::Local(assigned), _) => {
+                                    assigned == local
+                                }
+                                _ => false
+                            }
+                        }
+                        _ => false
+                    }
+                });
+                if is_dead {
+                    dead_stores.extend(defs_and_uses.iter().filter(|lvalue_use| {
+                        lvalue_use.context == LvalueContext::Store
+                    }).map(|lvalue_use| lvalue_use.location));
+                }
+            }
+
+            if dead_stores.is_empty() {
+                break;
+            }
+            for location in dead_stores {
+                debug!("RemoveDeadStores: removing {:?}",
+                       mir[location.block].statements[location.statement_index]);
+                mir[location.block].statements[location.statement_index].make_nop();
+            }
+        }
+    }
+}
@@ -9,6 +9,12 @@
 // except according to those terms.
 
 //! Inlining pass for MIR functions
+//!
+//! The pass runs at `-Z mir-opt-level=2`, and at the default level when
+//! optimizing. Whether a call is inlined depends on an estimate of the size
+//! of the callee, compared to a threshold which is higher for the functions
+//! marked `#[inline]`; both thresholds can be changed with `-Z` options, and
+//! `-Z print-inline-decisions` shows what was decided for each call.
 
 use rustc::hir::def_id::DefId;
 
@@ -18,8 +24,9 @@ use rustc_data_structures::indexed_vec::{Idx, IndexVec};
 use rustc::mir::*;
 use rustc::mir::transform::{MirPass, MirSource};
 use rustc::mir::visit::*;
+use rustc::session::config::OptLevel;
 use rustc::traits;
-use rustc::ty::{self, Ty, TyCtxt};
+use rustc::ty::{self, Ty, TyCtxt, TypeFoldable};
 use rustc::ty::subst::{Subst,Substs};
 
 use std::collections::VecDeque;
@@ -27,6 +34,7 @@ use super::simplify::{remove_dead_blocks, CfgSimplifier};
 
 use syntax::{attr};
 use syntax::abi::Abi;
+use syntax_pos::DUMMY_SP;
 
 const DEFAULT_THRESHOLD: usize = 50;
 const HINT_THRESHOLD: usize = 100;
@@ -44,6 +52,33 @@ struct CallSite<'tcx> {
     substs: &'tcx Substs<'tcx>,
     bb: BasicBlock,
     location: SourceInfo,
+    /// The index in the inlining history of the call this call site was
+    /// inlined from, if any.
+    inlined_from: Option<usize>,
+}
+
+/// What was decided for a call site.
+enum Decision {
+    Inline { cost: usize, threshold: usize, always: bool },
+    TooCostly { cost: usize, threshold: usize },
+    Refuse(&'static str),
+}
+
+impl Decision {
+    fn describe(&self) -> String {
+        match *self {
+            Decision::Inline { cost, always: true, .. } => {
+                format!("inlined (#[inline(always)], cost {})", cost)
+            }
+            Decision::Inline { cost, threshold, always: false } => {
+                format!("inlined (cost {}, threshold {})", cost, threshold)
+            }
+            Decision::TooCostly { cost, threshold } => {
+                format!("not inlined (cost {}, threshold {})", cost, threshold)
+            }
+            Decision::Refuse(reason) => format!("not inlined ({})", reason),
+        }
+    }
 }
 
 impl MirPass for Inline {
@@ -51,7 +86,12 @@ impl MirPass for Inline {
                           tcx: TyCtxt<'a, 'tcx, 'tcx>,
                           source: MirSource,
                           mir: &mut Mir<'tcx>) {
-        if tcx.sess.opts.debugging_opts.mir_opt_level >= 2 {
+        let enabled = match tcx.sess.opts.debugging_opts.mir_opt_level {
+            0 => false,
+            1 => tcx.sess.opts.optimize != OptLevel::No,
+            _ => true,
+        };
+        if enabled {
             Inliner { tcx, source }.run_pass(mir);
         }
     }
@@ -78,24 +118,21 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
 
         let mut callsites = VecDeque::new();
 
+        // The calls which have been inlined, each with the index of the
+        // call it was itself inlined from. The MIR of other crates has
+        // already been optimized, so the query cycle detection does not
+        // catch recursion between their functions; following this chain
+        // does.
+        let mut history: Vec<(DefId, Option<usize>)> = vec![];
+
         // Only do inlining into fn bodies.
         if let MirSource::Fn(_) = self.source {
             for (bb, bb_data) in caller_mir.basic_blocks().iter_enumerated() {
                 // Don't inline calls that are in cleanup blocks.
                 if bb_data.is_cleanup { continue; }
 
-                // Only consider direct calls to functions
-                let terminator = bb_data.terminator();
-                if let TerminatorKind::Call {
-                    func: Operand::Constant(ref f), .. } = terminator.kind {
-                    if let ty::TyFnDef(callee_def_id, substs, _) = f.ty.sty {
-                        callsites.push_back(CallSite {
-                            callee: callee_def_id,
-                            substs: substs,
-                            bb: bb,
-                            location: terminator.source_info
-                        });
-                    }
+                if let Some(callsite) = self.get_valid_callsite(bb, bb_data, None) {
+                    callsites.push_back(callsite);
                 }
             }
         }
@@ -106,43 +143,39 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
         loop {
             local_change = false;
             while let Some(callsite) = callsites.pop_front() {
-                if !self.tcx.is_mir_available(callsite.callee) {
-                    continue;
-                }
-
-                let callee_mir = match ty::queries::optimized_mir::try_get(self.tcx,
-                                                                           callsite.location.span,
-                                                                           callsite.callee) {
-                    Ok(ref callee_mir) if self.should_inline(callsite, callee_mir) => {
-                        callee_mir.subst(self.tcx, callsite.substs)
+                let callee_mir = match self.get_callee_mir(callsite, &history) {
+                    Ok(callee_mir) => callee_mir,
+                    Err(reason) => {
+                        self.report(callsite, Decision::Refuse(reason));
+                        continue;
                     }
+                };
 
-                    _ => continue,
+                let decision = self.should_inline(callsite, callee_mir);
+                let inline = match decision {
+                    Decision::Inline { .. } => true,
+                    _ => false,
                 };
+                self.report(callsite, decision);
+                if !inline {
+                    continue;
+                }
 
+                let callee_mir = callee_mir.subst(self.tcx, callsite.substs);
                 let start = caller_mir.basic_blocks().len();
 
                 if !self.inline_call(callsite, caller_mir, callee_mir) {
                     continue;
                 }
 
+                history.push((callsite.callee, callsite.inlined_from));
+                let inlined_from = Some(history.len() - 1);
+
                 // Add callsites from inlined function
                 for (bb, bb_data) in caller_mir.basic_blocks().iter_enumerated().skip(start) {
-                    // Only consider direct calls to functions
-                    let terminator = bb_data.terminator();
-                    if let TerminatorKind::Call {
-                        func: Operand::Constant(ref f), .. } = terminator.kind {
-                        if let ty::TyFnDef(callee_def_id, substs, _) = f.ty.sty {
-                            // Don't inline the same function multiple times.
-                            if callsite.callee != callee_def_id {
-                                callsites.push_back(CallSite {
-                                    callee: callee_def_id,
-                                    substs: substs,
-                                    bb: bb,
-                                    location: terminator.source_info
-                                });
-                            }
-                        }
+                    if let Some(new_callsite) = self.get_valid_callsite(bb, bb_data,
+                                                                        inlined_from) {
+                        callsites.push_back(new_callsite);
                     }
                 }
 
@@ -163,17 +196,109 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
         }
     }
 
+    /// The call site of the direct call terminating `bb_data`, if any, with
+    /// calls to trait methods resolved to the method of the impl when the
+    /// substitutions make that possible.
+    fn get_valid_callsite(&self,
+                          bb: BasicBlock,
+                          bb_data: &BasicBlockData<'tcx>,
+                          inlined_from: Option<usize>)
+                          -> Option<CallSite<'tcx>> {
+        // Only consider direct calls to functions
+        let terminator = bb_data.terminator();
+        if let TerminatorKind::Call { func: Operand::Constant(ref f), .. } = terminator.kind {
+            if let ty::TyFnDef(callee_def_id, substs, _) = f.ty.sty {
+                return self.resolve(callee_def_id, substs).map(|(callee, substs)| {
+                    CallSite {
+                        callee: callee,
+                        substs: substs,
+                        bb: bb,
+                        location: terminator.source_info,
+                        inlined_from: inlined_from,
+                    }
+                });
+            }
+        }
+        None
+    }
+
+    /// Resolves a call to a trait method to the method of the impl that
+    /// would be called. That is only possible once the substitutions no
+    /// longer refer to type parameters, which is usually the case for the
+    /// calls of a generic function inlined into a non-generic one.
+    fn resolve(&self, def_id: DefId, substs: &'tcx Substs<'tcx>)
+               -> Option<(DefId, &'tcx Substs<'tcx>)> {
+        let tcx = self.tcx;
+        let trait_id = match tcx.trait_of_item(def_id) {
+            Some(trait_id) => trait_id,
+            None => return Some((def_id, substs)),
+        };
+        if substs.needs_subst() || substs.has_projection_types() {
+            return None;
+        }
+
+        let trait_ref = ty::TraitRef::from_method(tcx, trait_id, substs);
+        match tcx.trans_fulfill_obligation(DUMMY_SP, ty::Binder(trait_ref)) {
+            traits::VtableImpl(impl_data) => {
+                let item = tcx.associated_item(def_id);
+                let (def_id, substs) = traits::find_associated_item(tcx, &item, substs,
+                                                                    &impl_data);
+                Some((def_id, tcx.erase_regions(&substs)))
+            }
+            // Closures, function pointers and trait objects are not inlined.
+            _ => None
+        }
+    }
+
+    fn get_callee_mir(&self, callsite: CallSite<'tcx>, history: &[(DefId, Option<usize>)])
+                      -> Result<&'tcx Mir<'tcx>, &'static str> {
+        let caller = self.tcx.hir.local_def_id(self.source.item_id());
+        let mut inlined_from = callsite.inlined_from;
+        if callsite.callee == caller {
+            return Err("recursive call");
+        }
+        while let Some(index) = inlined_from {
+            let (callee, next) = history[index];
+            if callee == callsite.callee {
+                return Err("recursive call");
+            }
+            inlined_from = next;
+        }
+
+        if !self.tcx.is_mir_available(callsite.callee) {
+            return Err("MIR not available");
+        }
+
+        match ty::queries::optimized_mir::try_get(self.tcx,
+                                                   callsite.location.span,
+                                                   callsite.callee) {
+            Ok(callee_mir) => Ok(callee_mir),
+            Err(_) => Err("cycle in the call graph"),
+        }
+    }
+
+    fn report(&self, callsite: CallSite<'tcx>, decision: Decision) {
+        debug!("Inline decision for {:?}: {}", callsite.callee, decision.describe());
+        if self.tcx.sess.opts.debugging_opts.print_inline_decisions {
+            let caller = self.tcx.hir.local_def_id(self.source.item_id());
+            println!("INLINE_DECISION {} -> {}: {}",
+                     self.tcx.item_path_str(caller),
+                     self.tcx.item_path_str(callsite.callee),
+                     decision.describe());
+        }
+    }
+
     fn should_inline(&self,
                      callsite: CallSite<'tcx>,
                      callee_mir: &Mir<'tcx>)
-                     -> bool
+                     -> Decision
     {
         let tcx = self.tcx;
 
         // Don't inline closures that have captures
         // FIXME: Handle closures better
         if callee_mir.upvar_decls.len() > 0 {
-            return false;
+            return Decision::Refuse("closure with captures");
         }
 
 
@@ -185,7 +310,7 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
             // there are cases that prevent inlining that we
             // need to check for first.
             attr::InlineAttr::Always => true,
-            attr::InlineAttr::Never => return false,
+            attr::InlineAttr::Never => return Decision::Refuse("#[inline(never)]"),
             attr::InlineAttr::Hint => true,
             attr::InlineAttr::None => false,
         };
@@ -195,14 +320,15 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
         // reference unexported symbols
         if callsite.callee.is_local() {
             if callsite.substs.types().count() == 0 && !hinted {
-                return false;
+                return Decision::Refuse("not eligible for cross-crate inlining");
             }
         }
 
+        let opts = &tcx.sess.opts.debugging_opts;
         let mut threshold = if hinted {
-            HINT_THRESHOLD
+            opts.inline_mir_hint_threshold.unwrap_or(HINT_THRESHOLD)
         } else {
-            DEFAULT_THRESHOLD
+            opts.inline_mir_threshold.unwrap_or(DEFAULT_THRESHOLD)
         };
 
         // Significantly lower the threshold for inlining cold functions
@@ -313,9 +439,11 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
         debug!("Inline cost for {:?} is {}", callsite.callee, cost);
 
         if let attr::InlineAttr::Always = hint {
-            true
+            Decision::Inline { cost: cost, threshold: threshold, always: true }
+        } else if cost <= threshold {
+            Decision::Inline { cost: cost, threshold: threshold, always: false }
         } else {
-            cost <= threshold
+            Decision::TooCostly { cost: cost, threshold: threshold }
         }
     }
 
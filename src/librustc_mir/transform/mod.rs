@@ -36,7 +36,10 @@ pub mod dump_mir;
 pub mod deaggregator;
 pub mod instcombine;
 pub mod copy_prop;
+pub mod const_prop;
+pub mod dead_stores;
 pub mod inline;
+pub mod validate;
 
 pub(crate) fn provide(providers: &mut Providers) {
     self::qualify_consts::provide(providers);
@@ -309,11 +309,14 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
     fn assign(&mut self, dest: &Lvalue<'tcx>, location: Location) {
         let qualif = self.qualif;
         let span = self.span;
+        let control_flow = self.const_fn_control_flow();
         let store = |slot: &mut Option<Qualif>| {
-            if slot.is_some() {
-                span_bug!(span, "multiple assignments to {:?}", dest);
+            match *slot {
+                // A `const fn` can assign its locals on several paths.
+                Some(previous) if control_flow => *slot = Some(previous | qualif),
+                Some(_) => span_bug!(span, "multiple assignments to {:?}", dest),
+                None => *slot = Some(qualif),
             }
-            *slot = Some(qualif);
         };
 
         // Only handle promotable temps in non-const functions.
@@ -337,6 +340,10 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
                 debug!("store to return pointer {:?}", index);
                 store(&mut self.return_qualif)
             }
+            Lvalue::Local(index) if self.const_fn_control_flow()
+                                 && self.mir.local_kind(index) == LocalKind::Var => {
+                debug!("store to variable {:?}", index);
+            }
 
             Lvalue::Projection(box Projection {
                 base: Lvalue::Local(index),
@@ -354,11 +361,36 @@ impl<'a, 'tcx> Qualifier<'a, 'tcx, 'tcx> {
             _ => {
                 // Catch more errors in the destination.
                 self.visit_lvalue(dest, LvalueContext::Store, location);
-                self.statement_like();
+                if !self.const_fn_control_flow() {
+                    self.statement_like();
+                }
             }
         }
     }
 
+    /// Whether this is a `const fn` checked on its whole CFG, which allows
+    /// local variables, branches and loops, instead of by `qualify_const`.
+    fn const_fn_control_flow(&self) -> bool {
+        self.mode == Mode::ConstFn && self.tcx.sess.features.borrow().const_fn_control_flow
+    }
+
+    /// Whether `rvalue` reads (part of) an argument in place, which is
+    /// how the bindings of the argument patterns are initialized.
+    fn reads_argument(&self, rvalue: &Rvalue<'tcx>) -> bool {
+        let mut lvalue = match *rvalue {
+            Rvalue::Use(Operand::Consume(ref lvalue)) |
+            Rvalue::Ref(_, _, ref lvalue) => lvalue,
+            _ => return false
+        };
+        while let Lvalue::Projection(ref proj) = *lvalue {
+            lvalue = &proj.base;
+        }
+        match *lvalue {
+            Lvalue::Local(local) => self.mir.local_kind(local) == LocalKind::Arg,
+            _ => false
+        }
+    }
+
     /// Qualify a whole const, static initializer or const fn.
     fn qualify_const(&mut self) -> Qualif {
         debug!("qualifying {} {:?}", self.mode, self.def_id);
@@ -478,7 +510,11 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
         match *lvalue {
             Lvalue::Local(local) => match self.mir.local_kind(local) {
                 LocalKind::ReturnPointer => {
-                    self.not_const();
+                    if self.const_fn_control_flow() {
+                        self.add(Qualif::NOT_CONST);
+                    } else {
+                        self.not_const();
+                    }
                 }
                 LocalKind::Arg => {
                     self.add(Qualif::FN_ARGUMENT);
@@ -550,7 +586,12 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
                         ProjectionElem::ConstantIndex {..} |
                         ProjectionElem::Subslice {..} |
                         ProjectionElem::Downcast(..) => {
-                            this.not_const()
+                            if this.const_fn_control_flow() {
+                                let ty = lvalue.ty(this.mir, this.tcx).to_ty(this.tcx);
+                                this.qualif.restrict(ty, this.tcx, this.param_env);
+                            } else {
+                                this.not_const()
+                            }
                         }
                     }
                 });
@@ -869,6 +910,7 @@ impl<'a, 'tcx> Visitor<'tcx> for Qualifier<'a, 'tcx, 'tcx> {
         // Check the allowed const fn argument forms.
         if let (Mode::ConstFn, &Lvalue::Local(index)) = (self.mode, dest) {
             if self.mir.local_kind(index) == LocalKind::Var &&
+               (!self.const_fn_control_flow() || self.reads_argument(rvalue)) &&
                self.const_fn_arg_vars.insert(index.index()) {
 
                 // Direct use of an argument is permitted.
@@ -976,7 +1018,7 @@ impl MirPass for QualifyAndPromoteConstants {
             let (temps, candidates) = {
                 let mut qualifier = Qualifier::new(tcx, param_env,
                                                    def_id, mir, mode);
-                if mode == Mode::ConstFn {
+                if mode == Mode::ConstFn && !qualifier.const_fn_control_flow() {
                     // Enforce a constant-like CFG for `const fn`.
                     qualifier.qualify_const();
                 } else {
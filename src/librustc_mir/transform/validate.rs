@@ -0,0 +1,324 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! With `-Z validate-mir`, checks after each pass on the validated and
+//! optimized MIR that the pass left the MIR well-formed, and reports a bug
+//! naming the pass if it did not. `TypeckMir` type-checks the MIR as it is
+//! built; this catches the passes that break it afterwards.
+//!
+//! The checks are:
+//!
+//! - every block has a terminator, and the blocks, locals and scopes
+//!   referred to exist;
+//! - cleanup blocks only branch to cleanup blocks, and unwinding always
+//!   goes to cleanup blocks;
+//! - a local is not used, nor its storage ended, where its storage is dead
+//!   on every path, for the locals which have storage markers;
+//! - the value assigned to an lvalue has the type of that lvalue.
+
+use rustc::mir::{BasicBlock, Local, Location, Lvalue, Mir, Statement, StatementKind};
+use rustc::mir::{Terminator, TerminatorKind, VisibilityScope};
+use rustc::mir::transform::{MirPassIndex, MirSource, MirSuite, PassHook};
+use rustc::mir::transform::{MIR_OPTIMIZED, MIR_VALIDATED};
+use rustc::mir::visit::{LvalueContext, Visitor};
+use rustc::traits::Reveal;
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc_data_structures::indexed_set::{IdxSet, IdxSetBuf};
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use util as mir_util;
+
+pub struct ValidateMir;
+
+impl PassHook for ValidateMir {
+    fn on_mir_pass<'a, 'tcx: 'a>(&self,
+                                 tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                 suite: MirSuite,
+                                 _pass_num: MirPassIndex,
+                                 pass_name: &str,
+                                 source: MirSource,
+                                 mir: &Mir<'tcx>,
+                                 is_after: bool)
+    {
+        if !is_after || !tcx.sess.opts.debugging_opts.validate_mir {
+            return;
+        }
+        if suite != MIR_VALIDATED && suite != MIR_OPTIMIZED {
+            return;
+        }
+        // The MIR of a program with errors may well be broken.
+        if tcx.sess.err_count() > 0 {
+            return;
+        }
+
+        let errors = validate(tcx, source, mir);
+        if !errors.is_empty() {
+            let mut dump: Vec<u8> = vec![];
+            mir_util::write_mir_fn(tcx, source, mir, &mut dump).unwrap();
+            span_bug!(mir.span,
+                      "invalid MIR after pass `{}`:\n{}\n\n{}",
+                      pass_name,
+                      errors.join("\n"),
+                      String::from_utf8_lossy(&dump));
+        }
+    }
+}
+
+fn validate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, source: MirSource, mir: &Mir<'tcx>)
+                      -> Vec<String> {
+    // The other checks index the MIR with the blocks, locals and scopes it
+    // refers to, so only do them once those are known to exist.
+    let errors = check_references(mir);
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    let storage_dead = StorageDead::compute(mir);
+    let mut validator = Validator {
+        tcx: tcx,
+        param_env: tcx.param_env(tcx.hir.local_def_id(source.item_id())),
+        mir: mir,
+        storage_dead: &storage_dead,
+        errors: vec![],
+    };
+    validator.visit_mir(mir);
+    validator.errors
+}
+
+fn check_references(mir: &Mir) -> Vec<String> {
+    let mut errors = vec![];
+    let num_blocks = mir.basic_blocks().len();
+    let num_locals = mir.local_decls.len();
+    let num_scopes = mir.visibility_scopes.len();
+
+    for (local, decl) in mir.local_decls.iter_enumerated() {
+        if decl.source_info.scope.index() >= num_scopes {
+            errors.push(format!("{:?} is in scope {:?}, which does not exist",
+                                local, decl.source_info.scope));
+        }
+    }
+
+    for (bb, data) in mir.basic_blocks().iter_enumerated() {
+        let terminator = match data.terminator {
+            Some(ref terminator) => terminator,
+            None => {
+                errors.push(format!("{:?} has no terminator", bb));
+                continue;
+            }
+        };
+
+        let mut missing = MissingReferences {
+            num_locals: num_locals,
+            num_scopes: num_scopes,
+            errors: vec![],
+        };
+        missing.visit_basic_block_data(bb, data);
+        errors.extend(missing.errors);
+
+        let unwind = unwind_target(terminator);
+        for &succ in terminator.successors().iter() {
+            if succ.index() >= num_blocks {
+                errors.push(format!("{:?} branches to {:?}, which does not exist", bb, succ));
+                continue;
+            }
+            if Some(succ) == unwind && !data.is_cleanup && !mir[succ].is_cleanup {
+                errors.push(format!("{:?} unwinds to {:?}, which is not a cleanup block",
+                                    bb, succ));
+            }
+            if data.is_cleanup && !mir[succ].is_cleanup {
+                errors.push(format!("cleanup block {:?} branches to {:?}, which is not a \
+                                     cleanup block", bb, succ));
+            }
+        }
+    }
+    errors
+}
+
+struct Validator<'v, 'a: 'v, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    mir: &'v Mir<'tcx>,
+    storage_dead: &'v StorageDead,
+    errors: Vec<String>,
+}
+
+impl<'v, 'a, 'tcx> Validator<'v, 'a, 'tcx> {
+    fn error(&mut self, location: Location, message: String) {
+        self.errors.push(format!("{:?}: {}", location, message));
+    }
+
+    /// Checks that `rvalue_ty` can be assigned to an lvalue of type
+    /// `lvalue_ty`, allowing for subtyping.
+    fn check_assignment(&mut self, location: Location, lvalue_ty: Ty<'tcx>, rvalue_ty: Ty<'tcx>) {
+        let tcx = self.tcx;
+        let lvalue_ty = tcx.normalize_associated_type_in_env(&lvalue_ty, self.param_env);
+        let rvalue_ty = tcx.normalize_associated_type_in_env(&rvalue_ty, self.param_env);
+        if lvalue_ty == rvalue_ty {
+            return;
+        }
+        let param_env = self.param_env;
+        let compatible = tcx.infer_ctxt(param_env, Reveal::All).enter(|infcx| {
+            infcx.can_sub_types(rvalue_ty, lvalue_ty).is_ok()
+        });
+        if !compatible {
+            self.error(location, format!("a value of type `{}` is assigned to an lvalue of \
+                                          type `{}`", rvalue_ty, lvalue_ty));
+        }
+    }
+}
+
+impl<'v, 'a, 'tcx> Visitor<'tcx> for Validator<'v, 'a, 'tcx> {
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &Statement<'tcx>,
+                       location: Location) {
+        if let StatementKind::Assign(ref lvalue, ref rvalue) = statement.kind {
+            let tcx = self.tcx;
+            let lvalue_ty = lvalue.ty(self.mir, tcx).to_ty(tcx);
+            let rvalue_ty = rvalue.ty(self.mir, tcx);
+            self.check_assignment(location, lvalue_ty, rvalue_ty);
+        }
+        self.super_statement(block, statement, location);
+    }
+
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let Lvalue::Local(local) = *lvalue {
+            if self.storage_dead.is_dead_at(self.mir, local, location) {
+                match context {
+                    LvalueContext::StorageLive => {}
+                    LvalueContext::StorageDead => {
+                        self.error(location, format!("the storage of {:?} is ended while \
+                                                      already dead", local));
+                    }
+                    _ => {
+                        self.error(location, format!("{:?} is used while its storage is dead",
+                                                     local));
+                    }
+                }
+            }
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+}
+
+/// The block a terminator branches to when unwinding, if any.
+fn unwind_target(terminator: &Terminator) -> Option<BasicBlock> {
+    match terminator.kind {
+        TerminatorKind::Drop { unwind, .. } |
+        TerminatorKind::DropAndReplace { unwind, .. } => unwind,
+        TerminatorKind::Call { cleanup, .. } |
+        TerminatorKind::Assert { cleanup, .. } => cleanup,
+        _ => None
+    }
+}
+
+/// Finds the locals and scopes which are referred to but do not exist.
+struct MissingReferences {
+    num_locals: usize,
+    num_scopes: usize,
+    errors: Vec<String>,
+}
+
+impl<'tcx> Visitor<'tcx> for MissingReferences {
+    fn visit_lvalue(&mut self,
+                    lvalue: &Lvalue<'tcx>,
+                    context: LvalueContext<'tcx>,
+                    location: Location) {
+        if let Lvalue::Local(local) = *lvalue {
+            if local.index() >= self.num_locals {
+                self.errors.push(format!("{:?}: {:?} does not exist", location, local));
+            }
+        }
+        self.super_lvalue(lvalue, context, location);
+    }
+
+    fn visit_visibility_scope(&mut self, scope: &VisibilityScope) {
+        if scope.index() >= self.num_scopes {
+            self.errors.push(format!("scope {:?} does not exist", scope));
+        }
+    }
+}
+
+/// Where the storage of the locals which have storage markers is dead on
+/// every path. The other locals are live throughout the function.
+struct StorageDead {
+    /// The locals which have storage markers.
+    marked: IdxSetBuf<Local>,
+    /// The locals whose storage may be live on entry to each block.
+    maybe_live_on_entry: IndexVec<BasicBlock, IdxSetBuf<Local>>,
+}
+
+impl StorageDead {
+    fn compute(mir: &Mir) -> StorageDead {
+        let num_locals = mir.local_decls.len();
+        let mut marked = IdxSetBuf::new_empty(num_locals);
+        for data in mir.basic_blocks() {
+            for statement in &data.statements {
+                match statement.kind {
+                    StatementKind::StorageLive(Lvalue::Local(local)) |
+                    StatementKind::StorageDead(Lvalue::Local(local)) => {
+                        marked.add(&local);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut maybe_live_on_entry: IndexVec<BasicBlock, IdxSetBuf<Local>> =
+            mir.basic_blocks().indices().map(|_| IdxSetBuf::new_empty(num_locals)).collect();
+
+        // Iterate the forwards analysis to a fixed point.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (bb, data) in mir.basic_blocks().iter_enumerated() {
+                let mut live = maybe_live_on_entry[bb].clone();
+                for statement in &data.statements {
+                    transfer(statement, &mut live);
+                }
+                for &succ in data.terminator().successors().iter() {
+                    if maybe_live_on_entry[succ].union(&live) {
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        StorageDead {
+            marked: marked,
+            maybe_live_on_entry: maybe_live_on_entry,
+        }
+    }
+
+    fn is_dead_at(&self, mir: &Mir, local: Local, location: Location) -> bool {
+        if !self.marked.contains(&local) {
+            return false;
+        }
+        let mut live = self.maybe_live_on_entry[location.block].clone();
+        for statement in &mir[location.block].statements[..location.statement_index] {
+            transfer(statement, &mut live);
+        }
+        !live.contains(&local)
+    }
+}
+
+fn transfer(statement: &Statement, live: &mut IdxSet<Local>) {
+    match statement.kind {
+        StatementKind::StorageLive(Lvalue::Local(local)) => {
+            live.add(&local);
+        }
+        StatementKind::StorageDead(Lvalue::Local(local)) => {
+            live.remove(&local);
+        }
+        _ => {}
+    }
+}
@@ -26,7 +26,6 @@
 
 use rustc::ty::cast::CastKind;
 use rustc_const_eval::ConstContext;
-use rustc::middle::const_val::ConstEvalErr;
 use rustc::middle::const_val::ErrKind::{IndexOpFeatureGated, UnimplementedConstVal, MiscCatchAll};
 use rustc::middle::const_val::ErrKind::{ErroneousReferencedConstant, MiscBinaryOp, NonConstPath};
 use rustc::middle::const_val::ErrKind::{TypeckError, Math};
@@ -66,7 +65,7 @@ impl<'a, 'gcx> CheckCrateVisitor<'a, 'gcx> {
     fn check_const_eval(&self, expr: &'gcx hir::Expr) {
         let const_cx = ConstContext::with_tables(self.tcx, self.tables);
         if let Err(err) = const_cx.eval(expr) {
-            match err.kind {
+            match err.innermost().kind {
                 UnimplementedConstVal(_) => {}
                 IndexOpFeatureGated => {}
                 ErroneousReferencedConstant(_) => {}
@@ -241,22 +240,23 @@ impl<'a, 'tcx> Visitor<'tcx> for CheckCrateVisitor<'a, 'tcx> {
 
         if self.in_fn && self.promotable {
             let const_cx = ConstContext::with_tables(self.tcx, self.tables);
-            match const_cx.eval(ex) {
-                Ok(_) => {}
-                Err(ConstEvalErr { kind: UnimplementedConstVal(_), .. }) |
-                Err(ConstEvalErr { kind: MiscCatchAll, .. }) |
-                Err(ConstEvalErr { kind: MiscBinaryOp, .. }) |
-                Err(ConstEvalErr { kind: NonConstPath, .. }) |
-                Err(ConstEvalErr { kind: ErroneousReferencedConstant(_), .. }) |
-                Err(ConstEvalErr { kind: Math(ConstMathErr::Overflow(Op::Shr)), .. }) |
-                Err(ConstEvalErr { kind: Math(ConstMathErr::Overflow(Op::Shl)), .. }) |
-                Err(ConstEvalErr { kind: IndexOpFeatureGated, .. }) => {}
-                Err(ConstEvalErr { kind: TypeckError, .. }) => {}
-                Err(msg) => {
-                    self.tcx.sess.add_lint(CONST_ERR,
-                                           ex.id,
-                                           msg.span,
-                                           msg.description().into_oneline().into_owned())
+            if let Err(err) = const_cx.eval(ex) {
+                match err.innermost().kind {
+                    UnimplementedConstVal(_) |
+                    MiscCatchAll |
+                    MiscBinaryOp |
+                    NonConstPath |
+                    ErroneousReferencedConstant(_) |
+                    Math(ConstMathErr::Overflow(Op::Shr)) |
+                    Math(ConstMathErr::Overflow(Op::Shl)) |
+                    IndexOpFeatureGated |
+                    TypeckError => {}
+                    _ => {
+                        self.tcx.sess.add_lint(CONST_ERR,
+                                               ex.id,
+                                               err.span,
+                                               err.description().into_oneline().into_owned())
+                    }
                 }
             }
         }
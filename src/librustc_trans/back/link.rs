@@ -14,7 +14,8 @@ use super::rpath::RPathConfig;
 use super::rpath;
 use super::msvc;
 use metadata::METADATA_FILENAME;
-use rustc::session::config::{self, NoDebugInfo, OutputFilenames, Input, OutputType};
+use rustc::session::config::{self, NoDebugInfo, OutputFilenames, Input, OutputType,
+                             SplitDebuginfo};
 use rustc::session::filesearch;
 use rustc::session::search_paths::PathKind;
 use rustc::session::Session;
@@ -28,7 +29,7 @@ use rustc::dep_graph::DepNode;
 use rustc::hir::def_id::CrateNum;
 use rustc::hir::svh::Svh;
 use rustc_back::tempdir::TempDir;
-use rustc_back::PanicStrategy;
+use rustc_back::{LinkerFlavor, LldFlavor, PanicStrategy};
 use rustc_incremental::IncrementalHashesMap;
 use context::get_reloc_model;
 use llvm;
@@ -147,7 +148,8 @@ pub fn build_link_meta(incremental_hashes_map: &IncrementalHashesMap) -> LinkMet
 pub fn get_linker(sess: &Session) -> (String, Command, Option<PathBuf>) {
     if let Some(ref linker) = sess.opts.cg.linker {
         (linker.clone(), Command::new(linker), None)
-    } else if sess.target.target.options.is_like_msvc {
+    } else if sess.target.target.options.is_like_msvc &&
+              sess.linker_flavor() == LinkerFlavor::Msvc {
         let (cmd, host) = msvc::link_exe_cmd(sess);
         ("link.exe".to_string(), cmd, host)
     } else {
@@ -409,11 +411,24 @@ fn link_binary_output(sess: &Session,
 fn object_filenames(trans: &CrateTranslation,
                     outputs: &OutputFilenames)
                     -> Vec<PathBuf> {
-    trans.modules.iter().map(|module| {
-        outputs.temp_path(OutputType::Object, Some(&module.name))
+    let upstream_lto_modules = trans.upstream_lto_modules.borrow();
+    trans.modules.iter().map(|module| &module.name).chain(upstream_lto_modules.iter()).map(|name| {
+        outputs.temp_path(OutputType::Object, Some(name))
     }).collect()
 }
 
+/// Whether LTO merged the rust objects of the upstream crates into the
+/// objects of the crate, in which case they must not be linked again.
+fn are_upstream_rust_objects_already_included(sess: &Session) -> bool {
+    match sess.lto() {
+        config::Lto::Fat |
+        config::Lto::Thin => true,
+
+        config::Lto::ThinLocal |
+        config::Lto::No => false,
+    }
+}
+
 fn archive_search_paths(sess: &Session) -> Vec<PathBuf> {
     let mut search = Vec::new();
     sess.target_filesearch(PathKind::Native).for_each_lib_search_path(|path, _| {
@@ -664,7 +679,10 @@ fn link_staticlib(sess: &Session, objects: &[PathBuf], out_filename: &Path,
         let skip_object_files = native_libs.iter().any(|lib| {
             lib.kind == NativeLibraryKind::NativeStatic && !relevant_lib(sess, lib)
         });
-        ab.add_rlib(path, &name.as_str(), sess.lto(), skip_object_files).unwrap();
+        ab.add_rlib(path,
+                    &name.as_str(),
+                    are_upstream_rust_objects_already_included(sess),
+                    skip_object_files).unwrap();
 
         all_native_libs.extend(sess.cstore.native_libraries(cnum));
     });
@@ -709,6 +727,13 @@ fn link_natively(sess: &Session,
     let (pname, mut cmd, extra) = get_linker(sess);
     cmd.env("PATH", command_path(sess, extra));
 
+    // LLD only infers its personality from the name it's invoked as when
+    // that is e.g. `ld.lld` or `lld-link`, which `rust-lld` isn't, so always
+    // spell it out. This has to come first on the command line.
+    if let LinkerFlavor::Lld(f) = flavor {
+        cmd.arg("-flavor").arg(f.desc());
+    }
+
     let root = sess.target_filesearch(PathKind::Native).get_lib_path();
     if let Some(args) = sess.target.target.options.pre_link_args.get(&flavor) {
         cmd.args(args);
@@ -838,12 +863,17 @@ fn link_natively(sess: &Session,
             sess.struct_err(&format!("could not exec the linker `{}`: {}", pname, e))
                 .note(&format!("{:?}", &cmd))
                 .emit();
-            if sess.target.target.options.is_like_msvc && e.kind() == io::ErrorKind::NotFound {
+            if sess.target.target.options.is_like_msvc && flavor == LinkerFlavor::Msvc &&
+               e.kind() == io::ErrorKind::NotFound {
                 sess.note_without_error("the msvc targets depend on the msvc linker \
                     but `link.exe` was not found");
                 sess.note_without_error("please ensure that VS 2013 or VS 2015 was installed \
                     with the Visual C++ option");
             }
+            if pname == "rust-lld" && e.kind() == io::ErrorKind::NotFound {
+                sess.note_without_error("`rust-lld` is only shipped by toolchains built with \
+                    `lld = true`; use `-C linker` to point at another copy of LLD");
+            }
             sess.abort_if_errors();
         }
     }
@@ -857,6 +887,33 @@ fn link_natively(sess: &Session,
             Err(e) => sess.fatal(&format!("failed to run dsymutil: {}", e)),
         }
     }
+
+    // With packed split debuginfo, the `.dwo` files the output refers to,
+    // including the ones of the upstream crates, are packaged into a `.dwp`
+    // file next to it, where debuggers look for it.
+    if sess.opts.cg.split_debuginfo == SplitDebuginfo::Packed &&
+       sess.opts.debuginfo != NoDebugInfo {
+        let mut dwp = out_filename.as_os_str().to_owned();
+        dwp.push(".dwp");
+        let mut cmd = Command::new("dwp");
+        cmd.arg("-e").arg(out_filename).arg("-o").arg(&dwp);
+        info!("{:?}", &cmd);
+        match cmd.output() {
+            Ok(prog) => {
+                if !prog.status.success() {
+                    let mut output = prog.stderr.clone();
+                    output.extend_from_slice(&prog.stdout);
+                    sess.struct_err(&format!("packaging split debuginfo with `dwp` failed: {}",
+                                             prog.status))
+                        .note(&format!("{:?}", &cmd))
+                        .note(&String::from_utf8_lossy(&output))
+                        .emit();
+                    sess.abort_if_errors();
+                }
+            }
+            Err(e) => sess.fatal(&format!("failed to run dwp: {}", e)),
+        }
+    }
 }
 
 fn link_args(cmd: &mut Linker,
@@ -890,8 +947,10 @@ fn link_args(cmd: &mut Linker,
 
     // If we're building a dynamic library then some platforms need to make sure
     // that all symbols are exported correctly from the dynamic library.
+    // Emscripten and wasm executables need their exports listed as well.
     if crate_type != config::CrateTypeExecutable ||
-       sess.target.target.options.is_like_emscripten {
+       sess.target.target.options.is_like_emscripten ||
+       sess.linker_flavor() == LinkerFlavor::Lld(LldFlavor::Wasm) {
         cmd.export_symbols(tmpdir, crate_type);
     }
 
@@ -1203,7 +1262,8 @@ fn add_upstream_rust_crates(cmd: &mut Linker,
             lib.kind == NativeLibraryKind::NativeStatic && !relevant_lib(sess, lib)
         });
 
-        if !sess.lto() && crate_type != config::CrateTypeDylib && !skip_native {
+        if !are_upstream_rust_objects_already_included(sess) &&
+           crate_type != config::CrateTypeDylib && !skip_native {
             cmd.link_rlib(&fix_windows_verbatim_for_gcc(cratepath));
             return
         }
@@ -1242,8 +1302,9 @@ fn add_upstream_rust_crates(cmd: &mut Linker,
                 // file, then we don't need the object file as it's part of the
                 // LTO module. Note that `#![no_builtins]` is excluded from LTO,
                 // though, so we let that object file slide.
-                let skip_because_lto = sess.lto() && is_rust_object &&
-                                        !sess.cstore.is_no_builtins(cnum);
+                let skip_because_lto = are_upstream_rust_objects_already_included(sess) &&
+                                       is_rust_object &&
+                                       !sess.cstore.is_no_builtins(cnum);
 
                 if skip_because_cfg_say_so || skip_because_lto {
                     archive.remove_file(&f);
@@ -1277,7 +1338,7 @@ fn add_upstream_rust_crates(cmd: &mut Linker,
     fn add_dynamic_crate(cmd: &mut Linker, sess: &Session, cratepath: &Path) {
         // If we're performing LTO, then it should have been previously required
         // that all upstream rust dependencies were available in an rlib format.
-        assert!(!sess.lto());
+        assert!(!are_upstream_rust_objects_already_included(sess));
 
         // Just need to tell the linker about where the library lives and
         // what its name is
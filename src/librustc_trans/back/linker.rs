@@ -22,9 +22,9 @@ use back::archive;
 use back::symbol_export::{self, ExportedSymbols};
 use rustc::middle::dependency_format::Linkage;
 use rustc::hir::def_id::{LOCAL_CRATE, CrateNum};
-use rustc_back::LinkerFlavor;
+use rustc_back::{LinkerFlavor, LldFlavor};
 use rustc::session::Session;
-use rustc::session::config::{self, CrateType, OptLevel, DebugInfoLevel};
+use rustc::session::config::{self, CrateType, OptLevel, DebugInfoLevel, Strip};
 use serialize::{json, Encoder};
 
 /// For all the linkers we support, and information they might
@@ -47,6 +47,7 @@ impl<'a, 'tcx> LinkerInfo {
                      cmd: Command,
                      sess: &'a Session) -> Box<Linker+'a> {
         match sess.linker_flavor() {
+            LinkerFlavor::Lld(LldFlavor::Link) |
             LinkerFlavor::Msvc => {
                 Box::new(MsvcLinker {
                     cmd: cmd,
@@ -70,6 +71,7 @@ impl<'a, 'tcx> LinkerInfo {
                     is_ld: false,
                 }) as Box<Linker>
             }
+            LinkerFlavor::Lld(LldFlavor::Ld) |
             LinkerFlavor::Ld => {
                 Box::new(GccLinker {
                     cmd: cmd,
@@ -79,6 +81,13 @@ impl<'a, 'tcx> LinkerInfo {
                     is_ld: true,
                 }) as Box<Linker>
             }
+            LinkerFlavor::Lld(LldFlavor::Wasm) => {
+                Box::new(WasmLd {
+                    cmd: cmd,
+                    sess: sess,
+                    info: self,
+                }) as Box<Linker>
+            }
         }
     }
 }
@@ -263,7 +272,23 @@ impl<'a> Linker for GccLinker<'a> {
     }
 
     fn debuginfo(&mut self) {
-        // Don't do anything special here for GNU-style linkers.
+        // The macOS linker only knows the short options, and its `-s` is
+        // obsolete; `-x` drops the local symbols instead.
+        let is_like_osx = self.sess.target.target.options.is_like_osx;
+        match self.sess.opts.cg.strip {
+            Strip::None => {}
+            Strip::Debuginfo => {
+                self.linker_arg(if is_like_osx { "-S" } else { "--strip-debug" });
+            }
+            Strip::Symbols => {
+                if is_like_osx {
+                    self.linker_arg("-S");
+                    self.linker_arg("-x");
+                } else {
+                    self.linker_arg("--strip-all");
+                }
+            }
+        }
     }
 
     fn no_default_libraries(&mut self) {
@@ -472,6 +497,12 @@ impl<'a> Linker for MsvcLinker<'a> {
     }
 
     fn debuginfo(&mut self) {
+        // The debuginfo and symbols end up in the PDB file, never in the
+        // binary itself, so stripping them just means not generating it.
+        if self.sess.opts.cg.strip != Strip::None {
+            return
+        }
+
         // This will cause the Microsoft linker to generate a PDB file
         // from the CodeView line tables in the object files.
         self.cmd.arg("/DEBUG");
@@ -627,6 +658,10 @@ impl<'a> Linker for EmLinker<'a> {
 
     fn debuginfo(&mut self) {
         // Preserve names or generate source maps depending on debug info
+        if self.sess.opts.cg.strip != Strip::None {
+            self.cmd.arg("-g0");
+            return
+        }
         self.cmd.arg(match self.sess.opts.debuginfo {
             DebugInfoLevel::NoDebugInfo => "-g0",
             DebugInfoLevel::LimitedDebugInfo => "-g3",
@@ -683,6 +718,120 @@ impl<'a> Linker for EmLinker<'a> {
     }
 }
 
+/// The LLVM WebAssembly linker, for targets with no toolchain of their own.
+///
+/// There are no shared libraries in WebAssembly yet, so everything is linked
+/// statically into one module.
+pub struct WasmLd<'a> {
+    cmd: Command,
+    sess: &'a Session,
+    info: &'a LinkerInfo,
+}
+
+impl<'a> Linker for WasmLd<'a> {
+    fn link_dylib(&mut self, lib: &str) {
+        self.cmd.arg("-l").arg(lib);
+    }
+
+    fn link_staticlib(&mut self, lib: &str) {
+        self.cmd.arg("-l").arg(lib);
+    }
+
+    fn link_rlib(&mut self, lib: &Path) {
+        self.cmd.arg(lib);
+    }
+
+    fn include_path(&mut self, path: &Path) {
+        self.cmd.arg("-L").arg(path);
+    }
+
+    fn framework_path(&mut self, _path: &Path) {
+        bug!("frameworks are not supported on wasm")
+    }
+
+    fn output_filename(&mut self, path: &Path) {
+        self.cmd.arg("-o").arg(path);
+    }
+
+    fn add_object(&mut self, path: &Path) {
+        self.cmd.arg(path);
+    }
+
+    fn position_independent_executable(&mut self) {
+        // noop
+    }
+
+    fn link_rust_dylib(&mut self, lib: &str, _path: &Path) {
+        self.cmd.arg("-l").arg(lib);
+    }
+
+    fn link_framework(&mut self, _framework: &str) {
+        bug!("frameworks are not supported on wasm")
+    }
+
+    fn link_whole_staticlib(&mut self, lib: &str, _search_path: &[PathBuf]) {
+        self.cmd.arg("--whole-archive").arg("-l").arg(lib).arg("--no-whole-archive");
+    }
+
+    fn link_whole_rlib(&mut self, lib: &Path) {
+        self.cmd.arg("--whole-archive").arg(lib).arg("--no-whole-archive");
+    }
+
+    fn gc_sections(&mut self, _keep_metadata: bool) {
+        self.cmd.arg("--gc-sections");
+    }
+
+    fn optimize(&mut self) {
+        self.cmd.arg(match self.sess.opts.optimize {
+            OptLevel::No => "-O0",
+            OptLevel::Less => "-O1",
+            OptLevel::Default => "-O2",
+            OptLevel::Aggressive => "-O3",
+            // wasm-ld has no size optimizations of its own.
+            OptLevel::Size | OptLevel::SizeMin => "-O2",
+        });
+    }
+
+    fn debuginfo(&mut self) {
+        match self.sess.opts.cg.strip {
+            Strip::None => {}
+            Strip::Debuginfo => { self.cmd.arg("--strip-debug"); }
+            Strip::Symbols => { self.cmd.arg("--strip-all"); }
+        }
+    }
+
+    fn no_default_libraries(&mut self) {
+        // noop
+    }
+
+    fn build_dylib(&mut self, _out_filename: &Path) {
+        bug!("building dynamic library is unsupported on wasm")
+    }
+
+    fn args(&mut self, args: &[String]) {
+        self.cmd.args(args);
+    }
+
+    fn export_symbols(&mut self, _tmpdir: &Path, crate_type: CrateType) {
+        // Without an export the linker would garbage collect everything, so
+        // executables export their `#[no_mangle]` functions too; this is the
+        // only way for a wasm module to be called.
+        for sym in &self.info.exports[&crate_type] {
+            self.cmd.arg(format!("--export={}", sym));
+        }
+    }
+
+    fn subsystem(&mut self, _subsystem: &str) {
+        // noop
+    }
+
+    fn finalize(&mut self) -> Command {
+        let mut cmd = Command::new("");
+        ::std::mem::swap(&mut cmd, &mut self.cmd);
+        cmd
+    }
+}
+
 fn exported_symbols(scx: &SharedCrateContext,
                     exported_symbols: &ExportedSymbols,
                     crate_type: CrateType)
@@ -708,5 +857,11 @@ fn exported_symbols(scx: &SharedCrateContext,
         }
     }
 
+    if crate_type == CrateType::CrateTypeCdylib {
+        if let Some(ref path) = scx.sess().opts.debugging_opts.export_symbols_file {
+            symbols = symbol_export::restrict_to_export_list(scx.sess(), path, symbols);
+        }
+    }
+
     symbols
 }
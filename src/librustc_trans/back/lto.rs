@@ -14,17 +14,20 @@ use back::symbol_export::{self, ExportedSymbols};
 use rustc::session::{self, config};
 use llvm;
 use llvm::archive_ro::ArchiveRO;
-use llvm::{ModuleRef, TargetMachineRef, True, False};
+use llvm::{ModuleRef, ContextRef, TargetMachineRef, True, False};
 use rustc::util::common::time;
 use rustc::util::common::path2cstr;
-use rustc::hir::def_id::LOCAL_CRATE;
+use rustc::hir::def_id::{CrateNum, LOCAL_CRATE};
 use back::write::{ModuleConfig, with_llvm_pmb};
+use errors::Handler;
 
 use libc;
 use flate;
 
 use std::ffi::CString;
 use std::path::Path;
+use std::slice;
+use std::sync::Arc;
 
 pub fn crate_type_allows_lto(crate_type: config::CrateType) -> bool {
     match crate_type {
@@ -38,12 +41,9 @@ pub fn crate_type_allows_lto(crate_type: config::CrateType) -> bool {
     }
 }
 
-pub fn run(sess: &session::Session,
-           llmod: ModuleRef,
-           tm: TargetMachineRef,
-           exported_symbols: &ExportedSymbols,
-           config: &ModuleConfig,
-           temp_no_opt_bc_filename: &Path) {
+/// Checks that LTO over the upstream crates can be done for all the outputs
+/// of this session.
+fn check_upstream_lto_allowed(sess: &session::Session) {
     if sess.opts.cg.prefer_dynamic {
         sess.struct_err("cannot prefer dynamic linking when performing LTO")
             .note("only 'staticlib', 'bin', and 'cdylib' outputs are \
@@ -59,7 +59,15 @@ pub fn run(sess: &session::Session,
                             static library outputs");
         }
     }
+}
 
+/// The symbols which LTO must preserve: those exported from the outputs of
+/// this session, out of the local crate and, if `upstream`, out of the
+/// upstream crates as well.
+fn symbols_below_threshold(sess: &session::Session,
+                           exported_symbols: &ExportedSymbols,
+                           upstream: bool)
+                           -> Vec<CString> {
     let export_threshold =
         symbol_export::crates_export_threshold(&sess.crate_types.borrow());
 
@@ -79,78 +87,110 @@ pub fn run(sess: &session::Session,
         .filter_map(symbol_filter)
         .collect();
 
-    // For each of our upstream dependencies, find the corresponding rlib and
-    // load the bitcode from the archive. Then merge it into the current LLVM
-    // module that we've got.
+    if upstream {
+        link::each_linked_rlib(sess, &mut |cnum, _| {
+            // `#![no_builtins]` crates don't participate in LTO.
+            if sess.cstore.is_no_builtins(cnum) {
+                return;
+            }
+
+            symbol_white_list.extend(
+                exported_symbols.exported_symbols(cnum)
+                                .iter()
+                                .filter_map(symbol_filter));
+        });
+    }
+
+    symbol_white_list
+}
+
+/// Calls `f` with the name and the decoded bitcode of each module of the
+/// upstream crates which participate in LTO.
+fn each_upstream_bitcode(sess: &session::Session,
+                         f: &mut FnMut(CrateNum, &str, flate::Bytes)) {
     link::each_linked_rlib(sess, &mut |cnum, path| {
         // `#![no_builtins]` crates don't participate in LTO.
         if sess.cstore.is_no_builtins(cnum) {
             return;
         }
 
-        symbol_white_list.extend(
-            exported_symbols.exported_symbols(cnum)
-                            .iter()
-                            .filter_map(symbol_filter));
-
         let archive = ArchiveRO::open(&path).expect("wanted an rlib");
         let bytecodes = archive.iter().filter_map(|child| {
             child.ok().and_then(|c| c.name().map(|name| (name, c)))
         }).filter(|&(name, _)| name.ends_with("bytecode.deflate"));
         for (name, data) in bytecodes {
-            let bc_encoded = data.data();
-
-            let bc_decoded = if is_versioned_bytecode_format(bc_encoded) {
-                time(sess.time_passes(), &format!("decode {}", name), || {
-                    // Read the version
-                    let version = extract_bytecode_format_version(bc_encoded);
-
-                    if version == 1 {
-                        // The only version existing so far
-                        let data_size = extract_compressed_bytecode_size_v1(bc_encoded);
-                        let compressed_data = &bc_encoded[
-                            link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET..
-                            (link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET + data_size as usize)];
-
-                        match flate::inflate_bytes(compressed_data) {
-                            Ok(inflated) => inflated,
-                            Err(_) => {
-                                sess.fatal(&format!("failed to decompress bc of `{}`",
-                                                   name))
-                            }
-                        }
-                    } else {
-                        sess.fatal(&format!("Unsupported bytecode format version {}",
-                                           version))
+            let bc_decoded = decode_bytecode(sess, name, data.data());
+            f(cnum, name, bc_decoded);
+        }
+    });
+}
+
+fn decode_bytecode(sess: &session::Session, name: &str, bc_encoded: &[u8]) -> flate::Bytes {
+    if is_versioned_bytecode_format(bc_encoded) {
+        time(sess.time_passes(), &format!("decode {}", name), || {
+            // Read the version
+            let version = extract_bytecode_format_version(bc_encoded);
+
+            if version == 1 {
+                // The only version existing so far
+                let data_size = extract_compressed_bytecode_size_v1(bc_encoded);
+                let compressed_data = &bc_encoded[
+                    link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET..
+                    (link::RLIB_BYTECODE_OBJECT_V1_DATA_OFFSET + data_size as usize)];
+
+                match flate::inflate_bytes(compressed_data) {
+                    Ok(inflated) => inflated,
+                    Err(_) => {
+                        sess.fatal(&format!("failed to decompress bc of `{}`",
+                                           name))
                     }
-                })
+                }
             } else {
-                time(sess.time_passes(), &format!("decode {}", name), || {
-                    // the object must be in the old, pre-versioning format, so
-                    // simply inflate everything and let LLVM decide if it can
-                    // make sense of it
-                    match flate::inflate_bytes(bc_encoded) {
-                        Ok(bc) => bc,
-                        Err(_) => {
-                            sess.fatal(&format!("failed to decompress bc of `{}`",
-                                               name))
-                        }
-                    }
-                })
-            };
-
-            let ptr = bc_decoded.as_ptr();
-            debug!("linking {}", name);
-            time(sess.time_passes(), &format!("ll link {}", name), || unsafe {
-                if !llvm::LLVMRustLinkInExternalBitcode(llmod,
-                                                        ptr as *const libc::c_char,
-                                                        bc_decoded.len() as libc::size_t) {
-                    write::llvm_err(sess.diagnostic(),
-                                    format!("failed to load bc of `{}`",
-                                            name));
+                sess.fatal(&format!("Unsupported bytecode format version {}",
+                                   version))
+            }
+        })
+    } else {
+        time(sess.time_passes(), &format!("decode {}", name), || {
+            // the object must be in the old, pre-versioning format, so
+            // simply inflate everything and let LLVM decide if it can
+            // make sense of it
+            match flate::inflate_bytes(bc_encoded) {
+                Ok(bc) => bc,
+                Err(_) => {
+                    sess.fatal(&format!("failed to decompress bc of `{}`",
+                                       name))
                 }
-            });
-        }
+            }
+        })
+    }
+}
+
+pub fn run(sess: &session::Session,
+           llmod: ModuleRef,
+           tm: TargetMachineRef,
+           exported_symbols: &ExportedSymbols,
+           config: &ModuleConfig,
+           temp_no_opt_bc_filename: &Path) {
+    check_upstream_lto_allowed(sess);
+
+    let symbol_white_list = symbols_below_threshold(sess, exported_symbols, true);
+
+    // For each of our upstream dependencies, find the corresponding rlib and
+    // load the bitcode from the archive. Then merge it into the current LLVM
+    // module that we've got.
+    each_upstream_bitcode(sess, &mut |_, name, bc_decoded| {
+        let ptr = bc_decoded.as_ptr();
+        debug!("linking {}", name);
+        time(sess.time_passes(), &format!("ll link {}", name), || unsafe {
+            if !llvm::LLVMRustLinkInExternalBitcode(llmod,
+                                                    ptr as *const libc::c_char,
+                                                    bc_decoded.len() as libc::size_t) {
+                write::llvm_err(sess.diagnostic(),
+                                format!("failed to load bc of `{}`",
+                                        name));
+            }
+        });
     });
 
     // Internalize everything but the exported symbols of the current module
@@ -179,33 +219,237 @@ pub fn run(sess: &session::Session,
 
     // Now we have one massive module inside of llmod. Time to run the
     // LTO-specific optimization passes that LLVM provides.
-    //
-    // This code is based off the code found in llvm's LTO code generator:
-    //      tools/lto/LTOCodeGenerator.cpp
-    debug!("running the pass manager");
     unsafe {
-        let pm = llvm::LLVMCreatePassManager();
-        llvm::LLVMRustAddAnalysisPasses(tm, pm, llmod);
-        let pass = llvm::LLVMRustFindAndCreatePass("verify\0".as_ptr() as *const _);
-        assert!(!pass.is_null());
-        llvm::LLVMRustAddPass(pm, pass);
+        run_pass_manager(tm, llmod, config, false, sess.time_passes());
+    }
+    debug!("lto done");
+}
 
-        with_llvm_pmb(llmod, config, &mut |b| {
+/// Runs the LTO passes over `llmod`: the full LTO pipeline if `thin` is
+/// false, otherwise the ThinLTO one, which expects the module to have gone
+/// through `ThinModule::prepare`.
+///
+/// This code is based off the code found in llvm's LTO code generator:
+///      tools/lto/LTOCodeGenerator.cpp
+pub unsafe fn run_pass_manager(tm: TargetMachineRef,
+                               llmod: ModuleRef,
+                               config: &ModuleConfig,
+                               thin: bool,
+                               time_passes: bool) {
+    debug!("running the pass manager");
+    let pm = llvm::LLVMCreatePassManager();
+    llvm::LLVMRustAddAnalysisPasses(tm, pm, llmod);
+    let pass = llvm::LLVMRustFindAndCreatePass("verify\0".as_ptr() as *const _);
+    assert!(!pass.is_null());
+    llvm::LLVMRustAddPass(pm, pass);
+
+    with_llvm_pmb(llmod, config, &mut |b| {
+        if thin {
+            llvm::LLVMRustPassManagerBuilderPopulateThinLTOPassManager(b, pm);
+        } else {
             llvm::LLVMPassManagerBuilderPopulateLTOPassManager(b, pm,
                 /* Internalize = */ False,
                 /* RunInliner = */ True);
+        }
+    });
+
+    let pass = llvm::LLVMRustFindAndCreatePass("verify\0".as_ptr() as *const _);
+    assert!(!pass.is_null());
+    llvm::LLVMRustAddPass(pm, pass);
+
+    time(time_passes, if thin { "ThinLTO passes" } else { "LTO passes" }, ||
+         llvm::LLVMRunPassManager(pm, llmod));
+
+    llvm::LLVMDisposePassManager(pm);
+}
+
+/// A module serialized to bitcode together with its ThinLTO summary.
+pub struct ThinBuffer(*mut llvm::ThinLTOBuffer);
+
+unsafe impl Send for ThinBuffer {}
+unsafe impl Sync for ThinBuffer {}
+
+impl ThinBuffer {
+    pub fn new(m: ModuleRef) -> ThinBuffer {
+        unsafe {
+            ThinBuffer(llvm::LLVMRustThinLTOBufferCreate(m))
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        unsafe {
+            let ptr = llvm::LLVMRustThinLTOBufferPtr(self.0) as *const u8;
+            let len = llvm::LLVMRustThinLTOBufferLen(self.0);
+            slice::from_raw_parts(ptr, len)
+        }
+    }
+}
+
+impl Drop for ThinBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            llvm::LLVMRustThinLTOBufferFree(self.0);
+        }
+    }
+}
+
+struct ThinData(*mut llvm::ThinLTOData);
+
+unsafe impl Send for ThinData {}
+unsafe impl Sync for ThinData {}
+
+impl Drop for ThinData {
+    fn drop(&mut self) {
+        unsafe {
+            llvm::LLVMRustFreeThinLTOData(self.0);
+        }
+    }
+}
+
+/// The state shared by all the modules of a ThinLTO session: the combined
+/// index of the modules, and the modules themselves, which the index refers
+/// to and which are imported from.
+struct ThinShared {
+    data: ThinData,
+    modules: Vec<ThinBuffer>,
+    module_names: Vec<CString>,
+}
+
+/// One of the modules of a ThinLTO session, to be optimized and translated
+/// on its own.
+pub struct ThinModule {
+    shared: Arc<ThinShared>,
+    idx: usize,
+}
+
+impl ThinModule {
+    /// The name of the module, which is unique within the session.
+    pub fn name(&self) -> &str {
+        self.shared.module_names[self.idx].to_str().unwrap()
+    }
+
+    /// Parses the module into `llcx` and runs the per-module steps of
+    /// ThinLTO on it: the promotion of the symbols which other modules
+    /// import, the resolution of weak symbols, internalization and finally
+    /// the import of the functions it uses from other modules.
+    pub unsafe fn prepare(&self, handler: &Handler, llcx: ContextRef) -> ModuleRef {
+        let data = self.shared.modules[self.idx].data();
+        let llmod = llvm::LLVMRustParseBitcodeForThinLTO(
+            llcx,
+            data.as_ptr(),
+            data.len(),
+            self.shared.module_names[self.idx].as_ptr(),
+        );
+        if llmod.is_null() {
+            write::llvm_err(handler,
+                            format!("failed to parse bitcode of `{}` for ThinLTO", self.name()));
+        }
+
+        let thin = self.shared.data.0;
+        if !llvm::LLVMRustPrepareThinLTORename(thin, llmod) ||
+           !llvm::LLVMRustPrepareThinLTOResolveWeak(thin, llmod) ||
+           !llvm::LLVMRustPrepareThinLTOInternalize(thin, llmod) ||
+           !llvm::LLVMRustPrepareThinLTOImport(thin, llmod) {
+            write::llvm_err(handler,
+                            format!("failed to prepare `{}` for ThinLTO", self.name()));
+        }
+        llmod
+    }
+}
+
+/// Sets up a ThinLTO session over `modules`, the optimized codegen units of
+/// the crate and, for `-C lto=thin`, the modules of the upstream crates.
+/// This builds the combined index of all modules and decides what each of
+/// them imports; the modules returned are then independent of each other.
+pub fn run_thin(sess: &session::Session,
+                exported_symbols: &ExportedSymbols,
+                modules: Vec<(String, ThinBuffer)>)
+                -> Vec<ThinModule> {
+    if unsafe { !llvm::LLVMRustThinLTOAvailable() } {
+        sess.fatal("ThinLTO is not available with the version of LLVM rustc was built with");
+    }
+
+    let upstream = sess.lto() == config::Lto::Thin;
+    if upstream {
+        check_upstream_lto_allowed(sess);
+    }
+    let symbol_white_list = symbols_below_threshold(sess, exported_symbols, upstream);
+
+    let mut module_names = Vec::with_capacity(modules.len());
+    let mut serialized_modules = Vec::with_capacity(modules.len());
+    for (name, buffer) in modules {
+        module_names.push(CString::new(name).unwrap());
+        serialized_modules.push(buffer);
+    }
+    if upstream {
+        each_upstream_bitcode(sess, &mut |cnum, name, bc_decoded| {
+            // Upstream modules are translated along with ours, so their
+            // names must not clash with ours, nor with those of other crates
+            // of the same name.
+            let name = name.trim_right_matches(".bytecode.deflate");
+            let name = format!("upstream-{}.{}", cnum.as_usize(), name);
+            let buffer = time(sess.time_passes(), &format!("summarize {}", name), || {
+                summarize_bitcode(sess, &bc_decoded, &name)
+            });
+            let name = CString::new(name).unwrap();
+            module_names.push(name);
+            serialized_modules.push(buffer);
         });
+    }
 
-        let pass = llvm::LLVMRustFindAndCreatePass("verify\0".as_ptr() as *const _);
-        assert!(!pass.is_null());
-        llvm::LLVMRustAddPass(pm, pass);
+    let thin_modules = module_names.iter().zip(&serialized_modules).map(|(name, module)| {
+        llvm::ThinLTOModule {
+            identifier: name.as_ptr(),
+            data: module.data().as_ptr(),
+            len: module.data().len(),
+        }
+    }).collect::<Vec<_>>();
+    let arr: Vec<*const libc::c_char> = symbol_white_list.iter()
+                                                         .map(|c| c.as_ptr())
+                                                         .collect();
 
-        time(sess.time_passes(), "LTO passes", ||
-             llvm::LLVMRunPassManager(pm, llmod));
+    let data = time(sess.time_passes(), "ThinLTO index", || unsafe {
+        llvm::LLVMRustCreateThinLTOData(thin_modules.as_ptr(),
+                                        thin_modules.len() as u32,
+                                        arr.as_ptr(),
+                                        arr.len() as u32)
+    });
+    if data.is_null() {
+        write::llvm_err(sess.diagnostic(), "failed to prepare the ThinLTO index".to_string());
+    }
 
-        llvm::LLVMDisposePassManager(pm);
+    let shared = Arc::new(ThinShared {
+        data: ThinData(data),
+        modules: serialized_modules,
+        module_names: module_names,
+    });
+    (0..shared.modules.len()).map(|idx| {
+        ThinModule {
+            shared: shared.clone(),
+            idx: idx,
+        }
+    }).collect()
+}
+
+/// Rlibs store the bitcode of their modules without the ThinLTO summary that
+/// the combined index is built from, so this parses the module and writes it
+/// out again with its summary.
+fn summarize_bitcode(sess: &session::Session, bc: &[u8], name: &str) -> ThinBuffer {
+    let cname = CString::new(name).unwrap();
+    unsafe {
+        let llcx = llvm::LLVMContextCreate();
+        let llmod = llvm::LLVMRustParseBitcodeForThinLTO(llcx, bc.as_ptr(), bc.len(),
+                                                         cname.as_ptr());
+        if llmod.is_null() {
+            llvm::LLVMContextDispose(llcx);
+            write::llvm_err(sess.diagnostic(),
+                            format!("failed to parse bitcode of `{}` for ThinLTO", name));
+        }
+        let buffer = ThinBuffer::new(llmod);
+        llvm::LLVMDisposeModule(llmod);
+        llvm::LLVMContextDispose(llcx);
+        buffer
     }
-    debug!("lto done");
 }
 
 fn is_versioned_bytecode_format(bc: &[u8]) -> bool {
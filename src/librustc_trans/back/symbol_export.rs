@@ -8,14 +8,21 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use back::symbol_names;
 use context::SharedCrateContext;
 use monomorphize::Instance;
-use rustc::util::nodemap::FxHashMap;
+use rustc::util::nodemap::{FxHashMap, FxHashSet, NodeSet};
 use rustc::hir::def_id::{DefId, CrateNum, LOCAL_CRATE};
+use rustc::session::Session;
 use rustc::session::config;
-use rustc::ty::TyCtxt;
+use rustc::ty::{self, TyCtxt};
+use trans_item::{TransItem, InstantiationMode};
 use syntax::attr;
 
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
 /// The SymbolExportLevel of a symbols specifies from which kinds of crates
 /// the symbol will be exported. `C` symbols will be exported from any
 /// kind of crate, including cdylibs which export very few things.
@@ -39,37 +46,14 @@ impl ExportedSymbols {
         }
     }
 
-    pub fn compute<'a, 'tcx>(scx: &SharedCrateContext<'a, 'tcx>) -> ExportedSymbols {
-        let mut local_crate: Vec<_> = scx
-            .exported_symbols()
-            .iter()
-            .map(|&node_id| {
-                scx.tcx().hir.local_def_id(node_id)
-            })
-            .map(|def_id| {
-                let name = scx.tcx().symbol_name(Instance::mono(scx.tcx(), def_id));
-                let export_level = export_level(scx, def_id);
-                debug!("EXPORTED SYMBOL (local): {} ({:?})", name, export_level);
-                (str::to_owned(&name), export_level)
-            })
-            .collect();
-
-        if scx.sess().entry_fn.borrow().is_some() {
-            local_crate.push(("main".to_string(), SymbolExportLevel::C));
-        }
-
-        if let Some(id) = scx.sess().derive_registrar_fn.get() {
-            let def_id = scx.tcx().hir.local_def_id(id);
-            let idx = def_id.index;
-            let disambiguator = scx.sess().local_crate_disambiguator();
-            let registrar = scx.sess().generate_derive_registrar_symbol(disambiguator, idx);
-            local_crate.push((registrar, SymbolExportLevel::C));
-        }
-
-        if scx.sess().crate_types.borrow().contains(&config::CrateTypeDylib) {
-            local_crate.push((metadata_symbol_name(scx.tcx()),
-                              SymbolExportLevel::Rust));
-        }
+    pub fn compute<'a, 'tcx>(scx: &SharedCrateContext<'a, 'tcx>,
+                             shared_generics: &[(u64, String)])
+                             -> ExportedSymbols {
+        let mut local_crate = local_exported_symbols(scx.tcx(), scx.exported_symbols());
+        local_crate.extend(shared_generics.iter().map(|&(_, ref name)| {
+            debug!("EXPORTED SYMBOL (shared generic): {}", name);
+            (name.clone(), SymbolExportLevel::Rust)
+        }));
 
         let mut exports = FxHashMap();
         exports.insert(LOCAL_CRATE, local_crate);
@@ -119,29 +103,25 @@ impl ExportedSymbols {
                             SymbolExportLevel::Rust
                         }
                     } else {
-                        export_level(scx, def_id)
+                        export_level(scx.tcx(), def_id)
                     };
                     debug!("EXPORTED SYMBOL (re-export): {} ({:?})", name, export_level);
                     (str::to_owned(&name), export_level)
                 })
-                .collect();
+                .collect::<Vec<_>>();
+
+            // The generic instances the crate shares are only ever used by
+            // Rust code.
+            let crate_exports = crate_exports.into_iter().chain(
+                scx.sess().cstore.exported_generics(cnum).into_iter().map(|(_, name)| {
+                    (name, SymbolExportLevel::Rust)
+                })).collect();
 
             exports.insert(cnum, crate_exports);
         }
 
-        return ExportedSymbols {
+        ExportedSymbols {
             exports: exports
-        };
-
-        fn export_level(scx: &SharedCrateContext,
-                        sym_def_id: DefId)
-                        -> SymbolExportLevel {
-            let attrs = scx.tcx().get_attrs(sym_def_id);
-            if attr::contains_extern_indicator(scx.sess().diagnostic(), &attrs) {
-                SymbolExportLevel::C
-            } else {
-                SymbolExportLevel::Rust
-            }
         }
     }
 
@@ -168,6 +148,108 @@ impl ExportedSymbols {
     }
 }
 
+/// Computes the symbols exported from the local crate, given the set of
+/// items that `find_exported_symbols` found to be exported.
+pub fn local_exported_symbols<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                        exported_symbols: &NodeSet)
+                                        -> Vec<(String, SymbolExportLevel)> {
+    let mut local_crate: Vec<_> = exported_symbols
+        .iter()
+        .map(|&node_id| {
+            tcx.hir.local_def_id(node_id)
+        })
+        .map(|def_id| {
+            let name = tcx.symbol_name(Instance::mono(tcx, def_id));
+            let export_level = export_level(tcx, def_id);
+            debug!("EXPORTED SYMBOL (local): {} ({:?})", name, export_level);
+            (str::to_owned(&name), export_level)
+        })
+        .collect();
+
+    if tcx.sess.entry_fn.borrow().is_some() {
+        local_crate.push(("main".to_string(), SymbolExportLevel::C));
+    }
+
+    if let Some(id) = tcx.sess.derive_registrar_fn.get() {
+        let def_id = tcx.hir.local_def_id(id);
+        let idx = def_id.index;
+        let disambiguator = tcx.sess.local_crate_disambiguator();
+        let registrar = tcx.sess.generate_derive_registrar_symbol(disambiguator, idx);
+        local_crate.push((registrar, SymbolExportLevel::C));
+    }
+
+    if tcx.sess.crate_types.borrow().contains(&config::CrateTypeDylib) {
+        local_crate.push((metadata_symbol_name(tcx),
+                          SymbolExportLevel::Rust));
+    }
+
+    local_crate
+}
+
+/// Whether `trans_item` is a generic instance the local crate shares with
+/// downstream crates, which link against it instead of translating their own
+/// copy. See `-Z share-generics`.
+pub fn is_shared_generic<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                   trans_item: &TransItem<'tcx>)
+                                   -> bool {
+    if !tcx.sess.share_generics() {
+        return false;
+    }
+
+    // Only Rust libraries have downstream crates.
+    if !tcx.sess.crate_types.borrow().iter().any(|&crate_type| {
+        crate_type == config::CrateTypeRlib || crate_type == config::CrateTypeDylib
+    }) {
+        return false;
+    }
+
+    match *trans_item {
+        TransItem::Fn(ref instance) => {
+            let is_item = match instance.def {
+                ty::InstanceDef::Item(..) => true,
+                _ => false,
+            };
+            is_item &&
+            trans_item.is_generic_fn() &&
+            trans_item.instantiation_mode(tcx) == InstantiationMode::GloballyShared
+        }
+        TransItem::Static(..) |
+        TransItem::GlobalAsm(..) => false,
+    }
+}
+
+/// The generic instances among `translation_items` that the local crate
+/// shares with downstream crates, as pairs of instance hash and symbol name.
+pub fn shared_generics<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                 translation_items: &FxHashSet<TransItem<'tcx>>)
+                                 -> Vec<(u64, String)> {
+    let mut shared_generics: Vec<_> = translation_items
+        .iter()
+        .filter(|trans_item| is_shared_generic(tcx, trans_item))
+        .map(|trans_item| {
+            let instance = match *trans_item {
+                TransItem::Fn(instance) => instance,
+                _ => bug!("shared generic {:?} is not a function", trans_item),
+            };
+            (symbol_names::instance_hash(tcx, instance),
+             str::to_owned(&trans_item.symbol_name(tcx)))
+        })
+        .collect();
+
+    // Keep the metadata deterministic.
+    shared_generics.sort();
+    shared_generics
+}
+
+fn export_level(tcx: TyCtxt, sym_def_id: DefId) -> SymbolExportLevel {
+    let attrs = tcx.get_attrs(sym_def_id);
+    if attr::contains_extern_indicator(tcx.sess.diagnostic(), &attrs) {
+        SymbolExportLevel::C
+    } else {
+        SymbolExportLevel::Rust
+    }
+}
+
 pub fn metadata_symbol_name(tcx: TyCtxt) -> String {
     format!("rust_metadata_{}_{}",
             tcx.crate_name(LOCAL_CRATE),
@@ -207,3 +289,35 @@ pub fn is_below_threshold(level: SymbolExportLevel,
         level == SymbolExportLevel::C
     }
 }
+
+/// Restricts `symbols`, the symbols a cdylib would export, to the ones listed
+/// in the file passed to `-Z export-symbols-file`. The file has one symbol per
+/// line; empty lines and lines starting with `#` are ignored.
+pub fn restrict_to_export_list(sess: &Session,
+                               path: &Path,
+                               symbols: Vec<String>)
+                               -> Vec<String> {
+    let mut contents = String::new();
+    if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut contents)) {
+        sess.fatal(&format!("failed to read export symbols file `{}`: {}",
+                            path.display(), e));
+    }
+
+    let listed: FxHashSet<&str> = contents.lines()
+                                          .map(|line| line.trim())
+                                          .filter(|line| !line.is_empty() &&
+                                                         !line.starts_with("#"))
+                                          .collect();
+
+    // A symbol which isn't exported is most likely a typo or a function that
+    // lost its `#[no_mangle]`, and some linkers reject version scripts naming
+    // undefined symbols anyway.
+    for &name in &listed {
+        if !symbols.iter().any(|symbol| symbol == name) {
+            sess.err(&format!("symbol `{}` listed in `{}` is not exported by the crate",
+                              name, path.display()));
+        }
+    }
+
+    symbols.into_iter().filter(|symbol| listed.contains(&symbol[..])).collect()
+}
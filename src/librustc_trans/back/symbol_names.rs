@@ -85,6 +85,14 @@
 //!   with each other. This facility is mainly intended to be used by build
 //!   tools like Cargo.
 //!
+//! Instances of generic functions are translated in every crate that needs
+//! them, so their hash also includes the name and disambiguator of the
+//! instantiating crate. With `-Z share-generics`, a crate that needs an
+//! instance which an upstream crate already translated and exported links
+//! against the upstream copy instead, and so names the instance after the
+//! upstream crate. The upstream crates record the instances they share in
+//! their metadata, keyed by the hash of the instance without any crate in it.
+//!
 //! A note on symbol name stability
 //! -------------------------------
 //! Previous versions of the compiler resorted to feeding NodeIds into the
@@ -100,7 +108,7 @@
 use monomorphize::Instance;
 
 use rustc::middle::weak_lang_items;
-use rustc::hir::def_id::DefId;
+use rustc::hir::def_id::{CrateNum, DefId, LOCAL_CRATE};
 use rustc::hir::map as hir_map;
 use rustc::ty::{self, Ty, TyCtxt, TypeFoldable};
 use rustc::ty::fold::TypeVisitor;
@@ -109,16 +117,19 @@ use rustc::ty::maps::Providers;
 use rustc::ty::subst::Substs;
 use rustc::hir::map::definitions::DefPathData;
 use rustc::util::common::record_time;
+use rustc::util::nodemap::FxHashMap;
 
 use syntax::attr;
 use syntax_pos::symbol::Symbol;
 
 use std::fmt::Write;
+use std::rc::Rc;
 
 pub fn provide(providers: &mut Providers) {
     *providers = Providers {
         def_symbol_name,
         symbol_name,
+        upstream_monomorphizations,
         ..*providers
     };
 }
@@ -136,7 +147,11 @@ fn get_symbol_hash<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
                              // values for generic type parameters,
                              // if any.
-                             substs: Option<&'tcx Substs<'tcx>>)
+                             substs: Option<&'tcx Substs<'tcx>>,
+
+                             // the crate translating the item, for
+                             // instances of generic functions.
+                             instantiating_crate: Option<CrateNum>)
                              -> u64 {
     debug!("get_symbol_hash(def_id={:?}, parameters={:?})", def_id, substs);
 
@@ -159,15 +174,15 @@ fn get_symbol_hash<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             assert!(!substs.has_erasable_regions());
             assert!(!substs.needs_subst());
             substs.visit_with(&mut hasher);
+        }
 
-            // If this is an instance of a generic function, we also hash in
-            // the ID of the instantiating crate. This avoids symbol conflicts
-            // in case the same instances is emitted in two crates of the same
-            // project.
-            if substs.types().next().is_some() {
-                hasher.hash(tcx.crate_name.as_str());
-                hasher.hash(tcx.sess.local_crate_disambiguator().as_str());
-            }
+        // If this is an instance of a generic function, we also hash in
+        // the ID of the instantiating crate. This avoids symbol conflicts
+        // in case the same instances is emitted in two crates of the same
+        // project.
+        if let Some(cnum) = instantiating_crate {
+            hasher.hash(tcx.original_crate_name(cnum).as_str());
+            hasher.hash(tcx.crate_disambiguator(cnum).as_str());
         }
     });
 
@@ -248,6 +263,23 @@ fn compute_symbol_name<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, instance: Instance
         return tcx.item_name(def_id).as_str().to_string();
     }
 
+    let instance_ty = item_type(tcx, def_id);
+
+    let instantiating_crate = if substs.types().next().is_some() {
+        Some(upstream_instantiating_crate(tcx, instance).unwrap_or(LOCAL_CRATE))
+    } else {
+        None
+    };
+
+    let hash = get_symbol_hash(tcx, Some(def_id), instance_ty, Some(substs),
+                               instantiating_crate);
+
+    SymbolPathBuffer::from_interned(tcx.def_symbol_name(def_id)).finish(hash)
+}
+
+/// The type of the item `def_id`, or of its closest parent having one, with
+/// regions erased. This is included in the symbol hash as a safeguard.
+fn item_type<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> Ty<'tcx> {
     // We want to compute the "type" of this item. Unfortunately, some
     // kinds of items (e.g., closures) don't have an entry in the
     // item-type array. So walk back up the find the closest parent
@@ -276,11 +308,61 @@ fn compute_symbol_name<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, instance: Instance
 
     // Erase regions because they may not be deterministic when hashed
     // and should not matter anyhow.
-    let instance_ty = tcx.erase_regions(&instance_ty);
+    tcx.erase_regions(&instance_ty)
+}
 
-    let hash = get_symbol_hash(tcx, Some(def_id), instance_ty, Some(substs));
+/// The hash of `instance` without any instantiating crate in it, which
+/// identifies the instance across crates.
+pub fn instance_hash<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, instance: Instance<'tcx>) -> u64 {
+    let def_id = instance.def_id();
+    get_symbol_hash(tcx, Some(def_id), item_type(tcx, def_id), Some(instance.substs), None)
+}
 
-    SymbolPathBuffer::from_interned(tcx.def_symbol_name(def_id)).finish(hash)
+/// The upstream crate that translated and exported the generic `instance`,
+/// if the local crate is to link against its copy instead of translating
+/// its own, see `-Z share-generics`.
+pub fn upstream_instantiating_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                              instance: Instance<'tcx>)
+                                              -> Option<CrateNum> {
+    match instance.def {
+        ty::InstanceDef::Item(def_id) if !def_id.is_local() => {}
+        _ => return None,
+    }
+    if !tcx.sess.share_generics() {
+        return None;
+    }
+    let upstream = tcx.upstream_monomorphizations(LOCAL_CRATE);
+    if upstream.is_empty() {
+        return None;
+    }
+    upstream.get(&instance_hash(tcx, instance)).cloned()
+}
+
+fn upstream_monomorphizations<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                        cnum: CrateNum)
+                                        -> Rc<FxHashMap<u64, CrateNum>> {
+    assert_eq!(cnum, LOCAL_CRATE);
+    let cstore = &tcx.sess.cstore;
+    let mut instances = FxHashMap();
+    if !tcx.sess.share_generics() {
+        return Rc::new(instances);
+    }
+
+    let mut crates = cstore.crates();
+    crates.sort();
+    for cnum in crates {
+        // Plugins and custom derive crates are not linked in.
+        if cstore.plugin_registrar_fn(cnum).is_some() ||
+           cstore.derive_registrar_fn(cnum).is_some() {
+            continue;
+        }
+        // Several upstream crates may have translated the same instance,
+        // any of them will do.
+        for (instance_hash, _) in cstore.exported_generics(cnum) {
+            instances.entry(instance_hash).or_insert(cnum);
+        }
+    }
+    Rc::new(instances)
 }
 
 // Follow C++ namespace-mangling style, see
@@ -13,7 +13,7 @@ use back::link::{get_linker, remove};
 use back::symbol_export::ExportedSymbols;
 use rustc_incremental::{save_trans_partition, in_incr_comp_dir};
 use rustc::session::config::{self, OutputFilenames, OutputType, OutputTypes, Passes, SomePasses,
-                             AllPasses, Sanitizer};
+                             AllPasses, Sanitizer, SplitDebuginfo, NoDebugInfo};
 use rustc::session::Session;
 use llvm;
 use llvm::{ModuleRef, TargetMachineRef, PassManagerRef, DiagnosticInfoRef, ContextRef};
@@ -30,6 +30,8 @@ use std::cmp;
 use std::ffi::CString;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::ptr;
 use std::str;
 use std::sync::{Arc, Mutex};
 use std::sync::mpsc::channel;
@@ -247,6 +249,13 @@ pub struct ModuleConfig {
     tm: TargetMachineRef,
     /// Names of additional optimization passes to run.
     passes: Vec<String>,
+    /// Path of the profiling data the instrumented code writes out, if the
+    /// code is to be instrumented for profile-guided optimization.
+    pgo_gen: Option<PathBuf>,
+    /// Path of the profile to optimize the code with.
+    pgo_use: Option<PathBuf>,
+    /// Whether to lower the coverage counters of `-Z instrument-coverage`.
+    instrument_coverage: bool,
     /// Some(level) to optimize at a certain level, or None to run
     /// absolutely no optimizations (used for the metadata module).
     opt_level: Option<llvm::CodeGenOptLevel>,
@@ -261,6 +270,11 @@ pub struct ModuleConfig {
     emit_ir: bool,
     emit_asm: bool,
     emit_obj: bool,
+    // Stop once the module is optimized and serialize it for ThinLTO instead
+    // of producing the outputs above, which is done after ThinLTO.
+    emit_thin_lto_buffer: bool,
+    // Move the debuginfo of the object file into a `.dwo` file.
+    split_dwarf: bool,
     // Miscellaneous flags.  These are mostly copied from command-line
     // options.
     no_verify: bool,
@@ -284,6 +298,9 @@ impl ModuleConfig {
         ModuleConfig {
             tm: tm,
             passes: passes,
+            pgo_gen: None,
+            pgo_use: None,
+            instrument_coverage: false,
             opt_level: None,
             opt_size: None,
 
@@ -293,6 +310,8 @@ impl ModuleConfig {
             emit_ir: false,
             emit_asm: false,
             emit_obj: false,
+            emit_thin_lto_buffer: false,
+            split_dwarf: false,
             obj_is_bitcode: false,
 
             no_verify: false,
@@ -314,6 +333,14 @@ impl ModuleConfig {
         self.inline_threshold = sess.opts.cg.inline_threshold;
         self.obj_is_bitcode = sess.target.target.options.obj_is_bitcode;
 
+        // `%m` is replaced by a signature of the binary, so that an executable
+        // and the shared libraries it loads don't write to the same file.
+        self.pgo_gen = sess.opts.cg.profile_generate.as_ref().map(|dir| {
+            dir.join("default_%m.profraw")
+        });
+        self.pgo_use = sess.opts.cg.profile_use.clone();
+        self.instrument_coverage = sess.opts.debugging_opts.instrument_coverage;
+
         // Copy what clang does by turning on loop vectorization at O2 and
         // slp vectorization at O3. Otherwise configure other optimization aspects
         // of this pass manager builder.
@@ -437,7 +464,8 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
                                mtrans: ModuleTranslation,
                                mllvm: ModuleLlvm,
                                config: ModuleConfig,
-                               output_names: OutputFilenames) {
+                               output_names: OutputFilenames)
+                               -> Option<(String, lto::ThinBuffer)> {
     let llmod = mllvm.llmod;
     let llcx = mllvm.llcx;
     let tm = config.tm;
@@ -513,6 +541,13 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
             }
         }
 
+        // Turn the `llvm.instrprof.increment` calls emitted for coverage
+        // into counters the profiler runtime writes out.
+        if config.instrument_coverage {
+            let output = CString::new("default_%m.profraw").unwrap();
+            llvm::LLVMRustAddInstrProfilingPass(mpm, output.as_ptr());
+        }
+
         cgcx.handler.abort_if_errors();
 
         // Finally, run the actual optimization passes
@@ -526,7 +561,7 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
         llvm::LLVMDisposePassManager(mpm);
 
         match cgcx.lto_ctxt {
-            Some((sess, exported_symbols)) if sess.lto() =>  {
+            Some((sess, exported_symbols)) if sess.lto() == config::Lto::Fat =>  {
                 time(sess.time_passes(), "all lto passes", || {
                     let temp_no_opt_bc_filename =
                         output_names.temp_path_ext("no-opt.lto.bc", module_name);
@@ -547,6 +582,62 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
         }
     }
 
+    if config.emit_thin_lto_buffer {
+        // The outputs are produced from the module once ThinLTO has run,
+        // see `optimize_thin_module`.
+        let buffer = lto::ThinBuffer::new(llmod);
+        llvm::LLVMRustDisposeTargetMachine(tm);
+        return Some((mtrans.name.clone(), buffer));
+    }
+
+    codegen(cgcx, llmod, &config, &output_names, module_name);
+    None
+}
+
+/// Runs ThinLTO on `module` in a context of its own, then produces the
+/// outputs requested by `config` from it.
+unsafe fn optimize_thin_module(cgcx: &CodegenContext,
+                               module: lto::ThinModule,
+                               config: ModuleConfig,
+                               output_names: OutputFilenames) {
+    let llcx = llvm::LLVMContextCreate();
+
+    let fv = HandlerFreeVars {
+        llcx: llcx,
+        cgcx: cgcx,
+    };
+    let fv = &fv as *const HandlerFreeVars as *mut c_void;
+
+    llvm::LLVMRustSetInlineAsmDiagnosticHandler(llcx, inline_asm_handler, fv);
+    llvm::LLVMContextSetDiagnosticHandler(llcx, diagnostic_handler, fv);
+
+    let module_name = Some(module.name());
+    let llmod = time(config.time_passes,
+                     &format!("ThinLTO import [{}]", cgcx.worker),
+                     || module.prepare(cgcx.handler, llcx));
+    lto::run_pass_manager(config.tm, llmod, &config, true, config.time_passes);
+
+    if config.emit_lto_bc {
+        let out = output_names.temp_path_ext("thin-lto.bc", module_name);
+        let out = path2cstr(&out);
+        llvm::LLVMWriteBitcodeToFile(llmod, out.as_ptr());
+    }
+
+    codegen(cgcx, llmod, &config, &output_names, module_name);
+
+    llvm::LLVMDisposeModule(llmod);
+    llvm::LLVMContextDispose(llcx);
+}
+
+/// Produces the outputs requested by `config` from the optimized `llmod`, and
+/// disposes of the target machine of `config`.
+unsafe fn codegen(cgcx: &CodegenContext,
+                  llmod: ModuleRef,
+                  config: &ModuleConfig,
+                  output_names: &OutputFilenames,
+                  module_name: Option<&str>) {
+    let tm = config.tm;
+
     // A codegen-specific pass manager is used to generate object
     // files for an LLVM module.
     //
@@ -622,6 +713,13 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
         }
     });
 
+    if write_obj && config.split_dwarf {
+        let dwo_out = output_names.temp_path_ext("dwo", module_name);
+        time(config.time_passes, &format!("split dwarf [{}]", cgcx.worker), || {
+            extract_dwo(cgcx.handler, &obj_out, &dwo_out);
+        });
+    }
+
     if copy_bc_to_obj {
         debug!("copying bitcode {:?} to obj {:?}", bc_out, obj_out);
         if let Err(e) = link_or_copy(&bc_out, &obj_out) {
@@ -640,6 +738,39 @@ unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
 }
 
 
+/// Moves the `.dwo` sections LLVM emits with `-split-dwarf` out of `obj` into
+/// `dwo`, the way `clang -gsplit-dwarf` does. The object file keeps the
+/// skeleton compile units, which hold the line tables and the name of the
+/// `.dwo` file, so symbolizers like libbacktrace still work without it.
+fn extract_dwo(handler: &Handler, obj: &Path, dwo: &Path) {
+    let mut extract = Command::new("objcopy");
+    extract.arg("--extract-dwo").arg(obj).arg(dwo);
+    let mut strip = Command::new("objcopy");
+    strip.arg("--strip-dwo").arg(obj);
+
+    for cmd in &mut [extract, strip] {
+        debug!("{:?}", cmd);
+        match cmd.output() {
+            Ok(prog) => {
+                if !prog.status.success() {
+                    let mut note = prog.stderr.clone();
+                    note.extend_from_slice(&prog.stdout);
+                    handler.struct_err(&format!("splitting debuginfo with `objcopy` failed: {}",
+                                                prog.status))
+                           .note(&format!("{:?}", cmd))
+                           .note(&String::from_utf8_lossy(&note))
+                           .emit();
+                    return;
+                }
+            }
+            Err(e) => {
+                handler.err(&format!("could not exec `objcopy` to split debuginfo: {}", e));
+                return;
+            }
+        }
+    }
+}
+
 pub fn cleanup_llvm(trans: &CrateTranslation) {
     for module in trans.modules.iter() {
         unsafe {
@@ -664,7 +795,7 @@ pub fn run_passes(sess: &Session,
     // case, but it would be confusing to have the validity of
     // `-Z lto -C codegen-units=2` depend on details of the crate being
     // compiled, so we complain regardless.
-    if sess.lto() && sess.opts.cg.codegen_units > 1 {
+    if sess.lto() == config::Lto::Fat && sess.opts.cg.codegen_units > 1 {
         // This case is impossible to handle because LTO expects to be able
         // to combine the entire crate and all its dependencies into a
         // single compilation unit, but each codegen unit is in a separate
@@ -672,6 +803,27 @@ pub fn run_passes(sess: &Session,
         sess.fatal("can't perform LTO when using multiple codegen units");
     }
 
+    // ThinLTO needs all the modules of the crate to be optimized by this
+    // session, which isn't the case of the modules reused by incremental
+    // compilation.
+    let thin_lto = sess.lto() == config::Lto::Thin || sess.lto() == config::Lto::ThinLocal;
+    if thin_lto && sess.opts.debugging_opts.incremental.is_some() {
+        sess.fatal("can't perform ThinLTO when compiling incrementally");
+    }
+
+    // The `.dwo` files aren't saved along with the objects reused by
+    // incremental compilation, and only ELF objects can be split this way.
+    let split_dwarf = sess.opts.cg.split_debuginfo != SplitDebuginfo::Off &&
+                      sess.opts.debuginfo != NoDebugInfo;
+    if sess.opts.cg.split_debuginfo != SplitDebuginfo::Off {
+        if sess.target.target.target_os != "linux" {
+            sess.fatal("`-C split-debuginfo` is only supported on Linux targets");
+        }
+        if sess.opts.debugging_opts.incremental.is_some() {
+            sess.fatal("can't split debuginfo when compiling incrementally");
+        }
+    }
+
     // Sanity check
     assert!(trans.modules.len() == sess.opts.cg.codegen_units ||
             sess.opts.debugging_opts.incremental.is_some() ||
@@ -703,6 +855,7 @@ pub fn run_passes(sess: &Session,
 
     modules_config.opt_level = Some(get_llvm_opt_level(sess.opts.optimize));
     modules_config.opt_size = Some(get_llvm_opt_size(sess.opts.optimize));
+    modules_config.split_dwarf = split_dwarf;
 
     // Save all versions of the bytecode if we're saving our temporaries.
     if sess.opts.cg.save_temps {
@@ -759,16 +912,21 @@ pub fn run_passes(sess: &Session,
 
     {
         let work = build_work_item(sess,
-                                   trans.metadata_module.clone(),
+                                   WorkModule::Translated(trans.metadata_module.clone()),
                                    metadata_config.clone(),
                                    crate_output.clone());
         work_items.push(work);
     }
 
+    // With ThinLTO, the modules are first only optimized, and translated
+    // once ThinLTO has run over all of them.
+    let mut optimize_config = modules_config.clone();
+    optimize_config.emit_thin_lto_buffer = thin_lto;
+
     for mtrans in trans.modules.iter() {
         let work = build_work_item(sess,
-                                   mtrans.clone(),
-                                   modules_config.clone(),
+                                   WorkModule::Translated(mtrans.clone()),
+                                   optimize_config.clone(),
                                    crate_output.clone());
         work_items.push(work);
     }
@@ -785,10 +943,44 @@ pub fn run_passes(sess: &Session,
     //       to crash because of too many open file handles. See #39280 for
     //       some discussion on how to improve this in the future.
     let num_workers = cmp::min(work_items.len() - 1, 32);
-    if num_workers <= 1 {
-        run_work_singlethreaded(sess, &trans.exported_symbols, work_items);
-    } else {
-        run_work_multithreaded(sess, work_items, num_workers);
+    let thin_buffers = execute_work_items(sess, &trans.exported_symbols, work_items, num_workers);
+
+    if thin_lto {
+        let local_modules = thin_buffers.len();
+        let thin_modules = time(sess.time_passes(), "ThinLTO", || {
+            lto::run_thin(sess, &trans.exported_symbols, thin_buffers)
+        });
+
+        // The upstream modules only stand in for the objects of the upstream
+        // crates, so the other outputs are only produced for them when
+        // saving temporaries.
+        let mut upstream_config = modules_config.clone();
+        upstream_config.split_dwarf = false;
+        if !sess.opts.cg.save_temps {
+            upstream_config.emit_bc = false;
+            upstream_config.emit_ir = false;
+            upstream_config.emit_asm = false;
+        }
+
+        let mut work_items = Vec::with_capacity(thin_modules.len());
+        for (i, module) in thin_modules.into_iter().enumerate() {
+            let config = if i < local_modules {
+                modules_config.clone()
+            } else if upstream_config.emit_obj {
+                trans.upstream_lto_modules.borrow_mut().push(module.name().to_string());
+                upstream_config.clone()
+            } else {
+                continue
+            };
+            let work = build_work_item(sess,
+                                       WorkModule::Thin(module),
+                                       config,
+                                       crate_output.clone());
+            work_items.push(work);
+        }
+
+        let num_workers = cmp::min(work_items.len(), 32);
+        execute_work_items(sess, &trans.exported_symbols, work_items, num_workers);
     }
 
     // If in incr. comp. mode, preserve the `.o` files for potential re-use
@@ -939,6 +1131,13 @@ pub fn run_passes(sess: &Session,
             }
         }
 
+        if !keep_numbered_objects {
+            for module_name in trans.upstream_lto_modules.borrow().iter() {
+                let path = crate_output.temp_path(OutputType::Object, Some(module_name));
+                remove(sess, &path);
+            }
+        }
+
         if metadata_config.emit_bc && !user_wants_bitcode {
             let path = crate_output.temp_path(OutputType::Bitcode,
                                               Some(&trans.metadata_module.name));
@@ -971,13 +1170,21 @@ fn dump_incremental_data(trans: &CrateTranslation) {
 }
 
 struct WorkItem {
-    mtrans: ModuleTranslation,
+    module: WorkModule,
     config: ModuleConfig,
     output_names: OutputFilenames
 }
 
+enum WorkModule {
+    /// A module of the crate, either translated or reused from the
+    /// incremental compilation cache.
+    Translated(ModuleTranslation),
+    /// A module to run ThinLTO on.
+    Thin(lto::ThinModule),
+}
+
 fn build_work_item(sess: &Session,
-                   mtrans: ModuleTranslation,
+                   module: WorkModule,
                    config: ModuleConfig,
                    output_names: OutputFilenames)
                    -> WorkItem
@@ -985,35 +1192,47 @@ fn build_work_item(sess: &Session,
     let mut config = config;
     config.tm = create_target_machine(sess);
     WorkItem {
-        mtrans: mtrans,
+        module: module,
         config: config,
         output_names: output_names
     }
 }
 
 fn execute_work_item(cgcx: &CodegenContext,
-                     work_item: WorkItem) {
+                     work_item: WorkItem)
+                     -> Option<(String, lto::ThinBuffer)> {
+    let mtrans = match work_item.module {
+        WorkModule::Translated(mtrans) => mtrans,
+        WorkModule::Thin(module) => {
+            debug!("running ThinLTO on {:?}", module.name());
+            unsafe {
+                optimize_thin_module(cgcx, module, work_item.config, work_item.output_names);
+            }
+            return None
+        }
+    };
+
     unsafe {
-        match work_item.mtrans.source {
+        match mtrans.source {
             ModuleSource::Translated(mllvm) => {
-                debug!("llvm-optimizing {:?}", work_item.mtrans.name);
+                debug!("llvm-optimizing {:?}", mtrans.name);
                 optimize_and_codegen(cgcx,
-                                     work_item.mtrans,
+                                     mtrans,
                                      mllvm,
                                      work_item.config,
-                                     work_item.output_names);
+                                     work_item.output_names)
             }
             ModuleSource::Preexisting(wp) => {
                 let incr_comp_session_dir = cgcx.incr_comp_session_dir
                                                 .as_ref()
                                                 .unwrap();
-                let name = &work_item.mtrans.name;
+                let name = &mtrans.name;
                 for (kind, saved_file) in wp.saved_files {
                     let obj_out = work_item.output_names.temp_path(kind, Some(name));
                     let source_file = in_incr_comp_dir(&incr_comp_session_dir,
                                                        &saved_file);
                     debug!("copying pre-existing module `{}` from {:?} to {}",
-                           work_item.mtrans.name,
+                           mtrans.name,
                            source_file,
                            obj_out.display());
                     match link_or_copy(&source_file, &obj_out) {
@@ -1026,26 +1245,41 @@ fn execute_work_item(cgcx: &CodegenContext,
                         }
                     }
                 }
+                None
             }
         }
     }
 }
 
+/// Processes `work_items` on `num_workers` threads, or on the current thread
+/// if there is at most one worker. Returns the modules serialized for ThinLTO.
+fn execute_work_items(sess: &Session,
+                      exported_symbols: &ExportedSymbols,
+                      work_items: Vec<WorkItem>,
+                      num_workers: usize)
+                      -> Vec<(String, lto::ThinBuffer)> {
+    if num_workers <= 1 {
+        run_work_singlethreaded(sess, exported_symbols, work_items)
+    } else {
+        run_work_multithreaded(sess, work_items, num_workers)
+    }
+}
+
 fn run_work_singlethreaded(sess: &Session,
                            exported_symbols: &ExportedSymbols,
-                           work_items: Vec<WorkItem>) {
+                           work_items: Vec<WorkItem>)
+                           -> Vec<(String, lto::ThinBuffer)> {
     let cgcx = CodegenContext::new_with_session(sess, exported_symbols);
 
     // Since we're running single-threaded, we can pass the session to
     // the proc, allowing `optimize_and_codegen` to perform LTO.
-    for work in work_items.into_iter().rev() {
-        execute_work_item(&cgcx, work);
-    }
+    work_items.into_iter().rev().filter_map(|work| execute_work_item(&cgcx, work)).collect()
 }
 
 fn run_work_multithreaded(sess: &Session,
                           work_items: Vec<WorkItem>,
-                          num_workers: usize) {
+                          num_workers: usize)
+                          -> Vec<(String, lto::ThinBuffer)> {
     assert!(num_workers > 0);
 
     // Run some workers to process the work items.
@@ -1082,12 +1316,13 @@ fn run_work_multithreaded(sess: &Session,
                 incr_comp_session_dir: incr_comp_session_dir
             };
 
+            let mut thin_buffers = vec![];
             loop {
                 // Avoid holding the lock for the entire duration of the match.
                 let maybe_work = work_items_arc.lock().unwrap().pop();
                 match maybe_work {
                     Some(work) => {
-                        execute_work_item(&cgcx, work);
+                        thin_buffers.extend(execute_work_item(&cgcx, work));
 
                         // Make sure to fail the worker so the main thread can
                         // tell that there were errors.
@@ -1097,14 +1332,15 @@ fn run_work_multithreaded(sess: &Session,
                 }
             }
 
-            tx.take().unwrap().send(()).unwrap();
+            tx.take().unwrap().send(thin_buffers).unwrap();
         }).unwrap();
     }
 
     let mut panicked = false;
+    let mut thin_buffers = vec![];
     for rx in futures {
         match rx.recv() {
-            Ok(()) => {},
+            Ok(buffers) => thin_buffers.extend(buffers),
             Err(_) => {
                 panicked = true;
             },
@@ -1115,6 +1351,7 @@ fn run_work_multithreaded(sess: &Session,
     if panicked {
         sess.fatal("aborting due to worker thread panic");
     }
+    thin_buffers
 }
 
 pub fn run_assembler(sess: &Session, outputs: &OutputFilenames) {
@@ -1161,10 +1398,17 @@ pub unsafe fn with_llvm_pmb(llmod: ModuleRef,
     let opt_size = config.opt_size.unwrap_or(llvm::CodeGenOptSizeNone);
     let inline_threshold = config.inline_threshold;
 
+    let pgo_gen_path = config.pgo_gen.as_ref().map(|path| path2cstr(path));
+    let pgo_use_path = config.pgo_use.as_ref().map(|path| path2cstr(path));
+
     llvm::LLVMRustConfigurePassManagerBuilder(builder, opt_level,
                                               config.merge_functions,
                                               config.vectorize_slp,
-                                              config.vectorize_loop);
+                                              config.vectorize_loop,
+                                              pgo_gen_path.as_ref().map_or(ptr::null(),
+                                                                           |s| s.as_ptr()),
+                                              pgo_use_path.as_ref().map_or(ptr::null(),
+                                                                           |s| s.as_ptr()));
     llvm::LLVMPassManagerBuilderSetSizeLevel(builder, opt_size as u32);
 
     if opt_size != llvm::CodeGenOptSizeNone {
@@ -43,7 +43,7 @@ use rustc::dep_graph::AssertDepGraphSafe;
 use rustc::middle::cstore::LinkMeta;
 use rustc::hir::map as hir_map;
 use rustc::util::common::time;
-use rustc::session::config::{self, NoDebugInfo};
+use rustc::session::config::{self, NoDebugInfo, OutputFilenames};
 use rustc::session::{self, DataTypeKind, Session};
 use rustc_incremental::IncrementalHashesMap;
 use abi;
@@ -74,6 +74,7 @@ use value::Value;
 use rustc::util::nodemap::{NodeSet, FxHashMap, FxHashSet};
 
 use libc::c_uint;
+use std::cell::RefCell;
 use std::ffi::{CStr, CString};
 use std::str;
 use std::i32;
@@ -726,7 +727,8 @@ fn contains_null(s: &str) -> bool {
 
 fn write_metadata<'a, 'gcx>(tcx: TyCtxt<'a, 'gcx, 'gcx>,
                             link_meta: &LinkMeta,
-                            exported_symbols: &NodeSet)
+                            exported_symbols: &NodeSet,
+                            shared_generics: &[(u64, String)])
                             -> (ContextRef, ModuleRef, EncodedMetadata) {
     use flate;
 
@@ -761,7 +763,8 @@ fn write_metadata<'a, 'gcx>(tcx: TyCtxt<'a, 'gcx, 'gcx>,
     let cstore = &tcx.sess.cstore;
     let metadata = cstore.encode_metadata(tcx,
                                           &link_meta,
-                                          exported_symbols);
+                                          exported_symbols,
+                                          shared_generics);
     if kind == MetadataKind::Uncompressed {
         return (metadata_llcx, metadata_llmod, metadata);
     }
@@ -1048,9 +1051,26 @@ pub fn find_exported_symbols(tcx: TyCtxt, reachable: &NodeSet) -> NodeSet {
     }).collect()
 }
 
+/// The names of the symbols the local crate exports for its crate types, as
+/// `trans_crate` would compute them. Used by `--print exported-symbols`.
+pub fn exported_symbol_names<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                       reachable: &NodeSet)
+                                       -> Vec<String> {
+    let exported_symbols = find_exported_symbols(tcx, reachable);
+    let threshold = symbol_export::crates_export_threshold(&tcx.sess.crate_types.borrow());
+    let mut names: Vec<_> = symbol_export::local_exported_symbols(tcx, &exported_symbols)
+        .into_iter()
+        .filter(|&(_, level)| symbol_export::is_below_threshold(level, threshold))
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+    names
+}
+
 pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                              analysis: ty::CrateAnalysis,
-                             incremental_hashes_map: &IncrementalHashesMap)
+                             incremental_hashes_map: &IncrementalHashesMap,
+                             output_filenames: &OutputFilenames)
                              -> CrateTranslation {
     // Be careful with this krate: obviously it gives access to the
     // entire contents of the krate. So if you push any subtasks of
@@ -1067,11 +1087,32 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
     let shared_ccx = SharedCrateContext::new(tcx,
                                              exported_symbols,
-                                             check_overflow);
+                                             check_overflow,
+                                             output_filenames);
+
+    let should_trans = !tcx.sess.opts.debugging_opts.no_trans &&
+                       tcx.sess.opts.output_types.should_trans();
+
+    // Run the translation item collector and partition the collected items into
+    // codegen units. This happens before the metadata is written, as it lists
+    // the generic instances the crate shares with downstream crates.
+    let translation_items_and_codegen_units = if should_trans {
+        Some(collect_and_partition_translation_items(&shared_ccx))
+    } else {
+        None
+    };
+
+    let shared_generics = match translation_items_and_codegen_units {
+        Some((ref translation_items, _)) => {
+            symbol_export::shared_generics(tcx, translation_items)
+        }
+        None => vec![],
+    };
+
     // Translate the metadata.
     let (metadata_llcx, metadata_llmod, metadata) =
         time(tcx.sess.time_passes(), "write metadata", || {
-            write_metadata(tcx, &link_meta, shared_ccx.exported_symbols())
+            write_metadata(tcx, &link_meta, shared_ccx.exported_symbols(), &shared_generics)
         });
 
     let metadata_module = ModuleTranslation {
@@ -1085,8 +1126,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     let no_builtins = attr::contains_name(&krate.attrs, "no_builtins");
 
     // Skip crate items and just output metadata in -Z no-trans mode.
-    if tcx.sess.opts.debugging_opts.no_trans ||
-       !tcx.sess.opts.output_types.should_trans() {
+    if !should_trans {
         let empty_exported_symbols = ExportedSymbols::empty();
         let linker_info = LinkerInfo::new(&shared_ccx, &empty_exported_symbols);
         return CrateTranslation {
@@ -1099,13 +1139,11 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             no_builtins: no_builtins,
             linker_info: linker_info,
             windows_subsystem: None,
+            upstream_lto_modules: RefCell::new(vec![]),
         };
     }
 
-    // Run the translation item collector and partition the collected items into
-    // codegen units.
-    let (translation_items, codegen_units) =
-        collect_and_partition_translation_items(&shared_ccx);
+    let (translation_items, codegen_units) = translation_items_and_codegen_units.unwrap();
 
     let mut all_stats = Stats::default();
     let modules: Vec<ModuleTranslation> = codegen_units
@@ -1195,6 +1233,9 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                 }
             }
 
+            // Emit the coverage mapping, which needs to end up in llvm.used
+            mir::coverage::finalize(&ccx);
+
             // Create the llvm.used variable
             // This variable has type [N x i8*] and is stored in the llvm.metadata section
             if !ccx.used_statics().borrow().is_empty() {
@@ -1264,7 +1305,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
 
     let sess = shared_ccx.sess();
 
-    let exported_symbols = ExportedSymbols::compute(&shared_ccx);
+    let exported_symbols = ExportedSymbols::compute(&shared_ccx, &shared_generics);
 
     // Get the list of llvm modules we created. We'll do a few wacky
     // transforms on them now.
@@ -1319,6 +1360,7 @@ pub fn trans_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         no_builtins: no_builtins,
         linker_info: linker_info,
         windows_subsystem: windows_subsystem,
+        upstream_lto_modules: RefCell::new(vec![]),
     }
 }
 
@@ -1589,10 +1631,16 @@ fn collect_and_partition_translation_items<'a, 'tcx>(scx: &SharedCrateContext<'a
 
     assert_symbols_are_distinct(scx.tcx(), items.iter());
 
-    let strategy = if scx.sess().opts.debugging_opts.incremental.is_some() {
-        PartitioningStrategy::PerModule
-    } else {
-        PartitioningStrategy::FixedUnitCount(scx.sess().opts.cg.codegen_units)
+    let strategy = match (&scx.sess().opts.debugging_opts.incremental,
+                          scx.sess().opts.debugging_opts.hashed_partitioning) {
+        (&Some(_), Some(unit_count)) => PartitioningStrategy::Hashed(unit_count),
+        (&Some(_), None) => PartitioningStrategy::PerModule,
+        (&None, hashed) => {
+            if hashed.is_some() {
+                scx.sess().warn("`-Z hashed-partitioning` has no effect without `-Z incremental`");
+            }
+            PartitioningStrategy::FixedUnitCount(scx.sess().opts.cg.codegen_units)
+        }
     };
 
     let codegen_units = time(time_passes, "codegen unit partitioning", || {
@@ -1605,6 +1653,10 @@ fn collect_and_partition_translation_items<'a, 'tcx>(scx: &SharedCrateContext<'a
     assert!(scx.tcx().sess.opts.cg.codegen_units == codegen_units.len() ||
             scx.tcx().sess.opts.debugging_opts.incremental.is_some());
 
+    if scx.sess().opts.debugging_opts.print_partition_stats {
+        partitioning::print_stats(scx.tcx(), &codegen_units);
+    }
+
     let translation_items: FxHashSet<TransItem<'tcx>> = items.iter().cloned().collect();
 
     if scx.sess().opts.debugging_opts.print_trans_items.is_some() {
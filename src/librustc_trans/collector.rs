@@ -20,7 +20,9 @@
 //! is instantiated with.
 //! This also applies to generic items from other crates: A generic definition
 //! in crate X might produce monomorphizations that are compiled into crate Y.
-//! We also have to collect these here.
+//! We also have to collect these here, unless an upstream crate already
+//! compiled the same monomorphization and shares it (see `-Z share-generics`),
+//! in which case crate Y links against that copy.
 //!
 //! The following kinds of "translation items" are handled here:
 //!
@@ -201,6 +203,7 @@ use rustc::ty::adjustment::CustomCoerceUnsized;
 use rustc::mir::{self, Location};
 use rustc::mir::visit::Visitor as MirVisitor;
 
+use back::symbol_names;
 use context::SharedCrateContext;
 use common::{def_ty, instance_ty};
 use monomorphize::{self, Instance};
@@ -648,6 +651,10 @@ fn should_trans_locally<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, instance: &Instan
                 // We can link to the item in question, no instance needed
                 // in this crate
                 false
+            } else if symbol_names::upstream_instantiating_crate(tcx, *instance).is_some() {
+                // An upstream crate shares its copy of this generic instance,
+                // see `-Z share-generics`.
+                false
             } else {
                 if !tcx.is_mir_available(def_id) {
                     bug!("Cannot create local trans-item for {:?}", def_id)
@@ -784,11 +784,20 @@ pub fn compile_unit_metadata(scc: &SharedCrateContext,
     let producer = format!("clang LLVM (rustc version {})",
                            (option_env!("CFG_VERSION")).expect("CFG_VERSION"));
 
+    // With split debuginfo, the object file only records where to find the
+    // `.dwo` file `back::write` extracts the debuginfo into.
+    let split_name = if sess.opts.cg.split_debuginfo != config::SplitDebuginfo::Off {
+        let path = scc.output_filenames().temp_path_ext("dwo", Some(codegen_unit_name));
+        path.to_string_lossy().into_owned()
+    } else {
+        String::new()
+    };
+
     let name_in_debuginfo = CString::new(name_in_debuginfo).unwrap();
     let work_dir = CString::new(&sess.working_dir.0[..]).unwrap();
     let producer = CString::new(producer).unwrap();
     let flags = "\0";
-    let split_name = "\0";
+    let split_name = CString::new(split_name).unwrap();
 
     unsafe {
         let file_metadata = llvm::LLVMRustDIBuilderCreateFile(
@@ -802,7 +811,7 @@ pub fn compile_unit_metadata(scc: &SharedCrateContext,
             sess.opts.optimize != config::OptLevel::No,
             flags.as_ptr() as *const _,
             0,
-            split_name.as_ptr() as *const _)
+            split_name.as_ptr())
     };
 }
 
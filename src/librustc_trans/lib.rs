@@ -52,6 +52,7 @@ extern crate rustc_data_structures;
 extern crate rustc_incremental;
 pub extern crate rustc_llvm as llvm;
 extern crate rustc_platform_intrinsics as intrinsics;
+extern crate rustc_const_eval;
 extern crate rustc_const_math;
 #[macro_use]
 #[no_link]
@@ -63,12 +64,14 @@ extern crate syntax_pos;
 extern crate rustc_errors as errors;
 extern crate serialize;
 
-pub use base::trans_crate;
+pub use base::{trans_crate, exported_symbol_names};
 pub use back::symbol_names::provide;
 
 pub use metadata::LlvmMetadataLoader;
 pub use llvm_util::{init, target_features, print_version, print_passes, print, enable_llvm_debug};
 
+use std::cell::RefCell;
+
 pub mod back {
     mod archive;
     pub(crate) mod linker;
@@ -169,7 +172,11 @@ pub struct CrateTranslation {
     pub exported_symbols: back::symbol_export::ExportedSymbols,
     pub no_builtins: bool,
     pub windows_subsystem: Option<String>,
-    pub linker_info: back::linker::LinkerInfo
+    pub linker_info: back::linker::LinkerInfo,
+    /// The names of the modules of upstream crates which were translated
+    /// along with the crate by `-C lto=thin`. Their objects are linked in
+    /// place of those of the upstream crates.
+    pub upstream_lto_modules: RefCell<Vec<String>>,
 }
 
 __build_diagnostic_array! { librustc_trans, DIAGNOSTICS }
@@ -12,7 +12,7 @@ use syntax_pos::symbol::Symbol;
 use back::write::create_target_machine;
 use llvm;
 use rustc::session::Session;
-use rustc::session::config::PrintRequest;
+use rustc::session::config::{PrintRequest, SplitDebuginfo};
 use libc::{c_int, c_char};
 use std::ffi::CString;
 
@@ -53,6 +53,9 @@ unsafe fn configure_llvm(sess: &Session) {
         add("rustc"); // fake program name
         if sess.time_llvm_passes() { add("-time-passes"); }
         if sess.print_llvm_passes() { add("-debug-pass=Structure"); }
+        if sess.opts.cg.split_debuginfo != SplitDebuginfo::Off {
+            add("-split-dwarf=Enable");
+        }
 
         for arg in &sess.opts.cg.llvm_args {
             add(&(*arg));
@@ -12,7 +12,9 @@ use llvm::{self, ValueRef};
 use rustc::middle::const_val::{ConstEvalErr, ConstVal, ErrKind};
 use rustc_const_math::ConstInt::*;
 use rustc_const_math::ConstFloat::*;
+use rustc_const_eval::interpret;
 use rustc_const_math::{ConstInt, ConstMathErr};
+use rustc::hir;
 use rustc::hir::def_id::DefId;
 use rustc::infer::TransNormalize;
 use rustc::mir;
@@ -262,6 +264,10 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
     }
 
     fn trans(&mut self) -> Result<Const<'tcx>, ConstEvalErr<'tcx>> {
+        if let Some(result) = self.interpret() {
+            return result;
+        }
+
         let tcx = self.ccx.tcx();
         let mut bb = mir::START_BLOCK;
 
@@ -360,6 +366,137 @@ impl<'a, 'tcx> MirConstContext<'a, 'tcx> {
         }
     }
 
+    /// Evaluates a constant that calls a `const fn` with the MIR interpreter,
+    /// as the bodies of `const fn`s can contain control flow and mutation,
+    /// which can't be translated to LLVM constants directly. Returns `None`
+    /// for constants that don't call one, or whose value can't be lowered.
+    fn interpret(&self) -> Option<Result<Const<'tcx>, ConstEvalErr<'tcx>>> {
+        let has_call = self.mir.basic_blocks().iter().any(|data| {
+            match data.terminator().kind {
+                mir::TerminatorKind::Call { .. } => true,
+                _ => false
+            }
+        });
+        if self.mir.arg_count != 0 || !has_call {
+            return None;
+        }
+
+        let tcx = self.ccx.tcx();
+        match interpret::eval_mir(tcx, self.mir, self.substs) {
+            Ok((value, memory)) => {
+                let ty = self.monomorphize(&self.mir.return_ty);
+                self.lower_value(&value, &memory, ty).map(Ok)
+            }
+            Err(err) => {
+                let mut cause = &err;
+                loop {
+                    match cause.kind {
+                        ErrKind::InConstFn(_, box ref inner) |
+                        ErrKind::ErroneousReferencedConstant(box ref inner) => cause = inner,
+                        _ => break
+                    }
+                }
+                match cause.kind {
+                    // Leave what the interpreter doesn't support to the translation.
+                    ErrKind::UnimplementedConstVal(_) |
+                    ErrKind::TypeckError => None,
+                    _ => {
+                        err.report(tcx, self.mir.span, "constant");
+                        // The error was reported, don't report it again.
+                        Some(Err(ConstEvalErr { span: err.span, kind: ErrKind::TypeckError }))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Lowers a value computed by the MIR interpreter to a LLVM constant.
+    fn lower_value(&self, value: &interpret::Value<'tcx>, memory: &interpret::Memory<'tcx>,
+                   ty: Ty<'tcx>)
+                   -> Option<Const<'tcx>> {
+        let ccx = self.ccx;
+        let tcx = ccx.tcx();
+        let llval = match *value {
+            interpret::Value::Undef => C_undef(type_of::type_of(ccx, ty)),
+            interpret::Value::Const(ref cv) => {
+                match (cv, &ty.sty) {
+                    (&ConstVal::Function(def_id, substs), &ty::TyFnPtr(_)) => {
+                        callee::resolve_and_get_fn(ccx, def_id, substs)
+                    }
+                    _ => return Some(Const::from_constval(ccx, cv.clone(), ty))
+                }
+            }
+            interpret::Value::Aggregate { variant, ref fields } => {
+                let (field_tys, kind) = match ty.sty {
+                    ty::TyArray(elem_ty, n) => {
+                        (vec![elem_ty; n], mir::AggregateKind::Array(elem_ty))
+                    }
+                    ty::TyTuple(tys, _) => (tys.to_vec(), mir::AggregateKind::Tuple),
+                    ty::TyClosure(def_id, substs) => {
+                        (substs.upvar_tys(def_id, tcx).collect(),
+                         mir::AggregateKind::Closure(def_id, substs))
+                    }
+                    ty::TyAdt(adt, substs) if !adt.is_union() => {
+                        let field_tys = adt.variants[variant].fields.iter().map(|field| {
+                            tcx.normalize_associated_type(&field.ty(tcx, substs))
+                        }).collect();
+                        (field_tys, mir::AggregateKind::Adt(adt, variant, substs, None))
+                    }
+                    _ => return None
+                };
+                let undef = interpret::Value::Undef;
+                let mut llfields = Vec::with_capacity(field_tys.len());
+                for (i, &field_ty) in field_tys.iter().enumerate() {
+                    let field = fields.get(i).unwrap_or(&undef);
+                    match self.lower_value(field, memory, field_ty) {
+                        Some(field) => llfields.push(field.llval),
+                        None => return None
+                    }
+                }
+                if let mir::AggregateKind::Array(_) = kind {
+                    return Some(self.const_array(ty, &llfields));
+                }
+                trans_const(ccx, ty, &kind, &llfields)
+            }
+            interpret::Value::Ptr(ref ptr) => {
+                let pointee_ty = match ty.sty {
+                    ty::TyRef(_, ty::TypeAndMut { ty, mutbl: hir::MutImmutable }) => ty,
+                    _ => return None
+                };
+                let target = match memory.read(ptr) {
+                    Ok(target) => target,
+                    Err(_) => return None
+                };
+                if ccx.shared().type_is_sized(pointee_ty) {
+                    let target = match self.lower_value(target, memory, pointee_ty) {
+                        Some(target) => target,
+                        None => return None
+                    };
+                    consts::addr_of(ccx, target.llval, ccx.align_of(pointee_ty), "ref")
+                } else if let ty::TySlice(elem_ty) = pointee_ty.sty {
+                    let len = match *target {
+                        interpret::Value::Aggregate { ref fields, .. } => fields.len(),
+                        _ => return None
+                    };
+                    let array_ty = tcx.mk_array(elem_ty, len);
+                    let array = match self.lower_value(target, memory, array_ty) {
+                        Some(array) => array,
+                        None => return None
+                    };
+                    let llptr = consts::addr_of(ccx, array.llval, ccx.align_of(array_ty), "ref");
+                    let llty = type_of::in_memory_type_of(ccx, pointee_ty).ptr_to();
+                    let base = consts::ptrcast(llptr, llty);
+                    let prev_const = ccx.const_unsized().borrow_mut().insert(base, llptr);
+                    assert!(prev_const.is_none() || prev_const == Some(llptr));
+                    C_struct(ccx, &[base, C_uint(ccx, len)], false)
+                } else {
+                    return None;
+                }
+            }
+        };
+        Some(Const::new(llval, ty))
+    }
+
     fn store(&mut self, dest: &mir::Lvalue<'tcx>, value: Const<'tcx>, span: Span) {
         if let mir::Lvalue::Local(index) = *dest {
             self.locals[index] = Some(value);
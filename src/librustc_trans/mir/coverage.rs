@@ -0,0 +1,271 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Source-based code coverage for `-Z instrument-coverage`.
+//!
+//! Every basic block of an instrumented function that has a source span gets
+//! a counter, incremented by an `llvm.instrprof.increment` call at the start
+//! of the block, and a code region covering the spans of its statements and
+//! terminator. The whole body gets a region as well, counted by the counter
+//! of the start block, so that functions which never run show up as
+//! uncovered instead of missing.
+//!
+//! LLVM's instrprof lowering pass turns the increments into counters which
+//! the profiler runtime writes to a `.profraw` file on exit, while the
+//! regions are written to the `__llvm_covmap` section, where `llvm-cov`
+//! finds them to map the counters back to the source.
+
+use libc::{c_char, c_uint};
+use llvm::{self, ValueRef, CoverageRegion};
+use rustc::mir::{self, Mir};
+use rustc::ty;
+use rustc_data_structures::bitvec::BitVector;
+use rustc_data_structures::fx::FxHasher;
+use rustc_data_structures::indexed_vec::Idx;
+use builder::Builder;
+use common::{C_array, C_bytes, C_struct, C_u32, C_u64, CrateContext, val_ty};
+use consts;
+use declare;
+use monomorphize::Instance;
+use type_::Type;
+
+use syntax::codemap::CodeMap;
+use syntax_pos::{DUMMY_SP, NO_EXPANSION, Pos, Span};
+
+use std::cmp;
+use std::ffi::CString;
+use std::hash::{Hash, Hasher};
+use std::iter;
+use std::path::Path;
+
+/// The coverage mapping record of an instrumented function.
+pub struct FunctionCoverage {
+    /// The name the profiler runtime knows the function by, its symbol name.
+    name: String,
+    /// The hash of the regions, which `llvm-cov` checks to find out whether
+    /// the counters of a profile still match the mapping.
+    hash: u64,
+    /// The source file all regions of the function are in.
+    file: String,
+    regions: Vec<CoverageRegion>,
+}
+
+/// The counters of a function being translated.
+pub struct CoverageCounters {
+    /// The name variable of the function, as an `i8*`.
+    name: ValueRef,
+    hash: u64,
+    num_counters: u32,
+    /// The blocks whose counter is referenced by a region, which are the
+    /// only ones that need to increment it.
+    counted: BitVector,
+}
+
+impl CoverageCounters {
+    pub fn increment(&self, bcx: &Builder, bb: mir::BasicBlock) {
+        if !self.counted.contains(bb.index()) {
+            return;
+        }
+        let ccx = bcx.ccx;
+        bcx.call(ccx.get_intrinsic("llvm.instrprof.increment"),
+                 &[self.name,
+                   C_u64(ccx, self.hash),
+                   C_u32(ccx, self.num_counters),
+                   C_u32(ccx, bb.index() as u32)],
+                 None);
+    }
+}
+
+/// Computes the regions of `instance` and records its coverage mapping, if
+/// it is to be instrumented.
+pub fn instrument<'a, 'tcx>(ccx: &CrateContext<'a, 'tcx>,
+                            mir: &Mir<'tcx>,
+                            instance: Instance<'tcx>)
+                            -> Option<CoverageCounters> {
+    if !ccx.sess().opts.debugging_opts.instrument_coverage {
+        return None;
+    }
+
+    // Only the functions of the local crate have spans pointing into the
+    // sources being compiled; shims don't have any source at all.
+    match instance.def {
+        ty::InstanceDef::Item(def_id) if def_id.is_local() => {}
+        _ => return None,
+    }
+    let body_span = mir.span;
+    if body_span == DUMMY_SP || body_span.ctxt != NO_EXPANSION {
+        return None;
+    }
+
+    let codemap = ccx.sess().codemap();
+    let mut counted = BitVector::new(mir.basic_blocks().len());
+    let mut regions = vec![make_region(codemap, mir::START_BLOCK, body_span)];
+    counted.insert(mir::START_BLOCK.index());
+
+    for (bb, data) in mir.basic_blocks().iter_enumerated() {
+        // Cleanup blocks only run while unwinding, and instrumenting them
+        // would need funclet bundles on MSVC.
+        if data.is_cleanup {
+            continue;
+        }
+
+        // Spans from macro expansions, or from outside of the body like the
+        // ones of inlined functions, don't say anything about the body.
+        let spans = data.statements.iter().map(|statement| statement.source_info.span)
+                        .chain(iter::once(data.terminator().source_info.span))
+                        .filter(|&span| {
+                            span != DUMMY_SP && span.ctxt == NO_EXPANSION &&
+                                body_span.contains(span)
+                        });
+        let hull = spans.fold(None, |hull: Option<Span>, span| {
+            Some(match hull {
+                Some(hull) => Span {
+                    lo: cmp::min(hull.lo, span.lo),
+                    hi: cmp::max(hull.hi, span.hi),
+                    ctxt: NO_EXPANSION,
+                },
+                None => span,
+            })
+        });
+        if let Some(hull) = hull {
+            regions.push(make_region(codemap, bb, hull));
+            counted.insert(bb.index());
+        }
+    }
+
+    let mut hasher = FxHasher::default();
+    regions.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    let name = ccx.tcx().symbol_name(instance).to_string();
+    let file = codemap.lookup_char_pos(body_span.lo).file.name.clone();
+    let file = Path::new(&ccx.sess().working_dir.0).join(file).to_string_lossy().into_owned();
+
+    let name_var = create_name_var(ccx, &name);
+    ccx.coverage_functions().borrow_mut().push(FunctionCoverage {
+        name: name,
+        hash: hash,
+        file: file,
+        regions: regions,
+    });
+
+    Some(CoverageCounters {
+        name: name_var,
+        hash: hash,
+        num_counters: mir.basic_blocks().len() as u32,
+        counted: counted,
+    })
+}
+
+/// Makes the region of `span`, counted by the counter of `bb`. Lines and
+/// columns are 1-based, and the end column is exclusive.
+fn make_region(codemap: &CodeMap, bb: mir::BasicBlock, span: Span) -> CoverageRegion {
+    let start = codemap.lookup_char_pos(span.lo);
+    let end = codemap.lookup_char_pos(span.hi);
+    CoverageRegion {
+        counter_index: bb.index() as u32,
+        line_start: start.line as u32,
+        column_start: start.col.to_usize() as u32 + 1,
+        line_end: end.line as u32,
+        column_end: end.col.to_usize() as u32 + 1,
+    }
+}
+
+/// Creates the `__profn_` variable holding the name of a function, which the
+/// instrprof lowering pass derives the names of its counters from.
+fn create_name_var(ccx: &CrateContext, name: &str) -> ValueRef {
+    let var_name = format!("__profn_{}", name);
+    let init = C_bytes(ccx, name.as_bytes());
+    let g = declare::define_global(ccx, &var_name, val_ty(init)).unwrap_or_else(|| {
+        bug!("symbol `{}` is already defined", var_name);
+    });
+    unsafe {
+        llvm::LLVMSetInitializer(g, init);
+        llvm::LLVMSetGlobalConstant(g, llvm::True);
+        llvm::LLVMRustSetLinkage(g, llvm::Linkage::PrivateLinkage);
+    }
+    consts::ptrcast(g, Type::i8p(ccx))
+}
+
+/// Emits the `__llvm_coverage_mapping` variable with the coverage mapping
+/// records of the functions instrumented in this codegen unit.
+pub fn finalize(ccx: &CrateContext) {
+    let functions = ccx.coverage_functions().borrow();
+    if functions.is_empty() {
+        return;
+    }
+
+    let mut files: Vec<&str> = vec![];
+    for function in functions.iter() {
+        if !files.contains(&&function.file[..]) {
+            files.push(&function.file);
+        }
+    }
+    let files: Vec<CString> = files.into_iter().map(|file| CString::new(file).unwrap()).collect();
+    let file_ptrs: Vec<*const c_char> = files.iter().map(|file| file.as_ptr()).collect();
+    let mut data = llvm::build_byte_buffer(|s| unsafe {
+        llvm::LLVMRustCoverageWriteFilenamesSectionToBuffer(file_ptrs.as_ptr(),
+                                                            file_ptrs.len(),
+                                                            s);
+    });
+    let filenames_size = data.len();
+
+    // Each record is `<{ i64 NameMD5, i32 DataSize, i64 FuncHash }>`.
+    let record_ty = Type::struct_(ccx, &[Type::i64(ccx), Type::i32(ccx), Type::i64(ccx)], true);
+    let mut records = vec![];
+    for function in functions.iter() {
+        let file_id = files.iter().position(|file| file.to_bytes() == function.file.as_bytes())
+                           .unwrap();
+        let mapping = llvm::build_byte_buffer(|s| unsafe {
+            llvm::LLVMRustCoverageWriteMappingToBuffer(file_id as c_uint,
+                                                       function.regions.as_ptr(),
+                                                       function.regions.len() as c_uint,
+                                                       s);
+        });
+        let name = CString::new(&function.name[..]).unwrap();
+        let name_md5 = unsafe { llvm::LLVMRustCoverageComputeHash(name.as_ptr()) };
+        records.push(C_struct(ccx, &[C_u64(ccx, name_md5),
+                                     C_u32(ccx, mapping.len() as u32),
+                                     C_u64(ccx, function.hash)], true));
+        data.extend(mapping);
+    }
+    let coverage_size = data.len() - filenames_size;
+
+    // The filenames and mappings are padded to a multiple of 8 bytes, which
+    // the header doesn't account for.
+    while data.len() % 8 != 0 {
+        data.push(0);
+    }
+
+    let version = unsafe { llvm::LLVMRustCoverageMappingVersion() };
+    let header = C_struct(ccx, &[C_u32(ccx, records.len() as u32),
+                                 C_u32(ccx, filenames_size as u32),
+                                 C_u32(ccx, coverage_size as u32),
+                                 C_u32(ccx, version)], false);
+    let covmap = C_struct(ccx, &[header, C_array(record_ty, &records), C_bytes(ccx, &data)],
+                          false);
+
+    let section = if ccx.sess().target.target.options.is_like_osx {
+        "__LLVM_COV,__llvm_covmap"
+    } else {
+        "__llvm_covmap"
+    };
+    let section = CString::new(section).unwrap();
+    let g = declare::define_global(ccx, "__llvm_coverage_mapping", val_ty(covmap))
+        .unwrap_or_else(|| bug!("symbol `__llvm_coverage_mapping` is already defined"));
+    unsafe {
+        llvm::LLVMSetInitializer(g, covmap);
+        llvm::LLVMSetGlobalConstant(g, llvm::True);
+        llvm::LLVMRustSetLinkage(g, llvm::Linkage::InternalLinkage);
+        llvm::LLVMSetSection(g, section.as_ptr());
+        llvm::LLVMSetAlignment(g, 8);
+        ccx.used_statics().borrow_mut().push(consts::ptrcast(g, Type::i8p(ccx)));
+    }
+}
@@ -102,6 +102,9 @@ pub struct MirContext<'a, 'tcx:'a> {
 
     /// If this function is being monomorphized, this contains the type substitutions used.
     param_substs: &'tcx Substs<'tcx>,
+
+    /// The coverage counters of the blocks, with `-Z instrument-coverage`.
+    coverage: Option<coverage::CoverageCounters>,
 }
 
 impl<'a, 'tcx> MirContext<'a, 'tcx> {
@@ -245,6 +248,7 @@ pub fn trans_mir<'a, 'tcx: 'a>(
             assert!(!instance.substs.needs_infer());
             instance.substs
         },
+        coverage: coverage::instrument(ccx, mir, instance),
     };
 
     let lvalue_locals = analyze::lvalue_locals(&mircx);
@@ -603,6 +607,7 @@ fn arg_local_refs<'a, 'tcx>(bcx: &Builder<'a, 'tcx>,
 mod analyze;
 mod block;
 mod constant;
+pub mod coverage;
 pub mod lvalue;
 mod operand;
 mod rvalue;
@@ -101,7 +101,18 @@
 //! Note though that as a side-effect of creating a codegen units per
 //! source-level module, functions from the same module will be available for
 //! inlining, even when they are not marked #[inline].
-
+//!
+//! Hashed Partitioning
+//! -------------------
+//! A module's volatile codegen unit still gets invalidated whenever a new
+//! instance of any of its generic functions comes up. With
+//! `-Z hashed-partitioning=N`, the volatile code of each module is spread over
+//! N codegen units instead, and the instances of a generic function always go
+//! to the unit picked by the hash of the function's `DefPath`. The hash doesn't
+//! depend on anything else in the crate, so a new instance of a generic
+//! function only invalidates the one unit holding that function's instances.
+
+use back::symbol_export;
 use collector::InliningMap;
 use common;
 use context::SharedCrateContext;
@@ -124,6 +135,10 @@ pub enum PartitioningStrategy {
     /// Generate one codegen unit per source-level module.
     PerModule,
 
+    /// Like `PerModule`, but spread the instances of generic functions over
+    /// this many codegen units per module, by the hash of their `DefPath`.
+    Hashed(usize),
+
     /// Partition the whole crate into a fixed number of codegen units.
     FixedUnitCount(usize)
 }
@@ -164,6 +179,12 @@ impl<'tcx> CodegenUnit<'tcx> {
         &self.items
     }
 
+    /// A rough estimate of the amount of code in this codegen unit, see
+    /// `TransItem::size_estimate`.
+    pub fn size_estimate<'a>(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>) -> usize {
+        self.items.keys().map(|item| item.size_estimate(tcx)).sum()
+    }
+
     pub fn work_product_id(&self) -> Arc<WorkProductId> {
         Arc::new(WorkProductId(self.name().to_string()))
     }
@@ -187,7 +208,8 @@ impl<'tcx> CodegenUnit<'tcx> {
                     let node_id =
                         scx.tcx().hir.as_local_node_id(instance.def_id());
                     node_id.map(|node_id| exported_symbols.contains(&node_id))
-                        .unwrap_or(false)
+                        .unwrap_or(false) ||
+                    symbol_export::is_shared_generic(scx.tcx(), &item)
                 }
                 TransItem::Static(node_id) => {
                     exported_symbols.contains(&node_id)
@@ -244,7 +266,8 @@ pub fn partition<'a, 'tcx, I>(scx: &SharedCrateContext<'a, 'tcx>,
     // respective 'home' codegen unit. Regular translation items are all
     // functions and statics defined in the local crate.
     let mut initial_partitioning = place_root_translation_items(scx,
-                                                                trans_items);
+                                                                trans_items,
+                                                                &strategy);
 
     debug_dump(tcx, "INITIAL PARTITONING:", initial_partitioning.codegen_units.iter());
 
@@ -282,7 +305,8 @@ struct PreInliningPartitioning<'tcx> {
 struct PostInliningPartitioning<'tcx>(Vec<CodegenUnit<'tcx>>);
 
 fn place_root_translation_items<'a, 'tcx, I>(scx: &SharedCrateContext<'a, 'tcx>,
-                                             trans_items: I)
+                                             trans_items: I,
+                                             strategy: &PartitioningStrategy)
                                              -> PreInliningPartitioning<'tcx>
     where I: Iterator<Item = TransItem<'tcx>>
 {
@@ -300,7 +324,15 @@ fn place_root_translation_items<'a, 'tcx, I>(scx: &SharedCrateContext<'a, 'tcx>,
                               trans_item.is_generic_fn();
 
             let codegen_unit_name = match characteristic_def_id {
-                Some(def_id) => compute_codegen_unit_name(tcx, def_id, is_volatile),
+                Some(def_id) => {
+                    let name = compute_codegen_unit_name(tcx, def_id, is_volatile);
+                    match *strategy {
+                        PartitioningStrategy::Hashed(unit_count) if is_volatile => {
+                            hashed_codegen_unit_name(tcx, &name, trans_item, unit_count)
+                        }
+                        _ => name,
+                    }
+                }
                 None => Symbol::intern(FALLBACK_CODEGEN_UNIT).as_str(),
             };
 
@@ -353,8 +385,9 @@ fn merge_codegen_units<'tcx>(initial_partitioning: &mut PreInliningPartitioning<
     // Note that "size" is estimated here rather inaccurately as the number of
     // translation items in a given unit. This could be improved on.
     while codegen_units.len() > target_cgu_count {
-        // Sort small cgus to the back
-        codegen_units.sort_by_key(|cgu| -(cgu.items.len() as i64));
+        // Sort small cgus to the back, breaking ties by name so that the
+        // result doesn't depend on the order the units were created in.
+        codegen_units.sort_by_key(|cgu| (-(cgu.items.len() as i64), cgu.name.clone()));
         let smallest = codegen_units.pop().unwrap();
         let second_smallest = codegen_units.last_mut().unwrap();
 
@@ -499,10 +532,42 @@ fn compute_codegen_unit_name<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     return Symbol::intern(&mod_path[..]).as_str();
 }
 
+/// The name of the unit, out of `unit_count` units split from the volatile
+/// unit `volatile_name`, which the generic function of `trans_item` hashes to.
+fn hashed_codegen_unit_name<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                      volatile_name: &str,
+                                      trans_item: TransItem<'tcx>,
+                                      unit_count: usize)
+                                      -> InternedString {
+    let def_id = match trans_item {
+        TransItem::Fn(instance) => instance.def_id(),
+        TransItem::Static(..) |
+        TransItem::GlobalAsm(..) => bug!("non-generic {:?} in a volatile codegen unit", trans_item),
+    };
+    let index = tcx.def_path_hash(def_id).to_smaller_hash() % unit_count as u64;
+    Symbol::intern(&format!("{}-{}", volatile_name, index)).as_str()
+}
+
 fn numbered_codegen_unit_name(crate_name: &str, index: usize) -> InternedString {
     Symbol::intern(&format!("{}{}{}", crate_name, NUMBERED_CODEGEN_UNIT_MARKER, index)).as_str()
 }
 
+/// Prints the number of items and the estimated size of each codegen unit,
+/// for `-Z print-partition-stats`.
+pub fn print_stats<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, codegen_units: &[CodegenUnit<'tcx>]) {
+    let mut total_items = 0;
+    let mut total_size = 0;
+    for cgu in codegen_units {
+        let size = cgu.size_estimate(tcx);
+        println!("PARTITION_STATS {}: {} items, estimated size {}",
+                 cgu.name(), cgu.items().len(), size);
+        total_items += cgu.items().len();
+        total_size += size;
+    }
+    println!("PARTITION_STATS total: {} codegen units, {} items, estimated size {}",
+             codegen_units.len(), total_items, total_size);
+}
+
 fn debug_dump<'a, 'b, 'tcx, I>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                label: &str,
                                cgus: I)
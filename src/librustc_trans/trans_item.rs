@@ -231,6 +231,26 @@ impl<'a, 'tcx> TransItem<'tcx> {
         }
     }
 
+    /// A rough estimate of the amount of code this item translates to: the
+    /// number of MIR statements and terminators of a function, 1 otherwise.
+    pub fn size_estimate(&self, tcx: TyCtxt<'a, 'tcx, 'tcx>) -> usize {
+        match *self {
+            TransItem::Fn(instance) => {
+                match instance.def {
+                    ty::InstanceDef::Intrinsic(..) |
+                    ty::InstanceDef::Virtual(..) => 1,
+                    def => {
+                        tcx.instance_mir(def).basic_blocks().iter().map(|data| {
+                            data.statements.len() + 1
+                        }).sum()
+                    }
+                }
+            }
+            TransItem::Static(..) |
+            TransItem::GlobalAsm(..) => 1,
+        }
+    }
+
     pub fn is_generic_fn(&self) -> bool {
         match *self {
             TransItem::Fn(ref instance) => {
@@ -59,6 +59,14 @@ pub fn init() {
     }
 }
 
+/// Returns a pair of random keys for seeding the `RandomState` of hash maps.
+#[allow(deprecated)] // rand
+pub fn hashmap_random_keys() -> (u64, u64) {
+    use rand::Rng;
+    let mut r = self::rand::OsRng::new().expect("failed to create an OS RNG");
+    (r.gen(), r.gen())
+}
+
 pub fn decode_error_kind(errno: i32) -> ErrorKind {
     match errno {
         syscall::ECONNREFUSED => ErrorKind::ConnectionRefused,
@@ -103,6 +103,14 @@ pub use sys::android::signal;
 #[cfg(not(target_os = "android"))]
 pub use libc::signal;
 
+/// Returns a pair of random keys for seeding the `RandomState` of hash maps.
+#[allow(deprecated)] // rand
+pub fn hashmap_random_keys() -> (u64, u64) {
+    use rand::Rng;
+    let mut r = self::rand::OsRng::new().expect("failed to create an OS RNG");
+    (r.gen(), r.gen())
+}
+
 pub fn decode_error_kind(errno: i32) -> ErrorKind {
     match errno as libc::c_int {
         libc::ECONNREFUSED => ErrorKind::ConnectionRefused,
@@ -0,0 +1,77 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! System bindings for the wasm32-unknown-unknown target.
+//!
+//! This target has no operating system underneath it: there are no files, no
+//! processes, no network, no threads and no clock. Operations that need one
+//! of those return an "unsupported" error, or panic where the interface
+//! leaves no room for an error. Standard output and standard error discard
+//! what is written to them.
+//!
+//! Everything here assumes a single thread of execution, which is the only
+//! thing the wasm MVP offers.
+
+#![allow(dead_code, missing_docs, bad_style)]
+
+use io::{self, ErrorKind};
+
+pub mod args;
+pub mod condvar;
+pub mod env;
+pub mod fs;
+pub mod memchr;
+pub mod mutex;
+pub mod net;
+pub mod os;
+#[path = "../unix/os_str.rs"]
+pub mod os_str;
+#[path = "../unix/path.rs"]
+pub mod path;
+pub mod pipe;
+pub mod process;
+pub mod rand;
+pub mod rwlock;
+pub mod stack_overflow;
+pub mod stdio;
+pub mod thread;
+pub mod thread_local;
+pub mod time;
+
+#[cfg(not(test))]
+pub fn init() {
+}
+
+/// An uninhabited type, used for the handles of resources that can't be
+/// created on this target.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Void {}
+
+pub fn unsupported<T>() -> io::Result<T> {
+    Err(unsupported_err())
+}
+
+pub fn unsupported_err() -> io::Error {
+    io::Error::new(io::ErrorKind::Other,
+                   "operation not supported on wasm32-unknown-unknown")
+}
+
+/// There is no source of randomness, so hash maps use fixed keys.
+pub fn hashmap_random_keys() -> (u64, u64) {
+    (1, 2)
+}
+
+pub fn decode_error_kind(_code: i32) -> ErrorKind {
+    ErrorKind::Other
+}
+
+pub unsafe fn abort_internal() -> ! {
+    ::intrinsics::abort();
+}
@@ -0,0 +1,88 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use fmt;
+use time::Duration;
+
+// There is no clock to read, so `now` panics. Times are only ever created
+// from other times, which keeps the arithmetic below well defined.
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Instant(Duration);
+
+/// The time since the epoch, kept as seconds and nanoseconds rather than a
+/// `Duration` so that `UNIX_EPOCH` can be a constant.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SystemTime {
+    secs: u64,
+    nanos: u32,
+}
+
+pub const UNIX_EPOCH: SystemTime = SystemTime { secs: 0, nanos: 0 };
+
+impl Instant {
+    pub fn now() -> Instant {
+        panic!("time not implemented on wasm32-unknown-unknown")
+    }
+
+    pub fn sub_instant(&self, other: &Instant) -> Duration {
+        self.0.checked_sub(other.0).unwrap_or_else(|| {
+            panic!("other was less than the current instant")
+        })
+    }
+
+    pub fn add_duration(&self, other: &Duration) -> Instant {
+        Instant(self.0.checked_add(*other)
+                      .expect("overflow when adding duration to instant"))
+    }
+
+    pub fn sub_duration(&self, other: &Duration) -> Instant {
+        Instant(self.0.checked_sub(*other)
+                      .expect("overflow when subtracting duration from instant"))
+    }
+}
+
+impl SystemTime {
+    pub fn now() -> SystemTime {
+        panic!("time not implemented on wasm32-unknown-unknown")
+    }
+
+    pub fn sub_time(&self, other: &SystemTime) -> Result<Duration, Duration> {
+        let (me, other) = (self.since_epoch(), other.since_epoch());
+        me.checked_sub(other).ok_or_else(|| other - me)
+    }
+
+    pub fn add_duration(&self, other: &Duration) -> SystemTime {
+        SystemTime::from_since_epoch(self.since_epoch().checked_add(*other)
+                                         .expect("overflow when adding duration to time"))
+    }
+
+    pub fn sub_duration(&self, other: &Duration) -> SystemTime {
+        SystemTime::from_since_epoch(self.since_epoch().checked_sub(*other)
+                                         .expect("overflow when subtracting duration from time"))
+    }
+
+    fn since_epoch(&self) -> Duration {
+        Duration::new(self.secs, self.nanos)
+    }
+
+    fn from_since_epoch(d: Duration) -> SystemTime {
+        SystemTime { secs: d.as_secs(), nanos: d.subsec_nanos() }
+    }
+}
+
+impl fmt::Debug for SystemTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SystemTime")
+         .field("secs", &self.secs)
+         .field("nanos", &self.nanos)
+         .finish()
+    }
+}
@@ -60,6 +60,8 @@ macro_rules! declare_features {
         pub struct Features {
             /// #![feature] attrs for stable language features, for error reporting
             pub declared_stable_lang_features: Vec<(Symbol, Span)>,
+            /// #![feature] attrs for active language features
+            pub declared_lang_features: Vec<(Symbol, Span)>,
             /// #![feature] attrs for non-language (library) features
             pub declared_lib_features: Vec<(Symbol, Span)>,
             $(pub $feature: bool),+
@@ -69,6 +71,7 @@ macro_rules! declare_features {
             pub fn new() -> Features {
                 Features {
                     declared_stable_lang_features: Vec::new(),
+                    declared_lang_features: Vec::new(),
                     declared_lib_features: Vec::new(),
                     $($feature: false),+
                 }
@@ -352,6 +355,13 @@ declare_features! (
 
     // rustc internal
     (active, abi_thiscall, "1.19.0", None),
+
+    // Used to identify the crate that contains the profiler runtime
+    // rustc internal
+    (active, profiler_runtime, "1.19.0", None),
+
+    // Allows local variables, branches and loops in `const fn`.
+    (active, const_fn_control_flow, "1.19.0", Some(24111)),
 );
 
 declare_features! (
@@ -691,6 +701,13 @@ pub const BUILTIN_ATTRIBUTES: &'static [(&'static str, AttributeType, AttributeG
                                               identify crates that contain the runtime of a \
                                               sanitizer and will never be stable",
                                              cfg_fn!(sanitizer_runtime))),
+    ("profiler_runtime", Whitelisted, Gated(Stability::Unstable,
+                                            "profiler_runtime",
+                                            "the `#[profiler_runtime]` attribute is used to \
+                                             identify the `profiler_builtins` crate which \
+                                             contains the profiler runtime and will never be \
+                                             stable",
+                                            cfg_fn!(profiler_runtime))),
 
     ("allow_internal_unstable", Normal, Gated(Stability::Unstable,
                                               "allow_internal_unstable",
@@ -1453,6 +1470,7 @@ pub fn get_features(span_handler: &Handler, krate_attrs: &[ast::Attribute]) -> F
                     if let Some(&(_, _, _, setter)) = ACTIVE_FEATURES.iter()
                         .find(|& &(n, _, _, _)| name == n) {
                         *(setter(&mut features)) = true;
+                        features.declared_lang_features.push((name, mi.span));
                         feature_checker.collect(&features, mi.span);
                     }
                     else if let Some(&(_, _, _)) = REMOVED_FEATURES.iter()
@@ -0,0 +1,262 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A SARIF emitter for errors.
+//!
+//! Unlike the JSON emitter, which prints one object per diagnostic, this
+//! emitter collects all diagnostics of the compilation and writes a single
+//! [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/) log when it
+//! is dropped, i.e. when the session goes away.
+//!
+//! Error codes and lint names become the `rules` of the run, primary spans
+//! become the `locations` of a result, labelled secondary spans its
+//! `relatedLocations`, and code suggestions its `fixes`.
+
+use codemap::{CodeMap, FilePathMapping};
+use syntax_pos::{Pos, Span, MultiSpan};
+use errors::registry::Registry;
+use errors::{DiagnosticBuilder, CodeSuggestion, CodeMapper, Level};
+use errors::emitter::Emitter;
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use rustc_serialize::json::{Json, ToJson};
+
+const SARIF_SCHEMA: &'static str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &'static str = "2.1.0";
+const ERROR_INDEX_URL: &'static str = "https://doc.rust-lang.org/error-index.html";
+
+pub struct SarifEmitter {
+    dst: Box<Write + Send>,
+    registry: Option<Registry>,
+    cm: Rc<CodeMapper + 'static>,
+    /// The rules referenced so far, in order of first use.
+    rules: Vec<Json>,
+    rule_indices: HashMap<String, usize>,
+    results: Vec<Json>,
+}
+
+impl SarifEmitter {
+    pub fn stderr(registry: Option<Registry>,
+                  code_map: Rc<CodeMap>) -> SarifEmitter {
+        SarifEmitter::new(Box::new(io::stderr()), registry, code_map)
+    }
+
+    pub fn basic() -> SarifEmitter {
+        let file_path_mapping = FilePathMapping::empty();
+        SarifEmitter::stderr(None, Rc::new(CodeMap::new(file_path_mapping)))
+    }
+
+    pub fn new(dst: Box<Write + Send>,
+               registry: Option<Registry>,
+               code_map: Rc<CodeMap>) -> SarifEmitter {
+        SarifEmitter {
+            dst: dst,
+            registry: registry,
+            cm: code_map,
+            rules: vec![],
+            rule_indices: HashMap::new(),
+            results: vec![],
+        }
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit(&mut self, db: &DiagnosticBuilder) {
+        let result = self.result(db);
+        self.results.push(result);
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        let driver = object(vec![
+            ("name", "rustc".to_json()),
+            ("informationUri", "https://www.rust-lang.org/".to_json()),
+            ("version", option_env!("CFG_VERSION").unwrap_or("unknown version").to_json()),
+            ("rules", Json::Array(self.rules.drain(..).collect())),
+        ]);
+        let run = object(vec![
+            ("tool", object(vec![("driver", driver)])),
+            ("results", Json::Array(self.results.drain(..).collect())),
+        ]);
+        let log = object(vec![
+            ("$schema", SARIF_SCHEMA.to_json()),
+            ("version", SARIF_VERSION.to_json()),
+            ("runs", Json::Array(vec![run])),
+        ]);
+        // This may run while unwinding, where panicking would abort.
+        if let Err(e) = writeln!(&mut self.dst, "{}", log.pretty()) {
+            let _ = writeln!(io::stderr(), "failed to print diagnostics: {:?}", e);
+        }
+    }
+}
+
+impl SarifEmitter {
+    fn result(&mut self, db: &DiagnosticBuilder) -> Json {
+        // Notes and helps without a span of their own are folded into the
+        // message, the others become related locations.
+        let mut message = db.message();
+        let mut related = self.related_locations(&db.span);
+        for child in &db.children {
+            if child.span.primary_spans().is_empty() {
+                message.push_str(&format!("\n{}: {}", child.level, child.message()));
+            } else {
+                for &span in child.span.primary_spans() {
+                    related.push(self.location(span, Some(child.message())));
+                }
+            }
+        }
+
+        let mut result = vec![
+            ("level", sarif_level(db.level).to_json()),
+            ("message", text(message)),
+            ("locations", Json::Array(self.primary_locations(&db.span))),
+        ];
+        if let Some(id) = db.code.clone().or_else(|| db.lint.clone()) {
+            let index = self.rule_index(&id);
+            result.push(("ruleId", id.to_json()));
+            result.push(("ruleIndex", index.to_json()));
+        }
+        if !related.is_empty() {
+            result.push(("relatedLocations", Json::Array(related)));
+        }
+        let fixes: Vec<_> = db.suggestions.iter().flat_map(|sugg| self.fixes(sugg)).collect();
+        if !fixes.is_empty() {
+            result.push(("fixes", Json::Array(fixes)));
+        }
+        object(result)
+    }
+
+    /// Returns the index of the rule for the given error code or lint name,
+    /// registering the rule on first use.
+    fn rule_index(&mut self, id: &str) -> usize {
+        if let Some(&index) = self.rule_indices.get(id) {
+            return index;
+        }
+
+        let mut rule = vec![("id", id.to_json())];
+        let explanation = self.registry.as_ref().and_then(|r| r.find_description(id));
+        if let Some(explanation) = explanation {
+            let summary = explanation.trim().lines().next().unwrap_or("");
+            rule.push(("shortDescription", text(summary.to_owned())));
+            rule.push(("fullDescription", object(vec![
+                ("text", explanation.to_json()),
+                ("markdown", explanation.to_json()),
+            ])));
+            rule.push(("helpUri", format!("{}#{}", ERROR_INDEX_URL, id).to_json()));
+        }
+
+        let index = self.rules.len();
+        self.rules.push(object(rule));
+        self.rule_indices.insert(id.to_owned(), index);
+        index
+    }
+
+    fn primary_locations(&self, msp: &MultiSpan) -> Vec<Json> {
+        msp.span_labels()
+           .into_iter()
+           .filter(|span_label| span_label.is_primary)
+           .map(|span_label| self.location(span_label.span, span_label.label))
+           .collect()
+    }
+
+    fn related_locations(&self, msp: &MultiSpan) -> Vec<Json> {
+        msp.span_labels()
+           .into_iter()
+           .filter(|span_label| !span_label.is_primary && span_label.label.is_some())
+           .map(|span_label| self.location(span_label.span, span_label.label))
+           .collect()
+    }
+
+    fn location(&self, span: Span, message: Option<String>) -> Json {
+        let mut location = vec![("physicalLocation", self.physical_location(span))];
+        if let Some(message) = message {
+            location.push(("message", text(message)));
+        }
+        object(location)
+    }
+
+    fn physical_location(&self, span: Span) -> Json {
+        object(vec![
+            ("artifactLocation", self.artifact_location(span)),
+            ("region", self.region(span)),
+        ])
+    }
+
+    fn artifact_location(&self, span: Span) -> Json {
+        object(vec![("uri", self.cm.span_to_filename(span).to_json())])
+    }
+
+    fn region(&self, span: Span) -> Json {
+        let start = self.cm.lookup_char_pos(span.lo);
+        let end = self.cm.lookup_char_pos(span.hi);
+        object(vec![
+            ("startLine", start.line.to_json()),
+            ("startColumn", (start.col.to_usize() + 1).to_json()),
+            ("endLine", end.line.to_json()),
+            ("endColumn", (end.col.to_usize() + 1).to_json()),
+            ("byteOffset", (span.lo - start.file.start_pos).to_usize().to_json()),
+            ("byteLength", (span.hi - span.lo).to_usize().to_json()),
+        ])
+    }
+
+    /// A suggestion with several alternatives yields one fix per alternative.
+    fn fixes(&self, suggestion: &CodeSuggestion) -> Vec<Json> {
+        let alternatives = suggestion.substitution_parts
+                                     .first()
+                                     .map_or(0, |part| part.substitutions.len());
+        (0..alternatives).map(|i| {
+            // SARIF groups the replacements of a fix by artifact.
+            let mut changes: BTreeMap<String, Vec<Json>> = BTreeMap::new();
+            for part in &suggestion.substitution_parts {
+                let replacement = object(vec![
+                    ("deletedRegion", self.region(part.span)),
+                    ("insertedContent", text(part.substitutions[i].clone())),
+                ]);
+                changes.entry(self.cm.span_to_filename(part.span))
+                       .or_insert_with(Vec::new)
+                       .push(replacement);
+            }
+            let changes = changes.into_iter().map(|(file, replacements)| {
+                object(vec![
+                    ("artifactLocation", object(vec![("uri", file.to_json())])),
+                    ("replacements", Json::Array(replacements)),
+                ])
+            }).collect();
+            object(vec![
+                ("description", text(suggestion.msg.clone())),
+                ("artifactChanges", Json::Array(changes)),
+                ("properties", object(vec![
+                    ("applicability", format!("{:?}", suggestion.applicability).to_json()),
+                ])),
+            ])
+        }).collect()
+    }
+}
+
+fn sarif_level(level: Level) -> &'static str {
+    match level {
+        Level::Bug | Level::Fatal | Level::PhaseFatal | Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note | Level::Help | Level::Cancelled => "note",
+    }
+}
+
+/// A SARIF `message` object.
+fn text(s: String) -> Json {
+    object(vec![("text", Json::String(s))])
+}
+
+fn object(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(fields.into_iter().map(|(k, v)| (k.to_owned(), v)).collect())
+}
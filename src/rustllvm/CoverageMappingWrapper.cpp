@@ -0,0 +1,96 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#include "rustllvm.h"
+
+#if LLVM_VERSION_GE(4, 0)
+#include "llvm/ProfileData/Coverage/CoverageMapping.h"
+#include "llvm/ProfileData/Coverage/CoverageMappingWriter.h"
+#include "llvm/ProfileData/InstrProf.h"
+#endif
+
+using namespace llvm;
+
+// A code region of a function, counted by the `llvm.instrprof.increment`
+// counter `CounterIndex`. Lines and columns are 1-based and the end column
+// is exclusive. Must match `CoverageRegion` in `librustc_llvm/ffi.rs`.
+struct LLVMRustCoverageRegion {
+  uint32_t CounterIndex;
+  uint32_t LineStart;
+  uint32_t ColumnStart;
+  uint32_t LineEnd;
+  uint32_t ColumnEnd;
+};
+
+#if LLVM_VERSION_GE(4, 0)
+
+using namespace llvm::coverage;
+
+extern "C" void
+LLVMRustCoverageWriteFilenamesSectionToBuffer(const char **Filenames,
+                                              size_t FilenamesLen,
+                                              RustStringRef BufferOut) {
+  SmallVector<StringRef, 32> FilenameRefs;
+  for (size_t i = 0; i < FilenamesLen; i++) {
+    FilenameRefs.push_back(StringRef(Filenames[i]));
+  }
+  RawRustStringOstream OS(BufferOut);
+  CoverageFilenamesSectionWriter(FilenameRefs).write(OS);
+}
+
+extern "C" void
+LLVMRustCoverageWriteMappingToBuffer(unsigned FileID,
+                                     const LLVMRustCoverageRegion *Regions,
+                                     unsigned NumRegions,
+                                     RustStringRef BufferOut) {
+  unsigned VirtualFileMapping[] = {FileID};
+  SmallVector<CounterMappingRegion, 32> MappingRegions;
+  for (unsigned i = 0; i < NumRegions; i++) {
+    const LLVMRustCoverageRegion &R = Regions[i];
+    MappingRegions.push_back(CounterMappingRegion::makeRegion(
+        Counter::getCounter(R.CounterIndex), 0, R.LineStart, R.ColumnStart,
+        R.LineEnd, R.ColumnEnd));
+  }
+  RawRustStringOstream OS(BufferOut);
+  CoverageMappingWriter(VirtualFileMapping, None, MappingRegions).write(OS);
+}
+
+extern "C" uint64_t LLVMRustCoverageComputeHash(const char *Name) {
+  return IndexedInstrProf::ComputeHash(StringRef(Name));
+}
+
+extern "C" uint32_t LLVMRustCoverageMappingVersion() {
+  return CovMapVersion::CurrentVersion;
+}
+
+#else
+
+extern "C" void
+LLVMRustCoverageWriteFilenamesSectionToBuffer(const char **, size_t,
+                                              RustStringRef) {
+  report_fatal_error("-Z instrument-coverage requires LLVM 4.0 or later");
+}
+
+extern "C" void
+LLVMRustCoverageWriteMappingToBuffer(unsigned,
+                                     const LLVMRustCoverageRegion *,
+                                     unsigned, RustStringRef) {
+  report_fatal_error("-Z instrument-coverage requires LLVM 4.0 or later");
+}
+
+extern "C" uint64_t LLVMRustCoverageComputeHash(const char *) {
+  report_fatal_error("-Z instrument-coverage requires LLVM 4.0 or later");
+}
+
+extern "C" uint32_t LLVMRustCoverageMappingVersion() {
+  report_fatal_error("-Z instrument-coverage requires LLVM 4.0 or later");
+}
+
+#endif
@@ -21,9 +21,19 @@
 #include "llvm/Target/TargetMachine.h"
 #include "llvm/Target/TargetSubtargetInfo.h"
 #include "llvm/Transforms/IPO/PassManagerBuilder.h"
+#include "llvm/Transforms/Instrumentation.h"
 
 #if LLVM_VERSION_GE(4, 0)
+#include "llvm/Bitcode/BitcodeReader.h"
+#include "llvm/Bitcode/BitcodeWriterPass.h"
+#include "llvm/IR/ModuleSummaryIndex.h"
+#include "llvm/LTO/LTO.h"
 #include "llvm/Transforms/IPO/AlwaysInliner.h"
+#include "llvm/Transforms/IPO/FunctionImport.h"
+#include "llvm/Transforms/Utils/FunctionImportUtils.h"
+#if LLVM_VERSION_LE(4, 0)
+#include "llvm/Object/ModuleSummaryIndexObjectFile.h"
+#endif
 #endif
 
 #include "llvm-c/Transforms/PassManagerBuilder.h"
@@ -395,12 +405,30 @@ extern "C" void LLVMRustAddAnalysisPasses(LLVMTargetMachineRef TM,
 
 extern "C" void LLVMRustConfigurePassManagerBuilder(
     LLVMPassManagerBuilderRef PMBR, LLVMRustCodeGenOptLevel OptLevel,
-    bool MergeFunctions, bool SLPVectorize, bool LoopVectorize) {
+    bool MergeFunctions, bool SLPVectorize, bool LoopVectorize,
+    const char *PGOGenPath, const char *PGOUsePath) {
   // Ignore mergefunc for now as enabling it causes crashes.
   // unwrap(PMBR)->MergeFunctions = MergeFunctions;
   unwrap(PMBR)->SLPVectorize = SLPVectorize;
   unwrap(PMBR)->OptLevel = fromRust(OptLevel);
   unwrap(PMBR)->LoopVectorize = LoopVectorize;
+
+#if LLVM_VERSION_GE(4, 0)
+  if (PGOGenPath) {
+    assert(!PGOUsePath);
+#if LLVM_VERSION_GE(5, 0)
+    unwrap(PMBR)->EnablePGOInstrGen = true;
+#endif
+    unwrap(PMBR)->PGOInstrGen = PGOGenPath;
+  }
+  if (PGOUsePath) {
+    assert(!PGOGenPath);
+    unwrap(PMBR)->PGOInstrUse = PGOUsePath;
+  }
+#else
+  if (PGOGenPath || PGOUsePath)
+    report_fatal_error("profile-guided optimization requires LLVM 4.0 or later");
+#endif
 }
 
 // Unfortunately, the LLVM C API doesn't provide a way to set the `LibraryInfo`
@@ -555,6 +583,17 @@ extern "C" void LLVMRustAddAlwaysInlinePass(LLVMPassManagerBuilderRef PMBR,
 #endif
 }
 
+extern "C" void LLVMRustAddInstrProfilingPass(LLVMPassManagerRef PMR,
+                                              const char *OutputFilename) {
+#if LLVM_VERSION_GE(4, 0)
+  InstrProfOptions Options;
+  Options.InstrProfileOutput = OutputFilename;
+  unwrap(PMR)->add(createInstrProfilingLegacyPass(Options));
+#else
+  report_fatal_error("-Z instrument-coverage requires LLVM 4.0 or later");
+#endif
+}
+
 extern "C" void LLVMRustRunRestrictionPass(LLVMModuleRef M, char **Symbols,
                                            size_t Len) {
   llvm::legacy::PassManager passes;
@@ -613,3 +652,360 @@ extern "C" void LLVMRustSetModulePIELevel(LLVMModuleRef M) {
   unwrap(M)->setPIELevel(PIELevel::Level::Large);
 #endif
 }
+
+// Here you'll find an implementation of ThinLTO as used by the Rust compiler
+// right now. This ThinLTO support is only enabled on "recent ish" versions of
+// LLVM, and otherwise falls back to the stubs at the end of this file, which
+// `LLVMRustThinLTOAvailable` lets rustc check for up front.
+//
+// Most of the code here is copied from `lib/LTO/ThinLTOCodeGenerator.cpp`
+// and `lib/LTO/LTO.cpp`, trimmed to what rustc needs: rustc drives the
+// parallelism and the codegen itself, and only asks LLVM for the index and
+// the per-module steps.
+#if LLVM_VERSION_GE(4, 0)
+
+// The global analysis over all the modules of a ThinLTO session, which the
+// per-module steps below consult.
+struct LLVMRustThinLTOData {
+  // The combined summary index of all modules.
+  ModuleSummaryIndex Index;
+
+  // All modules we may import from, as serialized bitcode owned by rustc.
+  StringMap<MemoryBufferRef> ModuleMap;
+
+  // The symbols which must survive, i.e. which mustn't be internalized.
+  DenseSet<GlobalValue::GUID> GUIDPreservedSymbols;
+
+  // What each module imports from and exports to the others.
+  StringMap<FunctionImporter::ImportMapTy> ImportLists;
+  StringMap<FunctionImporter::ExportSetTy> ExportLists;
+  StringMap<GVSummaryMapTy> ModuleToDefinedGVSummaries;
+};
+
+struct LLVMRustThinLTOModule {
+  const char *identifier;
+  const char *data;
+  size_t len;
+};
+
+// Picks the copy of a symbol which the linker would keep, preferring strong
+// definitions. This is copied from `lib/LTO/ThinLTOCodeGenerator.cpp`.
+static const GlobalValueSummary *
+getFirstDefinitionForLinker(const GlobalValueSummaryList &GVSummaryList) {
+  auto StrongDefForLinker = llvm::find_if(
+      GVSummaryList, [](const std::unique_ptr<GlobalValueSummary> &Summary) {
+        auto Linkage = Summary->linkage();
+        return !GlobalValue::isAvailableExternallyLinkage(Linkage) &&
+               !GlobalValue::isWeakForLinker(Linkage);
+      });
+  if (StrongDefForLinker != GVSummaryList.end())
+    return StrongDefForLinker->get();
+
+  auto FirstDefForLinker = llvm::find_if(
+      GVSummaryList, [](const std::unique_ptr<GlobalValueSummary> &Summary) {
+        auto Linkage = Summary->linkage();
+        return !GlobalValue::isAvailableExternallyLinkage(Linkage);
+      });
+  if (FirstDefForLinker == GVSummaryList.end())
+    return nullptr;
+  return FirstDefForLinker->get();
+}
+
+// The main entry point for creating the global ThinLTO analysis. The
+// structure here is basically the same as before threads are spawned in the
+// `run` function in `lib/LTO/ThinLTOCodeGenerator.cpp`.
+extern "C" LLVMRustThinLTOData *
+LLVMRustCreateThinLTOData(LLVMRustThinLTOModule *Modules,
+                          unsigned NumModules,
+                          const char **PreservedSymbols,
+                          unsigned NumSymbols) {
+  auto Ret = llvm::make_unique<LLVMRustThinLTOData>();
+
+  // Load each module's summary and merge it into one combined index.
+  for (unsigned I = 0; I < NumModules; I++) {
+    auto Module = &Modules[I];
+    StringRef Buffer(Module->data, Module->len);
+    MemoryBufferRef MemBuffer(Buffer, Module->identifier);
+
+    Ret->ModuleMap[Module->identifier] = MemBuffer;
+
+#if LLVM_VERSION_GE(5, 0)
+    if (Error Err = readModuleSummaryIndex(MemBuffer, Ret->Index, I)) {
+      LLVMRustSetLastError(toString(std::move(Err)).c_str());
+      return nullptr;
+    }
+#else
+    Expected<std::unique_ptr<object::ModuleSummaryIndexObjectFile>> ObjOrErr =
+        object::ModuleSummaryIndexObjectFile::create(MemBuffer);
+    if (!ObjOrErr) {
+      LLVMRustSetLastError(toString(ObjOrErr.takeError()).c_str());
+      return nullptr;
+    }
+    auto Index = (*ObjOrErr)->takeIndex();
+    Ret->Index.mergeFrom(std::move(Index), I);
+#endif
+  }
+
+  // Collect for each module the list of functions it defines.
+  Ret->Index.collectDefinedGVSummariesPerModule(
+      Ret->ModuleToDefinedGVSummaries);
+
+  // The preserved symbols are needed as GUIDs for internalization.
+  for (unsigned I = 0; I < NumSymbols; I++) {
+    auto GUID = GlobalValue::getGUID(PreservedSymbols[I]);
+    Ret->GUIDPreservedSymbols.insert(GUID);
+  }
+
+  // Compute the import and export lists of all modules from the call graph in
+  // the combined index.
+#if LLVM_VERSION_GE(5, 0)
+  computeDeadSymbols(Ret->Index, Ret->GUIDPreservedSymbols);
+  ComputeCrossModuleImport(Ret->Index, Ret->ModuleToDefinedGVSummaries,
+                           Ret->ImportLists, Ret->ExportLists);
+#else
+  auto DeadSymbols = computeDeadSymbols(Ret->Index, Ret->GUIDPreservedSymbols);
+  ComputeCrossModuleImport(Ret->Index, Ret->ModuleToDefinedGVSummaries,
+                           Ret->ImportLists, Ret->ExportLists, &DeadSymbols);
+#endif
+
+  // Resolve linkonce and weak symbols to a single prevailing copy.
+  StringMap<std::map<GlobalValue::GUID, GlobalValue::LinkageTypes>> ResolvedODR;
+  DenseMap<GlobalValue::GUID, const GlobalValueSummary *> PrevailingCopy;
+  for (auto &I : Ret->Index) {
+#if LLVM_VERSION_GE(5, 0)
+    if (I.second.SummaryList.size() > 1)
+      PrevailingCopy[I.first] =
+          getFirstDefinitionForLinker(I.second.SummaryList);
+#else
+    if (I.second.size() > 1)
+      PrevailingCopy[I.first] = getFirstDefinitionForLinker(I.second);
+#endif
+  }
+  auto IsPrevailing = [&](GlobalValue::GUID GUID, const GlobalValueSummary *S) {
+    const auto &Prevailing = PrevailingCopy.find(GUID);
+    if (Prevailing == PrevailingCopy.end())
+      return true;
+    return Prevailing->second == S;
+  };
+  auto RecordNewLinkage = [&](StringRef ModuleIdentifier,
+                              GlobalValue::GUID GUID,
+                              GlobalValue::LinkageTypes NewLinkage) {
+    ResolvedODR[ModuleIdentifier][GUID] = NewLinkage;
+  };
+  thinLTOResolveWeakForLinkerInIndex(Ret->Index, IsPrevailing,
+                                     RecordNewLinkage);
+
+  // Everything that is live and not local stays exported, so internalization
+  // only affects what is dead. Exports between modules which importing
+  // introduces are on top of that.
+  std::set<GlobalValue::GUID> ExportedGUIDs;
+  for (auto &List : Ret->Index) {
+#if LLVM_VERSION_GE(5, 0)
+    for (auto &GVS : List.second.SummaryList) {
+#else
+    for (auto &GVS : List.second) {
+#endif
+      if (GlobalValue::isLocalLinkage(GVS->linkage()))
+        continue;
+      auto GUID = GVS->getOriginalName();
+#if LLVM_VERSION_GE(5, 0)
+      if (Ret->Index.isGUIDLive(GUID))
+#else
+      if (!DeadSymbols.count(GUID))
+#endif
+        ExportedGUIDs.insert(GUID);
+    }
+  }
+  auto IsExported = [&](StringRef ModuleIdentifier, GlobalValue::GUID GUID) {
+    const auto &ExportList = Ret->ExportLists.find(ModuleIdentifier);
+    return (ExportList != Ret->ExportLists.end() &&
+            ExportList->second.count(GUID)) ||
+           ExportedGUIDs.count(GUID);
+  };
+  thinLTOInternalizeAndPromoteInIndex(Ret->Index, IsExported);
+
+  return Ret.release();
+}
+
+extern "C" void LLVMRustFreeThinLTOData(LLVMRustThinLTOData *Data) {
+  delete Data;
+}
+
+// The per-module steps of ThinLTO, which rustc runs in this order on each
+// module, possibly on several threads at once. Each of them is copied from
+// `ProcessThinLTOModule` in `lib/LTO/ThinLTOCodeGenerator.cpp`.
+
+extern "C" bool LLVMRustPrepareThinLTORename(const LLVMRustThinLTOData *Data,
+                                             LLVMModuleRef M) {
+  Module &Mod = *unwrap(M);
+  if (renameModuleForThinLTO(Mod, Data->Index)) {
+    LLVMRustSetLastError("renameModuleForThinLTO failed");
+    return false;
+  }
+  return true;
+}
+
+extern "C" bool
+LLVMRustPrepareThinLTOResolveWeak(const LLVMRustThinLTOData *Data,
+                                  LLVMModuleRef M) {
+  Module &Mod = *unwrap(M);
+  const auto &DefinedGlobals =
+      Data->ModuleToDefinedGVSummaries.lookup(Mod.getModuleIdentifier());
+  thinLTOResolveWeakForLinkerModule(Mod, DefinedGlobals);
+  return true;
+}
+
+extern "C" bool
+LLVMRustPrepareThinLTOInternalize(const LLVMRustThinLTOData *Data,
+                                  LLVMModuleRef M) {
+  Module &Mod = *unwrap(M);
+  const auto &DefinedGlobals =
+      Data->ModuleToDefinedGVSummaries.lookup(Mod.getModuleIdentifier());
+  thinLTOInternalizeModule(Mod, DefinedGlobals);
+  return true;
+}
+
+extern "C" bool LLVMRustPrepareThinLTOImport(const LLVMRustThinLTOData *Data,
+                                             LLVMModuleRef M) {
+  Module &Mod = *unwrap(M);
+  const auto &ImportList = Data->ImportLists.lookup(Mod.getModuleIdentifier());
+  auto Loader = [&](StringRef Identifier) {
+    const auto &Memory = Data->ModuleMap.lookup(Identifier);
+    auto &Context = Mod.getContext();
+    return getLazyBitcodeModule(Memory, Context, true, true);
+  };
+  FunctionImporter Importer(Data->Index, Loader);
+  Expected<bool> Result = Importer.importFunctions(Mod, ImportList);
+  if (!Result) {
+    LLVMRustSetLastError(toString(Result.takeError()).c_str());
+    return false;
+  }
+  return true;
+}
+
+// A module serialized to bitcode together with its ThinLTO summary, which is
+// how the codegen units of rustc enter a ThinLTO session.
+struct LLVMRustThinLTOBuffer {
+  std::string data;
+};
+
+extern "C" LLVMRustThinLTOBuffer *LLVMRustThinLTOBufferCreate(LLVMModuleRef M) {
+  auto Ret = llvm::make_unique<LLVMRustThinLTOBuffer>();
+  {
+    raw_string_ostream OS(Ret->data);
+    {
+      legacy::PassManager PM;
+      PM.add(createWriteThinLTOBitcodePass(OS));
+      PM.run(*unwrap(M));
+    }
+  }
+  return Ret.release();
+}
+
+extern "C" void LLVMRustThinLTOBufferFree(LLVMRustThinLTOBuffer *Buffer) {
+  delete Buffer;
+}
+
+extern "C" const void *
+LLVMRustThinLTOBufferPtr(const LLVMRustThinLTOBuffer *Buffer) {
+  return Buffer->data.data();
+}
+
+extern "C" size_t
+LLVMRustThinLTOBufferLen(const LLVMRustThinLTOBuffer *Buffer) {
+  return Buffer->data.length();
+}
+
+// Parses the bitcode of a module taking part in ThinLTO into `Context`. This
+// is called once per module, concurrently on several threads.
+extern "C" LLVMModuleRef LLVMRustParseBitcodeForThinLTO(LLVMContextRef Context,
+                                                       const char *Data,
+                                                       size_t Len,
+                                                       const char *Identifier) {
+  StringRef Bitcode(Data, Len);
+  MemoryBufferRef Buffer(Bitcode, Identifier);
+  unwrap(Context)->enableDebugTypeODRUniquing();
+  Expected<std::unique_ptr<Module>> SrcOrError =
+      parseBitcodeFile(Buffer, *unwrap(Context));
+  if (!SrcOrError) {
+    LLVMRustSetLastError(toString(SrcOrError.takeError()).c_str());
+    return nullptr;
+  }
+  return wrap(std::move(*SrcOrError).release());
+}
+
+extern "C" bool LLVMRustThinLTOAvailable() { return true; }
+
+extern "C" void
+LLVMRustPassManagerBuilderPopulateThinLTOPassManager(LLVMPassManagerBuilderRef PMBR,
+                                                     LLVMPassManagerRef PMR) {
+  unwrap(PMBR)->populateThinLTOPassManager(*unwrap(PMR));
+}
+
+#else
+
+extern "C" bool LLVMRustThinLTOAvailable() { return false; }
+
+extern "C" void
+LLVMRustPassManagerBuilderPopulateThinLTOPassManager(LLVMPassManagerBuilderRef,
+                                                     LLVMPassManagerRef) {
+  report_fatal_error("ThinLTO not available");
+}
+
+struct LLVMRustThinLTOData {};
+struct LLVMRustThinLTOModule {};
+struct LLVMRustThinLTOBuffer {};
+
+extern "C" LLVMRustThinLTOData *
+LLVMRustCreateThinLTOData(LLVMRustThinLTOModule *, unsigned, const char **,
+                          unsigned) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" void LLVMRustFreeThinLTOData(LLVMRustThinLTOData *) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" bool LLVMRustPrepareThinLTORename(const LLVMRustThinLTOData *,
+                                             LLVMModuleRef) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" bool LLVMRustPrepareThinLTOResolveWeak(const LLVMRustThinLTOData *,
+                                                  LLVMModuleRef) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" bool LLVMRustPrepareThinLTOInternalize(const LLVMRustThinLTOData *,
+                                                  LLVMModuleRef) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" bool LLVMRustPrepareThinLTOImport(const LLVMRustThinLTOData *,
+                                             LLVMModuleRef) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" LLVMRustThinLTOBuffer *LLVMRustThinLTOBufferCreate(LLVMModuleRef) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" void LLVMRustThinLTOBufferFree(LLVMRustThinLTOBuffer *) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" const void *LLVMRustThinLTOBufferPtr(const LLVMRustThinLTOBuffer *) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" size_t LLVMRustThinLTOBufferLen(const LLVMRustThinLTOBuffer *) {
+  report_fatal_error("ThinLTO not available");
+}
+
+extern "C" LLVMModuleRef LLVMRustParseBitcodeForThinLTO(LLVMContextRef,
+                                                       const char *, size_t,
+                                                       const char *) {
+  report_fatal_error("ThinLTO not available");
+}
+
+#endif // LLVM_VERSION_GE(4, 0)
@@ -0,0 +1,75 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z borrowck-mir
+
+// Test that the MIR borrow checker reports the errors of the AST one
+// alongside them, with a `(Mir)` suffix.
+
+fn mutable_twice() {
+    let mut x = 0;
+    let r = &mut x;
+    let s = &mut x; //~ ERROR cannot borrow `x` as mutable more than once at a time (Mir)
+    //~^ ERROR cannot borrow `x` as mutable more than once at a time
+    *r += 1;
+    *s += 1;
+}
+
+fn mutable_and_shared() {
+    let mut x = 0;
+    let r = &mut x;
+    let s = &x;
+    //~^ ERROR cannot borrow `x` as immutable because it is also borrowed as mutable (Mir)
+    //~| ERROR cannot borrow `x` as immutable because it is also borrowed as mutable
+    *r += *s;
+}
+
+fn assign_borrowed() {
+    let mut x = 0;
+    let r = &x;
+    x = 1; //~ ERROR cannot assign to `x` because it is borrowed (Mir)
+    //~^ ERROR cannot assign to `x` because it is borrowed
+    drop(r);
+}
+
+fn move_borrowed() {
+    let x = String::new();
+    let r = &x;
+    let y = x; //~ ERROR cannot move out of `x` because it is borrowed (Mir)
+    //~^ ERROR cannot move out of `x` because it is borrowed
+    drop(r);
+    drop(y);
+}
+
+fn use_after_move() {
+    let x = String::new();
+    drop(x);
+    drop(x); //~ ERROR use of moved value: `x` (Mir)
+    //~^ ERROR use of moved value: `x`
+}
+
+fn dangling() {
+    let r;
+    {
+        let x = 0;
+        r = &x; //~ ERROR `x` does not live long enough (Mir)
+        //~^ ERROR `x` does not live long enough
+    }
+    drop(r);
+}
+
+fn main() {
+    mutable_twice();
+    mutable_and_shared();
+    assign_borrowed();
+    move_borrowed();
+    use_after_move();
+    dangling();
+}
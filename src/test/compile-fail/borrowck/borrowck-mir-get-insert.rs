@@ -0,0 +1,33 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test that the MIR borrow checker does not consider the borrow of `map`
+// live in the `else` branch, where the AST one reports it. Only the AST
+// error is expected, without a `(Mir)` one.
+
+#![feature(rustc_attrs)]
+
+use std::collections::HashMap;
+
+#[rustc_mir_borrowck]
+fn len_or_insert(map: &mut HashMap<u32, String>, key: u32) -> usize {
+    if let Some(value) = map.get(&key) {
+        value.len()
+    } else {
+        map.insert(key, String::new());
+        //~^ ERROR cannot borrow `*map` as mutable because it is also borrowed as immutable
+        0
+    }
+}
+
+fn main() {
+    let mut map = HashMap::new();
+    len_or_insert(&mut map, 1);
+}
@@ -0,0 +1,44 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Zforce-overflow-checks=on
+
+// Check that errors are reported for the values known through variables.
+
+#![deny(const_err)]
+
+fn black_box<T>(_: T) {
+    unimplemented!()
+}
+
+fn main() {
+    let a = 255u8;
+    let b = a + 1;
+    //~^ ERROR attempt to add with overflow
+    let c = [1, 2, 3];
+    let d = c[5];
+    //~^ ERROR index out of bounds: the len is 3 but the index is 5
+    let e = 0u32;
+    let f = 1 / e;
+    //~^ ERROR attempt to divide by zero
+
+    // Not reported, as this is never reached.
+    let g = false;
+    if g {
+        black_box(a * 2);
+    }
+
+    // Not reported, as `h` may have been changed.
+    let mut h = 255u8;
+    black_box(&mut h);
+    black_box(h + 1);
+
+    black_box((b, d, f));
+}
@@ -0,0 +1,44 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -C overflow-checks=off
+
+// Check that overflows known through variables are reported without
+// overflow checks, when the arithmetic has no `Assert`.
+
+#![deny(const_err)]
+
+fn black_box<T>(_: T) {
+    unimplemented!()
+}
+
+fn main() {
+    let a = 255u8;
+    let b = a + 1;
+    //~^ ERROR attempt to add with overflow
+    let c = 0u8;
+    let d = c - 1;
+    //~^ ERROR attempt to subtract with overflow
+    let e = 8u32;
+    let f = 1u8 << e;
+    //~^ ERROR attempt to shift left with overflow
+
+    // Reported once, by the check which division always has.
+    let h = -1i8;
+    let i = -128i8 / h;
+    //~^ ERROR attempt to divide with overflow
+
+    // Not reported, as `g` may have been changed.
+    let mut g = 255u8;
+    black_box(&mut g);
+    black_box(g + 1);
+
+    black_box((b, d, f, i));
+}
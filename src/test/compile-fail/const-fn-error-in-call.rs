@@ -0,0 +1,30 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test that errors inside of nested const fn calls are reported with the
+// calls they happened in.
+
+#![feature(const_fn)]
+
+const fn div(a: usize, b: usize) -> usize {
+    a / b
+    //~^ ERROR constant evaluation error
+    //~| attempt to divide by zero
+}
+
+const fn average(total: usize, count: usize) -> usize {
+    div(total, count) //~ NOTE inside call to `div`
+}
+
+fn main() {
+    let _: [u8; average(10, 0)];
+    //~^ NOTE for array length here
+    //~| NOTE inside call to `average`
+}
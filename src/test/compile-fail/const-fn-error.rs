@@ -8,20 +8,22 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-#![feature(const_fn)]
+#![feature(const_fn, const_fn_control_flow)]
 
 const X : usize = 2;
 
 const fn f(x: usize) -> usize {
     let mut sum = 0;
     for i in 0..x {
+    //~^ ERROR E0015
+    //~| ERROR E0015
+    //~| ERROR E0017
         sum += i;
     }
-    sum //~ ERROR E0080
-        //~| non-constant path in constant
+    sum
 }
 
 #[allow(unused_variables)]
 fn main() {
-    let a : [i32; f(X)]; //~ NOTE for array length here
+    let a : [i32; f(X)];
 }
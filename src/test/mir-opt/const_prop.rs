@@ -0,0 +1,44 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -O
+
+fn test() -> bool {
+    let x = 4u8;
+    x == 4
+}
+
+fn main() { }
+
+// END RUST SOURCE
+// START rustc.node4.ConstProp.before.mir
+//  bb0: {
+//      _1 = const 4u8;
+//      _2 = _1;
+//      _0 = Eq(_2, const 4u8);
+//      return;
+//  }
+// END rustc.node4.ConstProp.before.mir
+// START rustc.node4.ConstProp.after.mir
+//  bb0: {
+//      _1 = const 4u8;
+//      _2 = const 4u8;
+//      _0 = const true;
+//      return;
+//  }
+// END rustc.node4.ConstProp.after.mir
+// START rustc.node4.RemoveDeadStores.after.mir
+//  bb0: {
+//      _1 = const 4u8;
+//      nop;
+//      _0 = const true;
+//      return;
+//  }
+// END rustc.node4.RemoveDeadStores.after.mir
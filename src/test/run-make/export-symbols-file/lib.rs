@@ -0,0 +1,28 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "cdylib"]
+
+#[no_mangle]
+pub extern "C" fn api_version() -> u32 {
+    1
+}
+
+#[no_mangle]
+pub extern "C" fn api_call(x: u32) -> u32 {
+    internal_helper(x) + 1
+}
+
+// Public so that other C code of the project can call it, but not part of the
+// library's interface.
+#[no_mangle]
+pub extern "C" fn internal_helper(x: u32) -> u32 {
+    x * 2
+}
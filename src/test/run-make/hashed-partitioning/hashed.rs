@@ -0,0 +1,25 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "lib"]
+
+pub mod generic {
+    pub fn id<T>(x: T) -> T {
+        x
+    }
+
+    pub fn pair<T: Copy>(x: T) -> (T, T) {
+        (x, x)
+    }
+}
+
+pub fn user() -> (u32, (u8, u8)) {
+    (generic::id(0), generic::pair(1))
+}
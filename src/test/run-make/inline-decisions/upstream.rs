@@ -0,0 +1,41 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "rlib"]
+
+#[inline]
+pub fn ping(n: u32) -> u32 {
+    if n == 0 { 0 } else { pong(n - 1) }
+}
+
+#[inline]
+pub fn pong(n: u32) -> u32 {
+    if n == 0 { 1 } else { ping(n - 1) }
+}
+
+pub trait Double {
+    fn double(&self) -> Self;
+}
+
+impl Double for u32 {
+    #[inline]
+    fn double(&self) -> u32 {
+        self.wrapping_mul(2)
+    }
+}
+
+pub fn double_all<T: Double>(x: T) -> T {
+    x.double()
+}
+
+#[inline(never)]
+pub fn never<T>(x: T) -> T {
+    x
+}
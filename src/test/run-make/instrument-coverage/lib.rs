@@ -0,0 +1,28 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "lib"]
+
+pub fn add_one(x: u32) -> u32 {
+    if x == u32::max_value() {
+        x
+    } else {
+        x + 1
+    }
+}
+
+pub fn never_called() -> u32 {
+    42
+}
+
+#[test]
+fn test_add_one() {
+    assert_eq!(add_one(1), 2);
+}
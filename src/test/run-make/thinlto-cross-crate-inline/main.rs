@@ -0,0 +1,21 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+
+extern crate upstream;
+
+#[no_mangle]
+pub fn call_frobnicate(x: u32) -> u32 {
+    upstream::frobnicate(x)
+}
+
+fn main() {
+    assert_eq!(call_frobnicate(1), 31 ^ 0x5a5a);
+}
@@ -0,0 +1,82 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z borrowck-mir
+
+// Test that the MIR borrow checker accepts code that the AST one does.
+
+use std::collections::HashMap;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn disjoint_fields(p: &mut Point) {
+    let x = &mut p.x;
+    let y = &mut p.y;
+    *x += 1;
+    *y += 1;
+}
+
+fn reborrow(v: &mut Vec<i32>) -> &mut i32 {
+    v.push(1);
+    let r = &mut *v;
+    &mut r[0]
+}
+
+fn loops(v: &mut Vec<i32>) -> i32 {
+    let mut sum = 0;
+    for x in v.iter() {
+        sum += *x;
+    }
+    for x in v.iter_mut() {
+        *x += sum;
+    }
+    while let Some(x) = v.pop() {
+        sum += x;
+    }
+    sum
+}
+
+fn lookup(map: &mut HashMap<u32, String>, key: u32) -> usize {
+    let len = match map.get(&key) {
+        Some(value) => value.len(),
+        None => 0,
+    };
+    map.insert(key, String::new());
+    len
+}
+
+fn closures() -> i32 {
+    let mut x = 1;
+    {
+        let mut add = |n| x += n;
+        add(2);
+        add(3);
+    }
+    let get = || x;
+    get()
+}
+
+fn main() {
+    let mut p = Point { x: 1, y: 2 };
+    disjoint_fields(&mut p);
+    assert_eq!((p.x, p.y), (2, 3));
+
+    let mut v = vec![];
+    *reborrow(&mut v) += 1;
+    assert_eq!(v, [2]);
+    assert_eq!(loops(&mut v), 6);
+
+    let mut map = HashMap::new();
+    assert_eq!(lookup(&mut map, 1), 0);
+    assert_eq!(closures(), 6);
+}
@@ -0,0 +1,99 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Test const fns with local variables, branches and loops, which are
+// evaluated on their MIR.
+
+#![feature(const_fn, const_fn_control_flow)]
+
+#[derive(Copy, Clone)]
+enum Shape {
+    Square(u32),
+    Rect { w: u32, h: u32 },
+    Empty,
+}
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+const fn fib(n: usize) -> usize {
+    let mut a = 0;
+    let mut b = 1;
+    let mut i = 0;
+    while i < n {
+        let t = a + b;
+        a = b;
+        b = t;
+        i += 1;
+    }
+    a
+}
+
+const fn gcd(mut a: u32, mut b: u32) -> u32 {
+    loop {
+        if b == 0 {
+            return a;
+        }
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+}
+
+const fn area(shape: Shape) -> u32 {
+    match shape {
+        Shape::Square(side) => side * side,
+        Shape::Rect { w, h } => w * h,
+        Shape::Empty => 0,
+    }
+}
+
+const fn mirror(p: Point) -> Point {
+    let mut q = Point { x: 0, y: 0 };
+    q.x = p.y;
+    q.y = p.x;
+    q
+}
+
+const fn sum(xs: [u32; 4]) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < 4 {
+        total += xs[i];
+        i += 1;
+    }
+    total
+}
+
+const FIB: usize = fib(10);
+const GCD: u32 = gcd(84, 36);
+const AREAS: [u32; 3] = [area(Shape::Square(3)), area(Shape::Rect { w: 2, h: 5 }),
+                         area(Shape::Empty)];
+const MIRRORED: Point = mirror(Point { x: 1, y: 2 });
+const SUM: u32 = sum([1, 2, 3, 4]);
+static STATIC_FIB: usize = fib(12);
+
+fn main() {
+    let buf = [0u8; fib(6)];
+    assert_eq!(buf.len(), 8);
+    assert_eq!(FIB, 55);
+    assert_eq!(STATIC_FIB, 144);
+    assert_eq!(GCD, 12);
+    assert_eq!(AREAS, [9, 10, 0]);
+    assert_eq!((MIRRORED.x, MIRRORED.y), (2, 1));
+    assert_eq!(SUM, 10);
+
+    // The same functions called at runtime.
+    assert_eq!(fib(10), FIB);
+    assert_eq!(gcd(84, 36), GCD);
+    assert_eq!(area(Shape::Rect { w: 2, h: 5 }), 10);
+}
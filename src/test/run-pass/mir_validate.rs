@@ -0,0 +1,60 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z validate-mir -Z mir-opt-level=3
+
+// Check that the MIR passes leave the MIR well-formed, including when
+// inlining, propagating constants and removing dead stores.
+
+struct Counter {
+    count: u32,
+}
+
+impl Drop for Counter {
+    fn drop(&mut self) {
+        assert!(self.count > 0);
+    }
+}
+
+#[inline]
+fn add_all<I: Iterator<Item = u32>>(iter: I) -> u32 {
+    let mut sum = 0;
+    for x in iter {
+        sum += x;
+    }
+    sum
+}
+
+#[inline]
+fn pick(x: Option<u32>, default: u32) -> u32 {
+    match x {
+        Some(x) if x > 10 => x - 10,
+        Some(x) => x,
+        None => default,
+    }
+}
+
+fn main() {
+    let limit = 4;
+    let mut counter = Counter { count: 0 };
+    for i in 0..limit {
+        counter.count += i + 1;
+    }
+    assert_eq!(counter.count, 10);
+
+    let values = vec![1, 2, 3];
+    assert_eq!(add_all(values.into_iter()), 6);
+    assert_eq!(add_all((1..5).map(|x| x * 2)), 20);
+
+    let a = [1u8, 2, 3];
+    let index = 2;
+    assert_eq!(a[index], 3);
+    assert_eq!(pick(Some(15), 0) + pick(None, 7), 12);
+}
@@ -0,0 +1,190 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks that the erroneous examples of the error index still trigger the
+//! error code they document, and no other error code.
+//!
+//! `rustdoc --test` only checks that a `compile_fail,EXXXX` example fails
+//! with `EXXXX` among its errors; here the example must fail with that code
+//! alone, so that the explanations don't drift from what the compiler does.
+//! The errors in `FOLLOW_UP_ERRORS` are exempt from the "alone".
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+use syntax::diagnostics::metadata::ErrorMetadataMap;
+
+use rustc_serialize::json::Json;
+
+/// The errors whose erroneous examples also emit other errors that follow
+/// from the documented one, such as a missing trait item or a duplicate
+/// definition in an example that shows several forms of a mistake. Their
+/// examples only have to emit their code among others, as with
+/// `rustdoc --test`. Keep this sorted.
+const FOLLOW_UP_ERRORS: &'static [&'static str] = &[
+    "E0025", // E0027
+    "E0059", // E0277
+    "E0067", // E0368
+    "E0070", // E0423
+    "E0091", // E0428
+    "E0106", // E0428
+    "E0117", // E0120
+    "E0199", // E0119
+    "E0200", // E0119
+    "E0201", // E0592
+    "E0220", // E0191
+    "E0229", // E0277
+    "E0264", // E0522
+    "E0323", // E0046
+    "E0324", // E0046
+    "E0325", // E0046
+    "E0374", // E0392
+    "E0375", // E0277
+    "E0408", // E0425
+    "E0409", // E0308
+    "E0425", // E0423, E0433
+    "E0468", // E0469
+    "E0517", // E0390, E0428
+];
+
+/// Compiles each erroneous example in `err_map` with `rustc`, writing the
+/// examples and their outputs to `tmpdir`.
+pub fn check_examples(err_map: &ErrorMetadataMap,
+                      rustc: &Path,
+                      tmpdir: &Path) -> Result<(), Box<Error>> {
+    let mut checked = 0;
+    let mut failures = vec![];
+
+    for (err_code, info) in err_map {
+        let description = match info.description {
+            Some(ref description) => description,
+            None => continue,
+        };
+
+        for (i, (expected, example)) in erroneous_examples(description).into_iter().enumerate() {
+            let path = tmpdir.join(format!("{}-{}.rs", err_code, i));
+            File::create(&path)?.write_all(example.as_bytes())?;
+
+            let output = Command::new(rustc).arg("--error-format=json")
+                                            .arg("--out-dir").arg(tmpdir)
+                                            .arg(&path)
+                                            .output()?;
+            let emitted = emitted_codes(&String::from_utf8_lossy(&output.stderr));
+            checked += 1;
+
+            if output.status.success() {
+                failures.push(format!("{}: example {} compiles without errors",
+                                      err_code, i));
+            } else if !emitted.contains(&expected) {
+                let emitted = emitted.into_iter().collect::<Vec<_>>().join(", ");
+                failures.push(format!("{}: example {} should emit {}, but emits [{}]",
+                                      err_code, i, expected, emitted));
+            } else if emitted.len() != 1 && !FOLLOW_UP_ERRORS.contains(&&err_code[..]) {
+                let emitted = emitted.into_iter().collect::<Vec<_>>().join(", ");
+                failures.push(format!("{}: example {} should only emit {}, but emits [{}] \
+                                       (see `FOLLOW_UP_ERRORS`)",
+                                      err_code, i, expected, emitted));
+            }
+        }
+    }
+
+    println!("checked {} erroneous examples of the error index", checked);
+    if failures.is_empty() {
+        return Ok(());
+    }
+    for failure in &failures {
+        println!("    {}", failure);
+    }
+    Err(From::from(format!("{} erroneous examples don't match their error code",
+                           failures.len())))
+}
+
+/// Extracts the `compile_fail,EXXXX` code blocks of an explanation, together
+/// with their expected error code, prepared the way `rustdoc --test` would.
+fn erroneous_examples(description: &str) -> Vec<(String, String)> {
+    let mut examples = vec![];
+    let mut current: Option<(Option<String>, Vec<&str>)> = None;
+
+    for line in description.lines() {
+        let trimmed = line.trim();
+        if !trimmed.starts_with("```") {
+            if let Some((_, ref mut lines)) = current {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        match current.take() {
+            Some((Some(code), lines)) => examples.push((code, make_test(&lines))),
+            Some((None, _)) => {}
+            None => {
+                let tags: Vec<_> = trimmed[3..].split(',').map(|t| t.trim()).collect();
+                let erroneous = tags.contains(&"compile_fail") && !tags.contains(&"ignore");
+                let code = tags.iter().find(|t| t.starts_with("E") && t.len() == 5);
+                let expected = match code {
+                    Some(code) if erroneous => Some(code.to_string()),
+                    _ => None,
+                };
+                current = Some((expected, vec![]));
+            }
+        }
+    }
+
+    examples
+}
+
+/// Unhides the `# ` lines of an example and wraps it in a `main` function
+/// unless it has one, keeping crate attributes at the top.
+fn make_test(lines: &[&str]) -> String {
+    let lines: Vec<&str> = lines.iter().map(|line| {
+        let trimmed = line.trim_left();
+        if trimmed == "#" {
+            ""
+        } else if trimmed.starts_with("# ") {
+            &trimmed[2..]
+        } else {
+            *line
+        }
+    }).collect();
+
+    let (attrs, body): (Vec<&str>, Vec<&str>) = lines.into_iter().partition(|line| {
+        line.trim_left().starts_with("#![")
+    });
+    let body = body.join("\n");
+
+    let mut test = String::from("#![allow(unused)]\n");
+    for attr in attrs {
+        test.push_str(attr);
+        test.push('\n');
+    }
+    if body.contains("fn main") {
+        test.push_str(&body);
+    } else {
+        test.push_str("fn main() {\n");
+        test.push_str(&body);
+        test.push_str("\n}");
+    }
+    test.push('\n');
+    test
+}
+
+/// The codes of the errors in the JSON diagnostics printed by rustc.
+fn emitted_codes(stderr: &str) -> BTreeSet<String> {
+    stderr.lines().filter_map(|line| Json::from_str(line).ok()).filter_map(|diagnostic| {
+        if diagnostic.find("level").and_then(|l| l.as_string()) != Some("error") {
+            return None;
+        }
+        diagnostic.find_path(&["code", "code"]).and_then(|c| c.as_string()).map(String::from)
+    }).collect()
+}
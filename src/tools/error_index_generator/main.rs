@@ -27,6 +27,8 @@ use syntax::diagnostics::metadata::{get_metadata_dir, ErrorMetadataMap, ErrorMet
 use rustdoc::html::markdown::{Markdown, PLAYGROUND, RenderType};
 use rustc_serialize::json;
 
+mod check;
+
 enum OutputFormat {
     HTML(HTMLFormatter),
     Markdown(MarkdownFormatter),
@@ -186,6 +188,15 @@ fn main_with_result(format: OutputFormat, dst: &Path) -> Result<(), Box<Error>>
     Ok(())
 }
 
+/// Check the erroneous examples of the errors against `$RUSTC`.
+fn check_with_result(tmpdir: &Path) -> Result<(), Box<Error>> {
+    let build_arch = env::var("CFG_BUILD")?;
+    let rustc = env::var("RUSTC")?;
+    let metadata_dir = get_metadata_dir(&build_arch);
+    let err_map = load_all_errors(&metadata_dir)?;
+    check::check_examples(&err_map, Path::new(&rustc), tmpdir)
+}
+
 fn parse_args() -> (OutputFormat, PathBuf) {
     let mut args = env::args().skip(1);
     let format = args.next().map(|a| OutputFormat::from(&a))
@@ -204,6 +215,13 @@ fn main() {
     PLAYGROUND.with(|slot| {
         *slot.borrow_mut() = Some((None, String::from("https://play.rust-lang.org/")));
     });
+    if env::args().nth(1).map_or(false, |a| a == "check") {
+        let tmpdir = env::args().nth(2).map(PathBuf::from).unwrap_or_else(env::temp_dir);
+        if let Err(e) = check_with_result(&tmpdir) {
+            panic!("{}", e.description());
+        }
+        return;
+    }
     let (format, dst) = parse_args();
     if let Err(e) = main_with_result(format, &dst) {
         panic!("{}", e.description());